@@ -0,0 +1,259 @@
+use core::cell::{Cell, Ref, RefCell, RefMut};
+
+use defmt::warn;
+use embassy_time::Duration;
+use gas_index_algorithm::{AlgorithmType, GasIndexAlgorithm};
+
+/// Sampling interval the Sensirion gas index algorithm is tuned for.
+pub const RECOMMENDED_INTERVAL: Duration = Duration::from_secs(1);
+/// Intervals outside this window get a warning: the algorithm accepts them,
+/// but its tuning (learning times, gating) assumes roughly 1 Hz sampling.
+pub const MIN_INTERVAL: Duration = Duration::from_millis(500);
+pub const MAX_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Sensirion gas index tuning parameters (see `set_tuning_parameters`).
+#[derive(Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub struct GasIndexTuning {
+    /// Index reported for the learned "typical" air (VOC 100, NOx 1).
+    pub index_offset: i32,
+    /// Time constant (h) for the baseline offset to adapt. Longer values keep
+    /// the baseline from drifting toward elevated-but-constant levels in a
+    /// stable environment; shorter values re-baseline faster after moving.
+    pub learning_time_offset_hours: i32,
+    /// Time constant (h) for the gain (spread) estimate to adapt. VOC only;
+    /// ignored by the NOx algorithm.
+    pub learning_time_gain_hours: i32,
+    /// Maximum time (min) the baseline is frozen during a high event, so a
+    /// long event doesn't get learned as the new normal.
+    pub gating_max_duration_minutes: i32,
+    /// Initial standard deviation estimate. VOC only; ignored for NOx.
+    pub std_initial: i32,
+    /// Gain applied to the index. Higher values spread readings further from the offset.
+    pub gain_factor: i32,
+}
+
+impl GasIndexTuning {
+    /// Sensirion defaults for the VOC algorithm.
+    pub const VOC_DEFAULT: Self = Self {
+        index_offset: 100,
+        learning_time_offset_hours: 12,
+        learning_time_gain_hours: 12,
+        gating_max_duration_minutes: 180,
+        std_initial: 50,
+        gain_factor: 230,
+    };
+
+    /// Sensirion defaults for the NOx algorithm.
+    pub const NOX_DEFAULT: Self = Self {
+        index_offset: 1,
+        learning_time_offset_hours: 12,
+        learning_time_gain_hours: 12,
+        gating_max_duration_minutes: 720,
+        std_initial: 50,
+        gain_factor: 230,
+    };
+
+    fn apply(&self, algo: &mut GasIndexAlgorithm) {
+        algo.set_tuning_parameters(
+            self.index_offset,
+            self.learning_time_offset_hours,
+            self.learning_time_gain_hours,
+            self.gating_max_duration_minutes,
+            self.std_initial,
+            self.gain_factor,
+        );
+    }
+}
+
+/// Independent tuning for the VOC and NOx algorithms.
+#[derive(Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub struct GasIndexConfig {
+    pub voc: GasIndexTuning,
+    pub nox: GasIndexTuning,
+}
+
+impl Default for GasIndexConfig {
+    fn default() -> Self {
+        Self {
+            voc: GasIndexTuning::VOC_DEFAULT,
+            nox: GasIndexTuning::NOX_DEFAULT,
+        }
+    }
+}
+
+/// VOC algorithm with Sensirion default tuning, sampling every `interval`.
+///
+/// `interval` must be the measurement loop's period
+/// (`SensorConfig::measurement_interval`): the algorithm's learning and gating
+/// times are counted in samples, so a mismatch silently skews the index.
+/// Changing one means changing the other.
+pub fn new_voc_algo(interval: Duration) -> GasIndexAlgorithm {
+    new_algo(AlgorithmType::Voc, interval, &GasIndexTuning::VOC_DEFAULT)
+}
+
+/// NOx counterpart of `new_voc_algo`; the same interval rule applies.
+pub fn new_nox_algo(interval: Duration) -> GasIndexAlgorithm {
+    new_algo(AlgorithmType::Nox, interval, &GasIndexTuning::NOX_DEFAULT)
+}
+
+/// Build the VOC and NOx algorithms for a measurement loop running every
+/// `interval`. Pass the same `interval` to the measurement task so the
+/// algorithm's sampling assumption can't drift from the real cadence.
+pub fn build_algorithms(
+    interval: Duration,
+    config: &GasIndexConfig,
+) -> (GasIndexAlgorithm, GasIndexAlgorithm) {
+    (
+        new_algo(AlgorithmType::Voc, interval, &config.voc),
+        new_algo(AlgorithmType::Nox, interval, &config.nox),
+    )
+}
+
+fn new_algo(kind: AlgorithmType, interval: Duration, tuning: &GasIndexTuning) -> GasIndexAlgorithm {
+    if interval < MIN_INTERVAL || interval > MAX_INTERVAL {
+        warn!(
+            "Measurement interval {} ms is far from the recommended {} ms; gas index tuning may be off",
+            interval.as_millis(),
+            RECOMMENDED_INTERVAL.as_millis()
+        );
+    }
+    let sampling_interval = interval.as_micros() as f32 / 1_000_000.0;
+    let mut algo = GasIndexAlgorithm::new(kind, sampling_interval);
+    tuning.apply(&mut algo);
+    algo
+}
+
+/// The algorithm was borrowed elsewhere (e.g. mid-sample) when a reset or
+/// retune was requested; nothing was changed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, defmt::Format)]
+pub struct AlgorithmBusy;
+
+/// A gas index algorithm shared between tasks, plus the tuning it runs with.
+///
+/// The crate can't report its tuning back, so the wrapper keeps the last
+/// parameters it applied. Every access goes through `try_borrow*`, so a
+/// reset from a control command while another task holds the algorithm is
+/// refused rather than panicking. All users must run on the same executor.
+pub struct GasIndex {
+    algo: RefCell<GasIndexAlgorithm>,
+    tuning: Cell<GasIndexTuning>,
+}
+
+impl GasIndex {
+    /// Algorithm of `kind` sampling every `interval` (see `new_voc_algo`).
+    pub fn new(kind: AlgorithmType, interval: Duration, tuning: GasIndexTuning) -> Self {
+        Self {
+            algo: RefCell::new(new_algo(kind, interval, &tuning)),
+            tuning: Cell::new(tuning),
+        }
+    }
+
+    /// Forget the learned baseline, e.g. after moving the sensor. Tuning
+    /// parameters are kept.
+    pub fn reset(&self) -> Result<(), AlgorithmBusy> {
+        self.algo.try_borrow_mut().map_err(|_| AlgorithmBusy)?.reset();
+        Ok(())
+    }
+
+    pub fn get_tuning_parameters(&self) -> GasIndexTuning {
+        self.tuning.get()
+    }
+
+    pub fn set_tuning_parameters(&self, tuning: GasIndexTuning) -> Result<(), AlgorithmBusy> {
+        tuning.apply(&mut self.algo.try_borrow_mut().map_err(|_| AlgorithmBusy)?);
+        self.tuning.set(tuning);
+        Ok(())
+    }
+
+    pub fn try_borrow(&self) -> Result<Ref<'_, GasIndexAlgorithm>, AlgorithmBusy> {
+        self.algo.try_borrow().map_err(|_| AlgorithmBusy)
+    }
+
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, GasIndexAlgorithm>, AlgorithmBusy> {
+        self.algo.try_borrow_mut().map_err(|_| AlgorithmBusy)
+    }
+}
+
+/// Anything that turns raw ticks into an index. Lets the sampling path be
+/// driven by a stand-in processor in tests.
+pub trait IndexProcessor {
+    fn process(&mut self, sraw: i32) -> i32;
+}
+
+impl IndexProcessor for GasIndexAlgorithm {
+    fn process(&mut self, sraw: i32) -> i32 {
+        GasIndexAlgorithm::process(self, sraw)
+    }
+}
+
+/// Coarse alternative to the gas index algorithm for minimal builds: maps raw
+/// ticks straight to a 1–5 level through fixed, user-supplied thresholds, with
+/// no learning, no warm-up and almost no RAM.
+///
+/// This is far less accurate than the real algorithm. Raw ticks differ from
+/// sensor to sensor and drift with temperature, humidity and age, and nothing
+/// here adapts to them, so the thresholds must be picked per deployment and
+/// the output is only a rough indicator. The result is a level (1 = cleanest,
+/// 5 = worst), not a VOC index, and must not be mixed with real indices.
+///
+/// SGP41 raw ticks fall as the gas concentration rises, so `thresholds` are
+/// given in descending order: a reading at or above `thresholds[0]` is level 1,
+/// at or above `thresholds[1]` level 2, and so on; anything below
+/// `thresholds[3]` is level 5.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, defmt::Format)]
+pub struct RawThresholdMapper {
+    thresholds: [u16; 4],
+}
+
+impl RawThresholdMapper {
+    pub fn new(thresholds: [u16; 4]) -> Self {
+        debug_assert!(thresholds.windows(2).all(|w| w[0] >= w[1]));
+        Self { thresholds }
+    }
+}
+
+impl IndexProcessor for RawThresholdMapper {
+    fn process(&mut self, sraw: i32) -> i32 {
+        let level = self
+            .thresholds
+            .iter()
+            .position(|&t| sraw >= t as i32)
+            .unwrap_or(self.thresholds.len());
+        level as i32 + 1
+    }
+}
+
+/// Size of an exported algorithm state: the two internal state values
+/// (`get_states`: mean and standard deviation estimate) as big-endian `f32`s.
+pub const STATE_LEN: usize = 8;
+
+/// Snapshot the algorithm's internal state, e.g. to log it or seed another device.
+pub fn export_state(algo: &GasIndexAlgorithm) -> [u8; STATE_LEN] {
+    let (state0, state1) = algo.get_states();
+    let mut out = [0u8; STATE_LEN];
+    out[0..4].copy_from_slice(&state0.to_be_bytes());
+    out[4..8].copy_from_slice(&state1.to_be_bytes());
+    out
+}
+
+/// Restore a state produced by `export_state` (via `set_states`).
+pub fn import_state(algo: &mut GasIndexAlgorithm, state: &[u8; STATE_LEN]) {
+    let state0 = f32::from_be_bytes([state[0], state[1], state[2], state[3]]);
+    let state1 = f32::from_be_bytes([state[4], state[5], state[6], state[7]]);
+    algo.set_states(state0, state1);
+}
+
+/// Upper-case hex encoding of an exported state, for logging over RTT.
+///
+/// Export format: 16 hex digits, `state0` then `state1`, each a big-endian
+/// IEEE-754 `f32`. To re-import, parse the digits back into 8 bytes and send
+/// `ControlCommand::ImportAlgorithmState` with the same `Gas`.
+pub fn state_to_hex(state: &[u8; STATE_LEN]) -> [u8; 2 * STATE_LEN] {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let mut out = [0u8; 2 * STATE_LEN];
+    for (i, byte) in state.iter().enumerate() {
+        out[2 * i] = HEX[(byte >> 4) as usize];
+        out[2 * i + 1] = HEX[(byte & 0x0F) as usize];
+    }
+    out
+}
@@ -0,0 +1,94 @@
+//! Indices relative to a slow rolling baseline ("normal for this location"),
+//! for event detection rather than absolute levels. Unrelated to the gas
+//! index algorithm's own baseline, which keeps adapting underneath.
+//!
+//! The window (24 h by default) is split into `BASELINE_BUCKETS` buckets.
+//! Each bucket keeps a `RunningStats` mean, and the baseline is the mean of
+//! the completed buckets, so memory stays constant whatever the window.
+//!
+//! Needs long uptime to mean anything. There is no delta until the first
+//! bucket completes (window / 24, 1 h by default), and the baseline only
+//! covers a full day-night cycle after a whole window. The buckets live in
+//! RAM and are not part of the persisted algorithm state, so every reboot
+//! starts over even when the algorithm state is restored.
+
+use defmt::Format;
+use embassy_time::{Duration, Instant};
+
+use crate::stats::RunningStats;
+
+pub const BASELINE_BUCKETS: usize = 24;
+
+pub const DEFAULT_BASELINE_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Current indices minus the rolling baseline; positive = worse than usual.
+/// Published as `readings::Measurement::baseline_delta`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BaselineDelta {
+    pub voc: i32,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "crate::readings::nox_hidden"))]
+    pub nox: i32,
+}
+
+pub struct RollingBaseline {
+    bucket_len: Duration,
+    bucket_started: Instant,
+    voc: RunningStats,
+    nox: RunningStats,
+    // Ring of completed bucket means (VOC, NOx).
+    means: [(f32, f32); BASELINE_BUCKETS],
+    filled: usize,
+    next: usize,
+}
+
+impl RollingBaseline {
+    pub fn new(window: Duration, now: Instant) -> Self {
+        Self {
+            bucket_len: window / BASELINE_BUCKETS as u32,
+            bucket_started: now,
+            voc: RunningStats::new(),
+            nox: RunningStats::new(),
+            means: [(0.0, 0.0); BASELINE_BUCKETS],
+            filled: 0,
+            next: 0,
+        }
+    }
+
+    /// Add a sample and return its delta from the baseline, or `None` until
+    /// the first bucket completes. Warm-up samples (VOC index 0) are skipped.
+    pub fn update(&mut self, voc_index: i32, nox_index: i32, now: Instant) -> Option<BaselineDelta> {
+        if voc_index == 0 {
+            return None;
+        }
+        if now.saturating_duration_since(self.bucket_started) >= self.bucket_len {
+            if self.voc.count > 0 {
+                self.means[self.next] = (self.voc.mean, self.nox.mean);
+                self.next = (self.next + 1) % BASELINE_BUCKETS;
+                self.filled = (self.filled + 1).min(BASELINE_BUCKETS);
+            }
+            self.voc = RunningStats::new();
+            self.nox = RunningStats::new();
+            self.bucket_started = now;
+        }
+        self.voc.push(voc_index as f32);
+        self.nox.push(nox_index as f32);
+
+        let (voc, nox) = self.baseline()?;
+        Some(BaselineDelta {
+            voc: voc_index - libm::roundf(voc) as i32,
+            nox: nox_index - libm::roundf(nox) as i32,
+        })
+    }
+
+    /// (VOC, NOx) baseline over the completed buckets.
+    pub fn baseline(&self) -> Option<(f32, f32)> {
+        if self.filled == 0 {
+            return None;
+        }
+        let (voc, nox) = self.means[..self.filled]
+            .iter()
+            .fold((0.0, 0.0), |(v, n), (bv, bn)| (v + bv, n + bn));
+        Some((voc / self.filled as f32, nox / self.filled as f32))
+    }
+}
@@ -8,34 +8,131 @@
 
 extern crate alloc;
 use bt_hci::controller::ExternalController;
-use defmt::{error, info};
+use defmt::{error, info, warn};
 use embassy_sync::channel::{Channel as SyncChannel, Receiver, Sender};
 use embassy_time::{Duration, Timer};
 
 use embassy_executor::Spawner;
 use embassy_sync::blocking_mutex::raw::NoopRawMutex;
 use embassy_sync::mutex::Mutex;
-use embedded_hal_02::blocking::i2c::{Read, Write};
 use esp_hal::clock::CpuClock;
-use esp_hal::gpio::Io;
+use esp_hal::gpio::{Input, InputConfig, Io, Pull};
 use esp_hal::i2c::master::{Config as I2cConfig, I2c};
+use esp_hal::rtc_cntl::Rtc;
 use esp_hal::time::Rate;
 use esp_hal::timer::systimer::SystemTimer;
 use esp_hal::timer::timg::TimerGroup;
-use esp_hal::Blocking;
-use esp_sgp41_voc_nox::hal::{HalI2c, I2cCompat};
-use esp_sgp41_voc_nox::led::{Led, LedCommand};
-use esp_sgp41_voc_nox::tasks::conditioning::{sgp41_conditioning_task, SGP41_ADDR};
+use esp_sgp41_voc_nox::commission::{commission, self_test, SelfTestPolicy};
+use esp_sgp41_voc_nox::config::{update_config, MeasurementConfig, SensorConfig};
+use esp_sgp41_voc_nox::state::{transition_to, DeviceState};
+use esp_sgp41_voc_nox::compensation::CompensationMode;
+use esp_sgp41_voc_nox::hal::{negotiate_speed, HalI2c, I2cCompat, SpeedPlan};
+#[cfg(feature = "esp32c6")]
+use esp_sgp41_voc_nox::led::ColorOrder;
+use esp_sgp41_voc_nox::led::{ConditioningAnimation, Led, LedCommand, LedDriver, StatusLedConfig};
+#[cfg(feature = "dual-sgp41")]
+use esp_sgp41_voc_nox::mux::MuxChannel;
+use esp_sgp41_voc_nox::mux::SensorBus;
+#[cfg(feature = "low-power")]
+use esp_sgp41_voc_nox::power::{duty_cycle, DutyCycle};
+use esp_sgp41_voc_nox::readings::READINGS;
+use esp_sgp41_voc_nox::tasks::conditioning::sgp41_conditioning_task;
+use esp_sgp41_voc_nox::tasks::button::{button_task, ButtonConfig};
+#[cfg(feature = "co2-crosscheck")]
+use esp_sgp41_voc_nox::crosscheck::DivergenceRule;
+#[cfg(feature = "sdcard")]
+use esp_hal::spi::{master::{Config as SpiConfig, Spi}, Mode as SpiMode};
+#[cfg(feature = "sdcard")]
+use esp_sgp41_voc_nox::sdlog::SdLogConfig;
+#[cfg(feature = "sdcard")]
+use esp_sgp41_voc_nox::tasks::sdlog::sdlog_task;
+#[cfg(feature = "co2-crosscheck")]
+use esp_sgp41_voc_nox::tasks::crosscheck::crosscheck_task;
+#[cfg(feature = "sht4x")]
+use esp_sgp41_voc_nox::tasks::sht4x::sht4x_task;
+#[cfg(feature = "persistence")]
+use esp_sgp41_voc_nox::persistence::PersistenceConfig;
+#[cfg(feature = "persistence")]
+use esp_sgp41_voc_nox::tasks::persistence::{load_calibration, persistence_task, restore};
+#[cfg(feature = "persistence")]
+use esp_storage::FlashStorage;
+#[cfg(feature = "wifi-mqtt")]
+use embassy_net::StackResources;
+#[cfg(feature = "wifi-mqtt")]
+use esp_sgp41_voc_nox::mqtt::{MqttConfig, WifiConfig};
+#[cfg(feature = "http")]
+use esp_sgp41_voc_nox::tasks::http::http_task;
+#[cfg(feature = "influx")]
+use esp_sgp41_voc_nox::influx::InfluxConfig;
+#[cfg(feature = "influx")]
+use esp_sgp41_voc_nox::tasks::influx::influx_task;
+#[cfg(feature = "sntp")]
+use esp_sgp41_voc_nox::tasks::sntp::sntp_task;
+#[cfg(feature = "wifi-mqtt")]
+use esp_sgp41_voc_nox::tasks::mqtt::{mqtt_task, net_task, wifi_task};
+use esp_sgp41_voc_nox::tasks::ble::ble_task;
 use esp_sgp41_voc_nox::tasks::led::led_task;
 use esp_sgp41_voc_nox::tasks::sgp41_measurement::sgp41_measurement_task;
 use esp_wifi::ble::controller::BleConnector;
+use esp_wifi::EspWifiController;
 use panic_rtt_target as _;
 use static_cell::StaticCell;
 
-use esp_hal::rmt::{Channel as RmtChannel, Rmt};
+#[cfg(feature = "esp32c6")]
+use esp_hal::rmt::Rmt;
+#[cfg(feature = "dual-core")]
+use core::ptr::addr_of_mut;
+#[cfg(feature = "dual-core")]
+use esp_hal::system::{CpuControl, Stack};
+#[cfg(feature = "dual-core")]
+use esp_hal_embassy::Executor;
+#[cfg(any(feature = "esp32s3", feature = "sdcard", feature = "fan-relay"))]
+use esp_hal::gpio::{Level, Output, OutputConfig};
+#[cfg(feature = "fan-relay")]
+use esp_sgp41_voc_nox::tasks::relay::relay_task;
 
-use gas_index_algorithm::{AlgorithmType, GasIndexAlgorithm};
-use core::cell::RefCell;
+use esp_sgp41_voc_nox::algo::{GasIndex, GasIndexConfig};
+use esp_sgp41_voc_nox::ble::DeviceName;
+use esp_sgp41_voc_nox::calibration::IndexOffset;
+use esp_sgp41_voc_nox::driver::{Sgp41, Sgp41Error};
+use esp_sgp41_voc_nox::escalation::EscalationRule;
+use esp_sgp41_voc_nox::health::{record_nox_degraded, record_self_test, reset_reason, ResetReason};
+use esp_sgp41_voc_nox::reporting::set_voc_only_reporting;
+#[cfg(not(feature = "low-power"))]
+use esp_sgp41_voc_nox::supervisor::{Supervisor, SupervisorConfig};
+use esp_sgp41_voc_nox::timing::CONDITIONING_TIMEOUT;
+use gas_index_algorithm::AlgorithmType;
+
+#[cfg(feature = "dual-core")]
+static mut APP_CORE_STACK: Stack<8192> = Stack::new();
+#[cfg(feature = "dual-core")]
+static APP_CORE_EXECUTOR: StaticCell<Executor> = StaticCell::new();
+
+// Pre-command I²C settle delay (µs); raise only on boards that need it.
+const I2C_SETTLE_US: u32 = 0;
+
+// Bus speeds tried at startup, fastest first; the first one that passes
+// `I2C_SPEED_PROBES` CRC-verified serial reads in a row is kept.
+const I2C_SPEED_STEPS_KHZ: &[u32] = &[400, 200, 100];
+const I2C_SPEED_PROBES: u8 = 5;
+
+// Run the ~320 ms on-chip self-test at boot. Fast-boot deployments can turn
+// it off, but then a failed hotplate or pixel goes unnoticed until the
+// measurements start producing implausible data.
+const STARTUP_SELF_TEST: bool = true;
+const SELF_TEST_POLICY: SelfTestPolicy = SelfTestPolicy::WarnAndContinue;
+
+// How often the SHT4x is read for compensation.
+#[cfg(feature = "sht4x")]
+const SHT4X_PERIOD: Duration = Duration::from_secs(2);
+
+// Wake-to-wake period of the deep-sleep duty cycle.
+#[cfg(feature = "low-power")]
+const DUTY_CYCLE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+// TCA9548A channel of each SGP41, indexed by sensor id.
+#[cfg(feature = "dual-sgp41")]
+const SENSOR_MUX: [MuxChannel; 2] = [MuxChannel::tca9548a(0), MuxChannel::tca9548a(1)];
 
 // ── shared state between the two tasks ───────────────────────────────────────
 static I2C_BUS_CELL: StaticCell<Mutex<NoopRawMutex, I2cCompat<'static>>> = StaticCell::new();
@@ -47,8 +144,12 @@ esp_bootloader_esp_idf::esp_app_desc!();
 // A bounded queue for LED commands (4 entries)
 static LED_QUEUE: StaticCell<SyncChannel<NoopRawMutex, LedCommand, 4>> = StaticCell::new();
 
-static VOC_ALGO_CELL: StaticCell<RefCell<GasIndexAlgorithm>> = StaticCell::new();
-static NOX_ALGO_CELL: StaticCell<RefCell<GasIndexAlgorithm>> = StaticCell::new();
+static VOC_ALGO_CELL: StaticCell<GasIndex> = StaticCell::new();
+static NOX_ALGO_CELL: StaticCell<GasIndex> = StaticCell::new();
+#[cfg(feature = "dual-sgp41")]
+static SECOND_VOC_ALGO_CELL: StaticCell<GasIndex> = StaticCell::new();
+#[cfg(feature = "dual-sgp41")]
+static SECOND_NOX_ALGO_CELL: StaticCell<GasIndex> = StaticCell::new();
 
 #[esp_hal_embassy::main]
 async fn main(_spawner: Spawner) {
@@ -58,18 +159,52 @@ async fn main(_spawner: Spawner) {
     let peripherals = esp_hal::init(config);
     let _io = Io::new(peripherals.IO_MUX);
 
+    #[cfg(not(feature = "wifi-mqtt"))]
     esp_alloc::heap_allocator!(size: 64 * 1024);
+    // Wi-Fi and BLE coexisting: the Wi-Fi driver's buffers come on top of BLE's.
+    #[cfg(feature = "wifi-mqtt")]
+    esp_alloc::heap_allocator!(size: 128 * 1024);
 
     let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+    #[cfg(not(feature = "dual-core"))]
     esp_hal_embassy::init(timer0.alarm0);
+    // One alarm per executor: this core's and the app core's.
+    #[cfg(feature = "dual-core")]
+    esp_hal_embassy::init([timer0.alarm0, timer0.alarm1]);
 
     info!("Embassy initialized!");
 
+    let reset = reset_reason();
+    if reset == ResetReason::Watchdog {
+        warn!("Last reset was caused by a watchdog");
+    } else {
+        info!("Reset reason: {}", reset);
+    }
+
+    // ── LED setup for XIAO ESP32-S3 (built-in LED on GPIO21) ──────────
+    // Create unified LED API for different chips
+    #[cfg(feature = "esp32s3")]
+    let mut led_hw = Led::new_gpio(Output::new(peripherals.GPIO21, Level::Low, OutputConfig::default()));
+
+    #[cfg(feature = "esp32c6")]
+    let rmt = Rmt::new(peripherals.RMT, Rate::from_mhz(80)).expect("Failed to initialize RMT");
+
+    #[cfg(feature = "esp32c6")]
+    let mut led_hw = Led::new_ws2812(
+        rmt.channel0,
+        peripherals.GPIO8,  // WS2812 LED pin for ESP32-C6
+        ColorOrder::default(),
+    );
+    // "Initializing, no data yet" until the first state transition takes over.
+    let status_led = StatusLedConfig::default();
+    let (r, g, b) = status_led.initializing;
+    let _ = led_hw.set_color_rgb(r, g, b);
+
     // Initialize I2C for SGP41 sensor on GPIO4 (SDA) and GPIO5 (SCL)
     let sda = peripherals.GPIO4; // SDA pin
     let scl = peripherals.GPIO5; // SCL pin
 
-    let i2c_config = I2cConfig::default().with_frequency(Rate::from_khz(400));
+    let i2c_config = I2cConfig::default().with_frequency(Rate::from_khz(I2C_SPEED_STEPS_KHZ[0]));
 
     static RAW_I2C_CELL: StaticCell<HalI2c<'static>> = StaticCell::new();
 
@@ -84,52 +219,50 @@ async fn main(_spawner: Spawner) {
     };
     let raw_i2c = RAW_I2C_CELL.init(raw);
 
+    // The mux powers up with every channel off; the boot checks below talk
+    // to the first sensor.
+    #[cfg(feature = "dual-sgp41")]
+    if raw_i2c.write(SENSOR_MUX[0].address, &[SENSOR_MUX[0].control_byte()]).is_err() {
+        error!("TCA9548A mux not answering at 0x{:02X}", SENSOR_MUX[0].address);
+    }
+
+    let speed_plan = SpeedPlan {
+        steps_khz: I2C_SPEED_STEPS_KHZ,
+        probes: I2C_SPEED_PROBES,
+    };
+    if negotiate_speed(raw_i2c, &speed_plan).await.is_none() {
+        error!("SGP41 not answering reliably at any I²C speed");
+    }
+
     // ── wrap esp-hal I²C so it satisfies the driver (eh-0.2) traits ────
-    let mut i2c = I2cCompat::new(raw_i2c);
+    let mut i2c = I2cCompat::new(raw_i2c).with_settle_delay_us(I2C_SETTLE_US);
 
     // Test I2C communication by reading serial number
     info!("Testing SGP41 communication...");
-    let get_serial_cmd = [0x36, 0x82];
-    let mut serial_buffer = [0u8; 9]; // 6 bytes data + 3 CRC bytes
-
-    if i2c.write(SGP41_ADDR, &get_serial_cmd).is_ok() {
-        embassy_time::Timer::after(Duration::from_millis(1)).await;
-        if i2c.read(SGP41_ADDR, &mut serial_buffer).is_ok() {
+    let serial = match Sgp41::new(&mut i2c).read_serial_number().await {
+        Ok(words) => {
             info!(
-                "SGP41 connected! Serial: {:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
-                serial_buffer[0],
-                serial_buffer[1],
-                serial_buffer[3],
-                serial_buffer[4],
-                serial_buffer[6],
-                serial_buffer[7]
+                "SGP41 connected! Serial: {:04X}{:04X}{:04X}",
+                words[0], words[1], words[2]
             );
-        } else {
-            error!("Failed to read SGP41 serial number");
+            Some(words)
         }
-    } else {
-        error!("Failed to communicate with SGP41 sensor");
-        error!("Check connections: SDA=GPIO4, SCL=GPIO5, VCC=3.3V, GND=GND");
+        Err(Sgp41Error::I2c(e)) => {
+            error!("Failed to communicate with SGP41 sensor: {}", e);
+            error!("Check connections: SDA=GPIO4, SCL=GPIO5, VCC=3.3V, GND=GND");
+            None
+        }
+        Err(e) => {
+            warn!("SGP41 serial number rejected: {}", e);
+            None
+        }
+    };
+    match Sgp41::new(&mut i2c).get_feature_set().await {
+        Ok(features) => info!("SGP41 feature set: {}", features),
+        Err(e) => warn!("SGP41 feature set unreadable: {}", e),
     }
 
-    // ── LED setup for XIAO ESP32-S3 (built-in LED on GPIO21) ──────────
-    // Create unified LED API for different chips
-    #[cfg(feature = "esp32s3")]
-    let mut led = Led::new_gpio(Output::new(peripherals.GPIO21, Level::Low, Default::default()));
-
-    #[cfg(feature = "esp32c6")]
-    let rmt = Rmt::new(peripherals.RMT, Rate::from_mhz(80)).expect("Failed to initialize RMT");
-
-    #[cfg(feature = "esp32c6")]
-    let mut led_hw = Led::new_ws2812(
-        rmt.channel0,
-        peripherals.GPIO8,  // WS2812 LED pin for ESP32-C6
-    );
-    led_hw.set_color_rgb(30, 0, 0);
-
-    static LED_CELL: StaticCell<
-        Mutex<NoopRawMutex, Led<RmtChannel<Blocking, 0>>>
-    > = StaticCell::new();
+    static LED_CELL: StaticCell<Mutex<NoopRawMutex, LedDriver>> = StaticCell::new();
     let led: &'static _ = LED_CELL.init(Mutex::new(led_hw));
 
     // Initialize LED command queue and split sender/receiver
@@ -138,37 +271,368 @@ async fn main(_spawner: Spawner) {
     let led_sender2 = led_sender;
     let led_receiver: Receiver<'static, NoopRawMutex, LedCommand, 4> = led_queue.receiver();
 
+    // Single source of truth for the measurement cadence, conditioning length
+    // and fallback compensation; the algorithms sample at the same interval.
+    let sensor = SensorConfig::default();
+    let tuning = GasIndexConfig::default();
     let voc_algo: &'static _ =
-        VOC_ALGO_CELL.init(RefCell::new(GasIndexAlgorithm::new(AlgorithmType::Voc, 1.0)));
+        VOC_ALGO_CELL.init(GasIndex::new(AlgorithmType::Voc, sensor.measurement_interval, tuning.voc));
     let nox_algo: &'static _ =
-        NOX_ALGO_CELL.init(RefCell::new(GasIndexAlgorithm::new(AlgorithmType::Nox, 1.0)));
+        NOX_ALGO_CELL.init(GasIndex::new(AlgorithmType::Nox, sensor.measurement_interval, tuning.nox));
+    // The second sensor learns its own baseline.
+    #[cfg(feature = "dual-sgp41")]
+    let second_algos: (&'static GasIndex, &'static GasIndex) = (
+        SECOND_VOC_ALGO_CELL.init(GasIndex::new(AlgorithmType::Voc, sensor.measurement_interval, tuning.voc)),
+        SECOND_NOX_ALGO_CELL.init(GasIndex::new(AlgorithmType::Nox, sensor.measurement_interval, tuning.nox)),
+    );
+
+    // Pick up the learned baseline from the last run, if one was saved.
+    #[cfg(feature = "persistence")]
+    let mut flash = FlashStorage::new();
+    #[cfg(feature = "persistence")]
+    let baseline_restored = restore(&mut flash, &PersistenceConfig::default(), voc_algo, nox_algo);
+    #[cfg(not(feature = "persistence"))]
+    let baseline_restored = false;
+    // A saved index offset only applies to the sensor it was measured for.
+    #[cfg(feature = "persistence")]
+    let index_offset = load_calibration(&mut flash, &PersistenceConfig::default(), serial);
+    #[cfg(not(feature = "persistence"))]
+    let index_offset = IndexOffset::NONE;
 
     // Initialize WiFi/BLE
     let rng = esp_hal::rng::Rng::new(peripherals.RNG);
     let timer1 = TimerGroup::new(peripherals.TIMG0);
-    let wifi_init = esp_wifi::init(timer1.timer0, rng, peripherals.RADIO_CLK)
-        .expect("Failed to initialize WIFI/BLE controller");
+    static WIFI_INIT: StaticCell<EspWifiController<'static>> = StaticCell::new();
+    let wifi_init: &'static _ = WIFI_INIT.init(
+        esp_wifi::init(timer1.timer0, rng, peripherals.RADIO_CLK)
+            .expect("Failed to initialize WIFI/BLE controller"),
+    );
 
-    let transport = BleConnector::new(&wifi_init, peripherals.BT);
-    let _ble_controller = ExternalController::<_, 20>::new(transport);
+    // The serial read above has completed, so the advertised name is final here.
+    static BLE_NAME: StaticCell<DeviceName> = StaticCell::new();
+    let ble_name: &'static DeviceName = BLE_NAME.init(DeviceName::from_serial(serial));
+    info!("BLE device name: {}", ble_name.as_str());
+
+    let transport = BleConnector::new(wifi_init, peripherals.BT);
+    let ble_controller = ExternalController::<_, 20>::new(transport);
+    // Subscribe before the sensing tasks start so no reading is missed.
+    let ble_readings = READINGS.subscriber().expect("too many readings subscribers");
+    #[cfg(feature = "low-power")]
+    let mut duty_readings = READINGS.subscriber().expect("too many readings subscribers");
+    _spawner.must_spawn(ble_task(ble_controller, ble_name.as_str(), ble_readings));
+
+    // Wi-Fi station + MQTT publisher, when built with credentials.
+    #[cfg(feature = "wifi-mqtt")]
+    match WifiConfig::from_env() {
+        Some(wifi) => {
+            let (controller, interfaces) =
+                esp_wifi::wifi::new(wifi_init, peripherals.WIFI).expect("Failed to initialize Wi-Fi");
+            // DHCP, DNS, the MQTT connection, the HTTP listener, the Influx and
+            // the SNTP socket.
+            static NET_RESOURCES: StaticCell<StackResources<6>> = StaticCell::new();
+            let mut rng = rng;
+            let seed = (rng.random() as u64) << 32 | rng.random() as u64;
+            let (stack, runner) = embassy_net::new(
+                interfaces.sta,
+                embassy_net::Config::dhcpv4(Default::default()),
+                NET_RESOURCES.init(StackResources::new()),
+                seed,
+            );
+            _spawner.must_spawn(wifi_task(controller, wifi));
+            _spawner.must_spawn(net_task(runner));
+            #[cfg(feature = "sntp")]
+            _spawner.must_spawn(sntp_task(stack, option_env!("NTP_SERVER").unwrap_or("pool.ntp.org")));
+            let mqtt_readings = READINGS.subscriber().expect("too many readings subscribers");
+            _spawner.must_spawn(mqtt_task(
+                stack,
+                wifi,
+                MqttConfig::default(),
+                ble_name,
+                serial,
+                mqtt_readings,
+                led_sender,
+            ));
+            #[cfg(feature = "http")]
+            {
+                let http_readings = READINGS.subscriber().expect("too many readings subscribers");
+                _spawner.must_spawn(http_task(stack, http_readings, serial));
+            }
+            #[cfg(feature = "influx")]
+            match InfluxConfig::from_env() {
+                Some(influx) => {
+                    let influx_readings = READINGS.subscriber().expect("too many readings subscribers");
+                    _spawner.must_spawn(influx_task(stack, influx, serial, influx_readings));
+                }
+                None => warn!("INFLUX_HOST not set at build time; Influx output disabled"),
+            }
+        }
+        None => warn!("WIFI_SSID/MQTT_BROKER not set at build time; MQTT publishing disabled"),
+    }
 
     // Initialize the shared I2C bus mutex
     let i2c_bus: &'static Mutex<NoopRawMutex, I2cCompat<'static>> =
         I2C_BUS_CELL.init(Mutex::new(i2c));
+    #[cfg(not(feature = "dual-sgp41"))]
+    let primary = SensorBus::single(i2c_bus);
+    #[cfg(feature = "dual-sgp41")]
+    let primary = SensorBus::on_channel(0, i2c_bus, SENSOR_MUX[0]);
+
+
+    // Commissioning mode: one wiring check, report on LED and RTT, then idle.
+    if cfg!(feature = "commission") {
+        commission_mode(_spawner, &primary, led_receiver, led, status_led, led_sender).await;
+    }
+
+    update_config(|c| c.startup_self_test = STARTUP_SELF_TEST);
+    if STARTUP_SELF_TEST {
+        transition_to(DeviceState::SelfTest);
+        let outcome = self_test(&primary).await;
+        record_self_test(outcome);
+        match outcome {
+            Some(result) if result.passed() => info!("SGP41 self-test passed"),
+            Some(result) => {
+                error!("SGP41 self-test failed: {}", result);
+                match SELF_TEST_POLICY {
+                    SelfTestPolicy::Halt => {
+                        transition_to(DeviceState::Fault);
+                        loop {
+                            Timer::after(Duration::from_secs(60)).await;
+                        }
+                    }
+                    // VOC still works: keep the sensor useful without NOx.
+                    SelfTestPolicy::WarnAndContinue if result.voc_ok => {
+                        warn!("NOx pixel failed; continuing with VOC-only reporting");
+                        set_voc_only_reporting(true);
+                        record_nox_degraded();
+                    }
+                    SelfTestPolicy::WarnAndContinue => {}
+                }
+            }
+            None => error!("SGP41 self-test unreadable"),
+        }
+    } else {
+        info!("Startup self-test skipped");
+    }
 
+    // Live values from the SHT4x when fitted; readings older than three
+    // periods fall back to the defaults. Without one, use the configured
+    // defaults (uncompensated at 25 °C / 50 %).
+    #[cfg(feature = "sht4x")]
+    let compensation = CompensationMode::Live {
+        stale_after: SHT4X_PERIOD * 3,
+    };
+    #[cfg(not(feature = "sht4x"))]
+    let compensation = sensor.fallback_compensation();
 
-    // Run the burn‑in first; it will spawn the measurement task when done.
-    _spawner.must_spawn(sgp41_conditioning_task(i2c_bus, 10, led_sender, voc_algo));
-    _spawner.must_spawn(sgp41_measurement_task(
+    let sensing = Sensing {
         i2c_bus,
+        primary,
+        #[cfg(feature = "dual-sgp41")]
+        second_algos,
+        led,
+        led_sender,
         led_sender2,
+        led_receiver,
         voc_algo,
         nox_algo,
-    ));
-    _spawner.must_spawn(led_task(led_receiver, led));
-    
-    // Nothing else to do here; park the main task.
+        compensation,
+        sensor,
+        // Waking from deep sleep is a cold hotplate: always condition.
+        baseline_restored: baseline_restored && !cfg!(feature = "low-power"),
+        #[cfg(feature = "persistence")]
+        flash,
+        #[cfg(feature = "persistence")]
+        serial,
+        index_offset,
+        status_led,
+    };
+
+    // Core assignment: without `dual-core` everything shares this executor.
+    // With it, the sensing tasks move to the app core (core 1) so BLE/WiFi
+    // interrupt load on the pro core (core 0) can't delay the measurement
+    // cadence. Main, the button task and the radio stay on core 0.
+    #[cfg(not(feature = "dual-core"))]
+    spawn_sensing(_spawner, sensing);
+
+    #[cfg(feature = "dual-core")]
+    let _app_core = {
+        let mut cpu_control = CpuControl::new(peripherals.CPU_CTRL);
+        // Dropping the guard would park the app core, so it lives as long as main.
+        cpu_control
+            .start_app_core(unsafe { &mut *addr_of_mut!(APP_CORE_STACK) }, move || {
+                let executor = APP_CORE_EXECUTOR.init(Executor::new());
+                executor.run(|spawner| spawn_sensing(spawner, sensing));
+            })
+            .expect("Failed to start app core")
+    };
+
+    // BOOT button (GPIO9 on the ESP32-C6 DevKit), active low
+    let button = Input::new(peripherals.GPIO9, InputConfig::default().with_pull(Pull::Up));
+    _spawner.must_spawn(button_task(button, ButtonConfig::default()));
+
+    // Fan relay on GPIO10, driven by the escalation rule; active high.
+    #[cfg(feature = "fan-relay")]
+    {
+        let relay = Output::new(peripherals.GPIO10, Level::Low, OutputConfig::default());
+        _spawner.must_spawn(relay_task(relay));
+    }
+
+    // SD card on SPI2: SCK=GPIO6, MOSI=GPIO7, MISO=GPIO2, CS=GPIO3.
+    // 400 kHz is the SD initialization clock; plenty for one row per second.
+    #[cfg(feature = "sdcard")]
+    match Spi::new(
+        peripherals.SPI2,
+        SpiConfig::default().with_frequency(Rate::from_khz(400)).with_mode(SpiMode::_0),
+    ) {
+        Ok(spi) => {
+            let spi = spi
+                .with_sck(peripherals.GPIO6)
+                .with_mosi(peripherals.GPIO7)
+                .with_miso(peripherals.GPIO2);
+            let cs = Output::new(peripherals.GPIO3, Level::High, OutputConfig::default());
+            let sd_readings = READINGS.subscriber().expect("too many readings subscribers");
+            _spawner.must_spawn(sdlog_task(spi, cs, SdLogConfig::default(), sd_readings));
+        }
+        Err(_) => error!("SD card SPI initialization failed"),
+    }
+
+    // The main task stays on as the liveness supervisor and watchdog feeder.
+    let rtc = Rtc::new(peripherals.LPWR);
+    // Battery builds instead sleep after the first reading; the duty cycle's
+    // awake timeout bounds a hung wake.
+    #[cfg(feature = "low-power")]
+    {
+        let mut rtc = rtc;
+        duty_cycle(&mut rtc, &primary, &mut duty_readings, DutyCycle::every(DUTY_CYCLE_INTERVAL)).await
+    }
+    #[cfg(not(feature = "low-power"))]
+    Supervisor::new(SupervisorConfig::default()).run(rtc.rwdt).await
+}
+/// The `commission` build's whole run after startup: check the wiring once,
+/// show the verdict on the LED, log the report and idle.
+async fn commission_mode(
+    spawner: Spawner,
+    primary: &SensorBus,
+    led_receiver: Receiver<'static, NoopRawMutex, LedCommand, 4>,
+    led: &'static Mutex<NoopRawMutex, LedDriver>,
+    status_led: StatusLedConfig,
+    led_sender: Sender<'static, NoopRawMutex, LedCommand, 4>,
+) -> ! {
+    spawner.must_spawn(led_task(led_receiver, led, status_led));
+    let report = commission(primary).await;
+    report.log();
+    let (r, g, b) = report.led_color();
+    led_sender.send(LedCommand::Solid(r, g, b)).await;
     loop {
         Timer::after(Duration::from_secs(60)).await;
     }
-}
\ No newline at end of file
+}
+
+/// Resources owned by the sensing tasks (conditioning, measurement, LED).
+///
+/// The I²C bus, LED queue and LED driver use `NoopRawMutex`, which is only
+/// sound while every user runs on one executor, so these tasks are always
+/// spawned together on the same core. Anything that crosses cores (today the
+/// `control::CONTROL` channel fed by the button task, and the
+/// `readings::READINGS` pub-sub) must use `CriticalSectionRawMutex` instead.
+struct Sensing {
+    i2c_bus: &'static Mutex<NoopRawMutex, I2cCompat<'static>>,
+    // The first (or only) SGP41; owns the LED and the control commands.
+    primary: SensorBus,
+    // VOC and NOx algorithms of the SGP41 on `SENSOR_MUX[1]`.
+    #[cfg(feature = "dual-sgp41")]
+    second_algos: (&'static GasIndex, &'static GasIndex),
+    led: &'static Mutex<NoopRawMutex, LedDriver>,
+    led_sender: Sender<'static, NoopRawMutex, LedCommand, 4>,
+    led_sender2: Sender<'static, NoopRawMutex, LedCommand, 4>,
+    led_receiver: Receiver<'static, NoopRawMutex, LedCommand, 4>,
+    voc_algo: &'static GasIndex,
+    nox_algo: &'static GasIndex,
+    compensation: CompensationMode,
+    sensor: SensorConfig,
+    baseline_restored: bool,
+    #[cfg(feature = "persistence")]
+    flash: FlashStorage,
+    // Ties a saved index offset to this sensor.
+    #[cfg(feature = "persistence")]
+    serial: Option<[u16; 3]>,
+    index_offset: IndexOffset,
+    status_led: StatusLedConfig,
+}
+
+// SAFETY: `Sensing` is moved to the app core exactly once, before any of its
+// tasks run, and core 0 never touches these resources afterwards, so every
+// `NoopRawMutex`/`RefCell` inside is only ever accessed from one core.
+#[cfg(feature = "dual-core")]
+unsafe impl Send for Sensing {}
+
+fn spawn_sensing(spawner: Spawner, s: Sensing) {
+    // Start the compensation source first so conditioning can use it.
+    #[cfg(feature = "sht4x")]
+    spawner.must_spawn(sht4x_task(s.i2c_bus, SHT4X_PERIOD));
+    // Run the burn‑in first; the measurement task waits for it to finish.
+    spawner.must_spawn(sgp41_conditioning_task(
+        s.primary,
+        s.sensor,
+        s.led_sender,
+        s.voc_algo,
+        s.compensation,
+        ConditioningAnimation::PLEASE_WAIT,
+        s.baseline_restored,
+        CONDITIONING_TIMEOUT,
+    ));
+    spawner.must_spawn(sgp41_measurement_task(
+        s.primary,
+        Some(s.led_sender2),
+        s.voc_algo,
+        s.nox_algo,
+        MeasurementConfig {
+            sensor: s.sensor,
+            compensation: s.compensation,
+            escalation: Some(EscalationRule::default()),
+            index_offset: s.index_offset,
+            // Rolling-baseline deltas stay off;
+            // `rolling_baseline: Some(baseline::DEFAULT_BASELINE_WINDOW)` enables them.
+            ..MeasurementConfig::default()
+        },
+    ));
+    // Second SGP41: own algorithms and conditioning, no LED, escalation,
+    // calibration offset or saved baseline.
+    #[cfg(feature = "dual-sgp41")]
+    {
+        let (voc_algo, nox_algo) = s.second_algos;
+        let second = SensorBus::on_channel(1, s.i2c_bus, SENSOR_MUX[1]);
+        spawner.must_spawn(sgp41_conditioning_task(
+            second,
+            s.sensor,
+            s.led_sender,
+            voc_algo,
+            s.compensation,
+            ConditioningAnimation::PLEASE_WAIT,
+            false,
+            CONDITIONING_TIMEOUT,
+        ));
+        spawner.must_spawn(sgp41_measurement_task(
+            second,
+            None,
+            voc_algo,
+            nox_algo,
+            MeasurementConfig {
+                sensor: s.sensor,
+                compensation: s.compensation,
+                ..MeasurementConfig::default()
+            },
+        ));
+    }
+    spawner.must_spawn(led_task(s.led_receiver, s.led, s.status_led));
+    #[cfg(feature = "persistence")]
+    spawner.must_spawn(persistence_task(
+        s.flash,
+        PersistenceConfig::default(),
+        s.serial,
+        s.voc_algo,
+        s.nox_algo,
+    ));
+    #[cfg(feature = "co2-crosscheck")]
+    spawner.must_spawn(crosscheck_task(s.i2c_bus, DivergenceRule::default()));
+}
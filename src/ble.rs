@@ -0,0 +1,128 @@
+//! BLE presentation helpers shared by the radio tasks.
+
+use defmt::Format;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use trouble_host::prelude::Uuid;
+
+// Custom UUIDs are `5a9aXXXX-8f3e-4b8e-9d41-53475034310a`, numbered in the
+// `XXXX` group (no Bluetooth SIG equivalent exists).
+const UUID_BASE: u128 = 0x5a9a0000_8f3e_4b8e_9d41_53475034310a;
+
+const fn custom_uuid(id: u16) -> Uuid {
+    Uuid::new_long((UUID_BASE | (id as u128) << 96).to_le_bytes())
+}
+
+/// Custom service carrying the device characteristics below.
+pub const SGP41_SERVICE_UUID: Uuid = custom_uuid(0x0000);
+/// Read-only active configuration, see `config::ConfigSnapshot::to_ble_bytes`.
+pub const CONFIG_CHARACTERISTIC_UUID: Uuid = custom_uuid(0x0001);
+
+/// Read/notify `u8` air-quality category (0–5), see `category` for the mapping.
+/// Exposed alongside the raw VOC/NOx index characteristics.
+pub const CATEGORY_CHARACTERISTIC_UUID: Uuid = custom_uuid(0x0002);
+
+/// Read/notify raw ticks for host-side processing, see `RawTicks::to_ble_bytes`.
+pub const RAW_CHARACTERISTIC_UUID: Uuid = custom_uuid(0x0003);
+
+/// Read-only device health (uptime, reset reason, error counters), see
+/// `health::HealthSnapshot::to_ble_bytes`.
+pub const HEALTH_CHARACTERISTIC_UUID: Uuid = custom_uuid(0x0004);
+
+/// Read/notify `u16` VOC and NOx gas indices, in the Environmental Sensing
+/// service (0x181A) served by `tasks::ble`; the SIG defines no gas index
+/// characteristic.
+pub const VOC_INDEX_CHARACTERISTIC_UUID: Uuid = custom_uuid(0x0005);
+pub const NOX_INDEX_CHARACTERISTIC_UUID: Uuid = custom_uuid(0x0006);
+
+/// Write-only runtime commands for `control::CONTROL`, see
+/// `control::ControlCommand::from_ble_bytes`.
+pub const CONTROL_CHARACTERISTIC_UUID: Uuid = custom_uuid(0x0007);
+
+/// Length of the raw ticks characteristic value.
+pub const RAW_BLE_LEN: usize = 8;
+
+/// Latest raw sample, published by the measurement task once per measurement
+/// interval (1 Hz by default) for the raw ticks characteristic. Only the newest
+/// sample is kept, so a slow BLE task skips samples rather than lagging.
+pub static RAW_TICKS: Signal<CriticalSectionRawMutex, RawTicks> = Signal::new();
+
+/// Raw SGP41 output plus the compensation ticks sent with the measure command.
+///
+/// Compensation happens inside the sensor: the raw ticks here already reflect
+/// the humidity/temperature in `humidity_ticks`/`temperature_ticks`, so a host
+/// running its own gas index algorithm must not compensate again.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub struct RawTicks {
+    pub voc_raw: u16,
+    pub nox_raw: u16,
+    pub humidity_ticks: u16,
+    pub temperature_ticks: u16,
+}
+
+impl RawTicks {
+    /// `compensation` is the parameter block from `CompensationMode::params`.
+    pub fn new(voc_raw: u16, nox_raw: u16, compensation: [u8; 6]) -> Self {
+        Self {
+            voc_raw,
+            nox_raw,
+            humidity_ticks: u16::from_be_bytes([compensation[0], compensation[1]]),
+            temperature_ticks: u16::from_be_bytes([compensation[3], compensation[4]]),
+        }
+    }
+
+    /// Little-endian value: VOC raw, NOx raw, humidity ticks, temperature ticks
+    /// (`u16` each).
+    pub fn to_ble_bytes(&self) -> [u8; RAW_BLE_LEN] {
+        let mut out = [0u8; RAW_BLE_LEN];
+        out[0..2].copy_from_slice(&self.voc_raw.to_le_bytes());
+        out[2..4].copy_from_slice(&self.nox_raw.to_le_bytes());
+        out[4..6].copy_from_slice(&self.humidity_ticks.to_le_bytes());
+        out[6..8].copy_from_slice(&self.temperature_ticks.to_le_bytes());
+        out
+    }
+}
+
+/// Name advertised when the sensor serial could not be read at boot.
+pub const FALLBACK_DEVICE_NAME: &str = "SGP41";
+
+const NAME_LEN: usize = FALLBACK_DEVICE_NAME.len() + 5; // "SGP41-" + 4 hex digits
+
+/// Advertised device name: `SGP41-XXXX`, where `XXXX` is the last (least
+/// significant) serial word in upper-case hex. The serial is unique per sensor,
+/// so the name is stable across reboots and distinct between devices. Falls
+/// back to plain `SGP41` when the serial read failed or had a bad CRC.
+#[derive(Copy, Clone)]
+pub struct DeviceName {
+    buf: [u8; NAME_LEN],
+    len: usize,
+}
+
+impl DeviceName {
+    pub fn from_serial(serial: Option<[u16; 3]>) -> Self {
+        let mut buf = [0u8; NAME_LEN];
+        let prefix = FALLBACK_DEVICE_NAME.as_bytes();
+        buf[..prefix.len()].copy_from_slice(prefix);
+
+        let Some(serial) = serial else {
+            return Self {
+                buf,
+                len: prefix.len(),
+            };
+        };
+
+        const HEX: &[u8; 16] = b"0123456789ABCDEF";
+        let low = serial[2];
+        buf[prefix.len()] = b'-';
+        for i in 0..4 {
+            let nibble = (low >> (12 - 4 * i)) & 0xF;
+            buf[prefix.len() + 1 + i] = HEX[nibble as usize];
+        }
+        Self { buf, len: NAME_LEN }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Only ASCII is ever written into `buf`.
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or(FALLBACK_DEVICE_NAME)
+    }
+}
@@ -0,0 +1,53 @@
+//! Per-device field calibration of the reported gas index.
+//!
+//! The offset is linear: `reported = clamp(index + offset, 1, 500)`. It is not
+//! a recalibration. The gas index algorithm still learns its own baseline from
+//! the uncorrected raw ticks, and the offset is added afterwards, so it shifts
+//! the whole scale including the algorithm's "typical air" point (100 for VOC,
+//! 1 for NOx). Use it only for a sensor characterized as reading consistently
+//! high or low against a reference across the range of interest.
+//!
+//! Offsets are set at runtime (`control::ControlCommand::SetCalibration`, e.g.
+//! over BLE) and, with `persistence`, kept in flash for the sensor they were
+//! measured for (`persistence::CalibrationRecord`).
+
+use defmt::Format;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+
+/// Smallest and largest index the algorithm reports once it is running.
+pub const INDEX_MIN: i32 = 1;
+pub const INDEX_MAX: i32 = 500;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Format)]
+pub struct IndexOffset {
+    pub voc: i16,
+    pub nox: i16,
+}
+
+impl IndexOffset {
+    pub const NONE: Self = Self { voc: 0, nox: 0 };
+
+    pub fn apply_voc(&self, index: i32) -> i32 {
+        apply(index, self.voc)
+    }
+
+    pub fn apply_nox(&self, index: i32) -> i32 {
+        apply(index, self.nox)
+    }
+}
+
+// An index of 0 means the algorithm is still in its initial blackout and has
+// no value yet; keep it as-is rather than inventing a reading.
+fn apply(index: i32, offset: i16) -> i32 {
+    if index == 0 || offset == 0 {
+        return index;
+    }
+    (index + offset as i32).clamp(INDEX_MIN, INDEX_MAX)
+}
+
+/// Raised by the measurement task once it applies an offset from
+/// `control::ControlCommand::SetCalibration`; `persistence_task` saves it to
+/// flash for the current sensor. Without `persistence` the offset lasts until
+/// the next reset.
+pub static CALIBRATION_UPDATE: Signal<CriticalSectionRawMutex, IndexOffset> = Signal::new();
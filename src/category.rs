@@ -0,0 +1,60 @@
+//! Consumer-friendly air-quality categories derived from the VOC index.
+//!
+//! The numeric value (`as u8`) is the BLE category characteristic value and is
+//! a stable contract for app developers:
+//!
+//! | value | category   | VOC index |
+//! |-------|------------|-----------|
+//! | 0     | Excellent  | 1–92      |
+//! | 1     | Good       | 93–114    |
+//! | 2     | Moderate   | 115–155   |
+//! | 3     | Poor       | 156–250   |
+//! | 4     | Unhealthy  | 251–400   |
+//! | 5     | Hazardous  | 401–500   |
+//! | 255   | Warming up | 0         |
+//!
+//! The lower bands match the LED color thresholds. 100 is the VOC index of
+//! the sensor's learned "typical" air, so Excellent/Good cover normal rooms.
+//! The gas index algorithm reports 0 until its warm-up ends; that means "no
+//! index yet", not clean air, hence its own value.
+
+use defmt::Format;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Format)]
+#[repr(u8)]
+pub enum AirQualityCategory {
+    Excellent = 0,
+    Good = 1,
+    Moderate = 2,
+    Poor = 3,
+    Unhealthy = 4,
+    Hazardous = 5,
+    WarmingUp = 255,
+}
+
+/// Category for a VOC index.
+pub fn voc_category(voc_index: i32) -> AirQualityCategory {
+    match voc_index {
+        i32::MIN..=0 => AirQualityCategory::WarmingUp,
+        1..=92 => AirQualityCategory::Excellent,
+        93..=114 => AirQualityCategory::Good,
+        115..=155 => AirQualityCategory::Moderate,
+        156..=250 => AirQualityCategory::Poor,
+        251..=400 => AirQualityCategory::Unhealthy,
+        _ => AirQualityCategory::Hazardous,
+    }
+}
+
+impl AirQualityCategory {
+    pub fn label(self) -> &'static str {
+        match self {
+            AirQualityCategory::Excellent => "Excellent",
+            AirQualityCategory::Good => "Good",
+            AirQualityCategory::Moderate => "Moderate",
+            AirQualityCategory::Poor => "Poor",
+            AirQualityCategory::Unhealthy => "Unhealthy",
+            AirQualityCategory::Hazardous => "Hazardous",
+            AirQualityCategory::WarmingUp => "Warming up",
+        }
+    }
+}
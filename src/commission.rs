@@ -0,0 +1,118 @@
+use core::ops::RangeInclusive;
+use defmt::{error, info, Format};
+
+use crate::mux::SensorBus;
+use crate::prepare_default_params;
+use crate::driver::{SelfTestResult, Sgp41, Sgp41Error};
+
+/// VOC raw ticks considered plausible for a healthy, powered sensor. This is a
+/// coarse wiring check, not an accuracy bound. NOx is not range-checked because
+/// it reads 0 until the sensor has been conditioned.
+pub const VOC_RAW_PLAUSIBLE: RangeInclusive<u16> = 10_000..=60_000;
+
+/// What the startup self-test does when a pixel fails.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Format)]
+pub enum SelfTestPolicy {
+    /// Any failure is fatal: enter `Fault` and stop before measuring.
+    Halt,
+    /// Log and keep measuring. If only the NOx pixel failed, NOx is
+    /// suppressed on every output (VOC-only reporting) and the health
+    /// snapshot's `nox_degraded` flag is set.
+    #[default]
+    WarnAndContinue,
+}
+
+// LED colors for the commissioning verdict
+pub const COMMISSION_PASS_COLOR: (u8, u8, u8) = (0, 30, 0);
+pub const COMMISSION_FAIL_COLOR: (u8, u8, u8) = (30, 0, 0);
+
+/// Outcome of a `commission()` run.
+#[derive(Copy, Clone, Format)]
+pub struct CommissionReport {
+    /// The sensor acknowledged its address.
+    pub ack: bool,
+    /// Serial number, present only if all three words had valid CRCs.
+    pub serial: Option<[u16; 3]>,
+    /// Self-test result, present only if its word had a valid CRC.
+    pub self_test: Option<SelfTestResult>,
+    /// One uncompensated raw measurement (VOC, NOx), present only if CRC-valid.
+    pub raw: Option<(u16, u16)>,
+}
+
+impl CommissionReport {
+    /// Both pixels passed.
+    pub fn self_test_passed(&self) -> bool {
+        self.self_test.is_some_and(|result| result.passed())
+    }
+
+    pub fn raw_plausible(&self) -> bool {
+        matches!(self.raw, Some((voc, _)) if VOC_RAW_PLAUSIBLE.contains(&voc))
+    }
+
+    pub fn passed(&self) -> bool {
+        self.ack && self.serial.is_some() && self.self_test_passed() && self.raw_plausible()
+    }
+
+    /// LED color reporting the verdict.
+    pub fn led_color(&self) -> (u8, u8, u8) {
+        if self.passed() {
+            COMMISSION_PASS_COLOR
+        } else {
+            COMMISSION_FAIL_COLOR
+        }
+    }
+
+    pub fn log(&self) {
+        info!("Commissioning report:");
+        info!("  ACK:       {}", self.ack);
+        info!("  Serial:    {}", self.serial);
+        info!("  Self-test: {} (passed: {})", self.self_test, self.self_test_passed());
+        info!("  Raw:       {} (plausible: {})", self.raw, self.raw_plausible());
+        if self.passed() {
+            info!("Commissioning PASSED");
+        } else {
+            error!("Commissioning FAILED");
+        }
+    }
+}
+
+/// Quick field check that the sensor is wired and healthy: ACK, serial read,
+/// self-test and one raw measurement. Skips conditioning entirely.
+pub async fn commission(bus: &SensorBus) -> CommissionReport {
+    let mut report = CommissionReport {
+        ack: false,
+        serial: None,
+        self_test: None,
+        raw: None,
+    };
+
+    // ── serial number (also proves the sensor ACKs) ──────────────────────
+    match Sgp41::new(bus.lock().await).read_serial_number().await {
+        Ok(serial) => {
+            report.ack = true;
+            report.serial = Some(serial);
+        }
+        // A corrupted frame still means the sensor answered.
+        Err(Sgp41Error::CrcMismatch { .. }) => report.ack = true,
+        Err(_) => return report,
+    }
+
+    report.self_test = self_test(bus).await;
+    report.raw = measure_raw_once(bus, prepare_default_params()).await;
+
+    report
+}
+
+/// Run the on-chip self-test; `None` if the bus failed or the result word
+/// had a bad CRC.
+pub async fn self_test(bus: &SensorBus) -> Option<SelfTestResult> {
+    Sgp41::new(bus.lock().await).execute_self_test().await.ok()
+}
+
+/// One raw measurement (VOC, NOx) with the given compensation params.
+pub async fn measure_raw_once(
+    bus: &SensorBus,
+    params: [u8; 6],
+) -> Option<(u16, u16)> {
+    Sgp41::new(bus.lock().await).measure_raw_signals_with(params).await.ok()
+}
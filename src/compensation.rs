@@ -0,0 +1,126 @@
+use core::cell::Cell;
+use core::sync::atomic::{AtomicU8, Ordering};
+use critical_section::Mutex;
+use embassy_time::{Duration, Instant};
+
+use crate::{encode_ticks, prepare_default_params, temp_hum_ticks, temp_hum_ticks_checked, ParamError};
+
+/// How the SGP41 measure/conditioning commands are compensated.
+#[derive(Copy, Clone, Debug, PartialEq, defmt::Format)]
+pub enum CompensationMode {
+    /// No compensation: send the datasheet default ticks (0x8000 / 0x6666).
+    Default,
+    /// Fixed temperature (°C) and relative humidity (%) values.
+    Fixed { temp_c: f32, humidity_pct: f32 },
+    /// Latest values pushed with `update_live`. Falls back to the defaults
+    /// when no update arrived within `stale_after`.
+    Live { stale_after: Duration },
+}
+
+// Latest live temperature (°C), humidity (%) and when they were set.
+static LIVE: Mutex<Cell<Option<(f32, f32, Instant)>>> = Mutex::new(Cell::new(None));
+
+/// Whether a reading was actually temperature/humidity compensated, carried
+/// on every `MeasurementResult` and in the health snapshot so consumers can
+/// weight accuracy.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, defmt::Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum CompensationState {
+    /// Real values went out: `Fixed`, or `Live` within its staleness window.
+    Compensated = 0,
+    /// Compensation is not configured (`CompensationMode::Default`); the
+    /// datasheet defaults went out.
+    Default = 1,
+    /// `Live` is configured but no update arrived within `stale_after`; the
+    /// defaults went out instead until the source recovers.
+    Stale = 2,
+}
+
+impl CompensationState {
+    /// Classify a reading from the configured mode and the ticks actually
+    /// sent (`CompensationMode::ticks`).
+    pub fn of(mode: &CompensationMode, ticks: Option<(u16, u16)>) -> Self {
+        match (mode, ticks) {
+            (CompensationMode::Default, _) => CompensationState::Default,
+            (_, Some(_)) => CompensationState::Compensated,
+            (_, None) => CompensationState::Stale,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CompensationState::Compensated => "compensated",
+            CompensationState::Default => "default",
+            CompensationState::Stale => "stale",
+        }
+    }
+}
+
+// `CompensationState` of the last `ticks()` call.
+static STATE: AtomicU8 = AtomicU8::new(CompensationState::Default as u8);
+
+/// Record a fresh reading from the temperature/humidity source. Readings
+/// the SGP41 can't take (NaN, infinite or out of range) are dropped, so a
+/// faulty source goes stale instead of sending nonsense ticks.
+pub fn update_live(temp_c: f32, humidity_pct: f32) -> Result<(), ParamError> {
+    temp_hum_ticks_checked(temp_c, humidity_pct)?;
+    critical_section::with(|cs| LIVE.borrow(cs).set(Some((temp_c, humidity_pct, Instant::now()))));
+    Ok(())
+}
+
+/// State of the compensation last sent to the sensor.
+pub fn compensation_state() -> CompensationState {
+    match STATE.load(Ordering::Relaxed) {
+        0 => CompensationState::Compensated,
+        2 => CompensationState::Stale,
+        _ => CompensationState::Default,
+    }
+}
+
+/// Whether the last `params()` call fell back to defaults because the live
+/// source was stale (never true for `Default`/`Fixed`).
+pub fn compensation_degraded() -> bool {
+    compensation_state() == CompensationState::Stale
+}
+
+impl CompensationMode {
+    /// Temperature (°C) and humidity (%) actually being applied, if they come
+    /// from a real source: `None` for `Default` and for stale `Live` values.
+    pub fn temp_humidity(&self) -> Option<(f32, f32)> {
+        match *self {
+            CompensationMode::Default => None,
+            CompensationMode::Fixed {
+                temp_c,
+                humidity_pct,
+            } => Some((temp_c, humidity_pct)),
+            CompensationMode::Live { stale_after } => critical_section::with(|cs| LIVE.borrow(cs).get())
+                .filter(|&(_, _, at)| at.elapsed() <= stale_after)
+                .map(|(temp_c, humidity_pct, _)| (temp_c, humidity_pct)),
+        }
+    }
+
+    /// (humidity, temperature) ticks to send, as produced by
+    /// `prepare_temp_hum_params`; `None` when the defaults go out instead.
+    pub fn ticks(&self) -> Option<(u16, u16)> {
+        let ticks = self
+            .temp_humidity()
+            .map(|(temp_c, humidity_pct)| temp_hum_ticks(temp_c, humidity_pct));
+        STATE.store(CompensationState::of(self, ticks) as u8, Ordering::Relaxed);
+        ticks
+    }
+
+    /// The 6 parameter bytes (two words plus CRCs) appended to a command.
+    pub fn params(&self) -> [u8; 6] {
+        params_for(self.ticks())
+    }
+}
+
+/// Parameter bytes for ticks from `CompensationMode::ticks`, so a caller can
+/// keep the exact ticks it sent.
+pub fn params_for(ticks: Option<(u16, u16)>) -> [u8; 6] {
+    match ticks {
+        Some((humidity_ticks, temp_ticks)) => encode_ticks(humidity_ticks, temp_ticks),
+        None => prepare_default_params(),
+    }
+}
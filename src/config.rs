@@ -0,0 +1,259 @@
+//! Snapshot of the configuration the device is actually running.
+//!
+//! Tasks record their effective settings here when they start (and whenever
+//! a runtime override changes them), so `get_config()` reports what a unit is
+//! really doing rather than compile-time defaults.
+
+use core::cell::RefCell;
+use critical_section::Mutex;
+use defmt::Format;
+use embassy_time::Duration;
+
+use crate::calibration::IndexOffset;
+use crate::compensation::CompensationMode;
+use crate::escalation::EscalationRule;
+use crate::freeze::{DEFAULT_FAILURE_THRESHOLD, DEFAULT_FREEZE_THRESHOLD};
+use crate::led::CombinedAlarm;
+use crate::power_cycle::PowerCycleConfig;
+use crate::reporting::ReportPolicy;
+use crate::run_limit::RunLimit;
+use crate::timing::MAX_CONDITIONING;
+
+/// Sensor timing and fallback compensation, passed to both the conditioning
+/// and the measurement task.
+#[derive(Copy, Clone, Format)]
+pub struct SensorConfig {
+    /// Conditioning length at startup; capped by the datasheet at 10 s.
+    pub conditioning_secs: u8,
+    /// Time between measurements. The gas index algorithms must be built
+    /// with the same interval (`algo::GasIndex::new`).
+    pub measurement_interval: Duration,
+    /// Temperature (°C) and humidity (%) assumed when no live source is
+    /// configured. 25 °C / 50 % sends the datasheet's uncompensated defaults.
+    pub default_temp_c: f32,
+    pub default_humidity_pct: f32,
+}
+
+impl Default for SensorConfig {
+    fn default() -> Self {
+        Self {
+            conditioning_secs: 10,
+            measurement_interval: Duration::from_secs(1),
+            default_temp_c: 25.0,
+            default_humidity_pct: 50.0,
+        }
+    }
+}
+
+impl SensorConfig {
+    /// Compensation for a device without a live temperature/humidity source:
+    /// the datasheet defaults for 25 °C / 50 %, fixed values otherwise.
+    pub fn fallback_compensation(&self) -> CompensationMode {
+        if self.default_temp_c == 25.0 && self.default_humidity_pct == 50.0 {
+            CompensationMode::Default
+        } else {
+            CompensationMode::Fixed {
+                temp_c: self.default_temp_c,
+                humidity_pct: self.default_humidity_pct,
+            }
+        }
+    }
+}
+
+/// Periodic short re-conditioning during normal operation, for very long
+/// deployments.
+///
+/// The datasheet only prescribes conditioning after power-up (at most 10 s,
+/// longer can damage the sensing layer); it does not call for periodic
+/// re-conditioning. This is an opt-in maintenance knob, so keep `duration_secs`
+/// short; it is capped at `timing::MAX_CONDITIONING`.
+///
+/// Index continuity: no samples reach the gas index algorithms while it runs
+/// and their state is kept, not reset. The algorithms assume a fixed sampling
+/// interval, so the pause looks like `duration_secs` missing samples: the
+/// index resumes from where it was, with its baseline slightly less current.
+/// A few seconds once a day is negligible next to the algorithm's hours-long
+/// learning time.
+#[derive(Copy, Clone, PartialEq, Eq, Format)]
+pub struct MaintenanceConditioning {
+    pub every: Duration,
+    pub duration_secs: u8,
+}
+
+impl Default for MaintenanceConditioning {
+    fn default() -> Self {
+        Self {
+            every: Duration::from_secs(24 * 60 * 60),
+            duration_secs: 5,
+        }
+    }
+}
+
+impl MaintenanceConditioning {
+    pub fn capped_secs(&self) -> u8 {
+        self.duration_secs.min(MAX_CONDITIONING.as_secs() as u8)
+    }
+}
+
+/// Everything the measurement task is configured with besides its bus, LED
+/// and algorithms. Start from `Default` and override what differs.
+#[derive(Copy, Clone)]
+pub struct MeasurementConfig {
+    /// `measurement_interval` must match the interval the algorithms were
+    /// built with (`algo::GasIndex::new`).
+    pub sensor: SensorConfig,
+    pub compensation: CompensationMode,
+    pub power_cycle: PowerCycleConfig,
+    /// Sample on wall-clock multiples of the interval once a time source is set.
+    pub align_to_wall_clock: bool,
+    pub escalation: Option<EscalationRule>,
+    pub reporting: ReportPolicy,
+    /// Identical consecutive raw readings before a freeze is flagged (`None` disables).
+    pub freeze_threshold: Option<u16>,
+    /// Consecutive failed measurements before a soft reset and re-conditioning
+    /// (`None` disables; the task then just keeps retrying).
+    pub failure_threshold: Option<u16>,
+    /// Run a soak test for this long after conditioning (`None` disables).
+    pub soak_duration: Option<Duration>,
+    /// Field calibration for this sensor; applied to reported indices only.
+    pub index_offset: IndexOffset,
+    /// Stop after this many measurements or this long (`RunLimit::default()` never stops).
+    pub run_limit: RunLimit,
+    /// LED indication when VOC and NOx alarm together.
+    pub combined_alarm: CombinedAlarm,
+    /// Periodic short re-conditioning (`None` disables).
+    pub maintenance: Option<MaintenanceConditioning>,
+    /// Also report indices as deltas from a rolling baseline over this window
+    /// (`None` disables; see `baseline`).
+    pub rolling_baseline: Option<Duration>,
+}
+
+impl Default for MeasurementConfig {
+    fn default() -> Self {
+        Self {
+            sensor: SensorConfig::default(),
+            compensation: CompensationMode::Default,
+            power_cycle: PowerCycleConfig::default(),
+            align_to_wall_clock: true,
+            escalation: None,
+            reporting: ReportPolicy::default(),
+            freeze_threshold: Some(DEFAULT_FREEZE_THRESHOLD),
+            failure_threshold: Some(DEFAULT_FAILURE_THRESHOLD),
+            soak_duration: None,
+            index_offset: IndexOffset::NONE,
+            run_limit: RunLimit::default(),
+            combined_alarm: CombinedAlarm::default(),
+            maintenance: None,
+            rolling_baseline: None,
+        }
+    }
+}
+
+// Bits of `ConfigSnapshot::features`: cargo features compiled in
+pub const FEATURE_ESP32C6: u8 = 1 << 0;
+pub const FEATURE_ESP32S3: u8 = 1 << 1;
+pub const FEATURE_COMMISSION: u8 = 1 << 2;
+
+pub const fn compiled_features() -> u8 {
+    let mut features = 0;
+    if cfg!(feature = "esp32c6") {
+        features |= FEATURE_ESP32C6;
+    }
+    if cfg!(feature = "esp32s3") {
+        features |= FEATURE_ESP32S3;
+    }
+    if cfg!(feature = "commission") {
+        features |= FEATURE_COMMISSION;
+    }
+    features
+}
+
+#[derive(Copy, Clone, Format)]
+pub struct ConfigSnapshot {
+    pub measurement_interval_ms: u32,
+    pub conditioning_secs: u8,
+    /// Limit on the conditioning phase before it aborts into `Fault`.
+    pub conditioning_timeout_s: u32,
+    pub align_to_wall_clock: bool,
+    pub escalation: Option<EscalationRule>,
+    pub compensation: CompensationMode,
+    pub reporting: Option<ReportPolicy>,
+    /// Length (s) of the soak test currently running, if any.
+    pub soak_duration_s: Option<u32>,
+    /// Whether the on-chip self-test ran at boot.
+    pub startup_self_test: bool,
+    /// Measurement count after which the run stops (`None` = unbounded).
+    pub max_measurements: Option<u32>,
+    /// Per-device offset added to the reported indices.
+    pub index_offset: IndexOffset,
+    /// Rolling-baseline window (s) for delta reporting, if enabled.
+    pub baseline_window_s: Option<u32>,
+    pub features: u8,
+}
+
+/// Length of the BLE config characteristic value.
+pub const CONFIG_BLE_LEN: usize = 13;
+
+impl ConfigSnapshot {
+    const DEFAULT: Self = Self {
+        measurement_interval_ms: 1000,
+        conditioning_secs: 10,
+        conditioning_timeout_s: 30,
+        align_to_wall_clock: false,
+        escalation: None,
+        compensation: CompensationMode::Default,
+        reporting: None,
+        soak_duration_s: None,
+        startup_self_test: true,
+        max_measurements: None,
+        index_offset: IndexOffset::NONE,
+        baseline_window_s: None,
+        features: compiled_features(),
+    };
+
+    /// Little-endian value of the BLE config characteristic:
+    ///
+    /// | offset | size | field                                         |
+    /// |--------|------|-----------------------------------------------|
+    /// | 0      | 4    | measurement interval (ms)                     |
+    /// | 4      | 1    | conditioning duration (s)                     |
+    /// | 5      | 1    | flags: bit0 wall-clock aligned, bit1 escalation enabled, bit2 compensated |
+    /// | 6      | 2    | escalation threshold (0 if disabled)          |
+    /// | 8      | 4    | escalation sustained-for (s, 0 if disabled)   |
+    /// | 12     | 1    | compiled feature bits (`FEATURE_*`)           |
+    pub fn to_ble_bytes(&self) -> [u8; CONFIG_BLE_LEN] {
+        let mut out = [0u8; CONFIG_BLE_LEN];
+        out[0..4].copy_from_slice(&self.measurement_interval_ms.to_le_bytes());
+        out[4] = self.conditioning_secs;
+        let mut flags = 0u8;
+        if self.align_to_wall_clock {
+            flags |= 1 << 0;
+        }
+        if self.escalation.is_some() {
+            flags |= 1 << 1;
+        }
+        if self.compensation != CompensationMode::Default {
+            flags |= 1 << 2;
+        }
+        out[5] = flags;
+        if let Some(rule) = self.escalation {
+            out[6..8].copy_from_slice(&(rule.threshold.clamp(0, u16::MAX as i32) as u16).to_le_bytes());
+            out[8..12].copy_from_slice(&(rule.sustained_for.as_secs() as u32).to_le_bytes());
+        }
+        out[12] = self.features;
+        out
+    }
+}
+
+static ACTIVE_CONFIG: Mutex<RefCell<ConfigSnapshot>> =
+    Mutex::new(RefCell::new(ConfigSnapshot::DEFAULT));
+
+/// The configuration currently in effect.
+pub fn get_config() -> ConfigSnapshot {
+    critical_section::with(|cs| *ACTIVE_CONFIG.borrow_ref(cs))
+}
+
+/// Record a change to the effective configuration.
+pub fn update_config(f: impl FnOnce(&mut ConfigSnapshot)) {
+    critical_section::with(|cs| f(&mut ACTIVE_CONFIG.borrow_ref_mut(cs)));
+}
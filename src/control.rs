@@ -0,0 +1,80 @@
+//! Runtime control commands (from BLE writes, buttons, RTT, ...) handled by
+//! the measurement task between samples.
+
+use defmt::Format;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+
+use crate::algo::STATE_LEN;
+use crate::calibration::IndexOffset;
+use crate::escalation::Gas;
+
+#[derive(Copy, Clone, Format)]
+pub enum ControlCommand {
+    /// Log the active configuration snapshot.
+    DumpConfig,
+    /// Log both algorithms' internal state as hex (see `algo::state_to_hex`).
+    ExportAlgorithmState,
+    /// Seed one algorithm with a previously exported state.
+    ImportAlgorithmState { gas: Gas, state: [u8; STATE_LEN] },
+    /// Reset both algorithms and re-condition (re-baseline after moving the device).
+    CleanAirReset { recondition_secs: u8 },
+    /// Start (or restart) a soak test of the given length.
+    StartSoak { duration_s: u32 },
+    /// Re-read and log the sensor serial number with its CRC result, e.g.
+    /// to confirm which sensor is fitted after a swap.
+    ReadSerial,
+    /// Apply a per-device index offset to this sensor's readings and, with
+    /// `persistence`, keep it in flash for this serial.
+    SetCalibration { offset: IndexOffset },
+}
+
+pub static CONTROL: Channel<CriticalSectionRawMutex, ControlCommand, 4> = Channel::new();
+
+/// Length of the BLE control characteristic value.
+pub const CONTROL_BLE_LEN: usize = 2 + STATE_LEN;
+
+impl ControlCommand {
+    /// Decode a write to the BLE control characteristic: an opcode byte and
+    /// its little-endian arguments, zero-padded to `CONTROL_BLE_LEN`.
+    ///
+    /// | opcode | command                | arguments                           |
+    /// |--------|------------------------|-------------------------------------|
+    /// | 0x01   | `DumpConfig`           |                                     |
+    /// | 0x02   | `ExportAlgorithmState` |                                     |
+    /// | 0x03   | `ImportAlgorithmState` | gas (0 VOC, 1 NOx), state (8 bytes) |
+    /// | 0x04   | `CleanAirReset`        | recondition (s, `u8`)               |
+    /// | 0x05   | `StartSoak`            | duration (s, `u32`)                 |
+    /// | 0x06   | `ReadSerial`           |                                     |
+    /// | 0x07   | `SetCalibration`       | VOC offset, NOx offset (`i16` each) |
+    ///
+    /// `None` for an unknown opcode or gas.
+    pub fn from_ble_bytes(bytes: &[u8; CONTROL_BLE_LEN]) -> Option<Self> {
+        Some(match bytes[0] {
+            0x01 => ControlCommand::DumpConfig,
+            0x02 => ControlCommand::ExportAlgorithmState,
+            0x03 => {
+                let gas = match bytes[1] {
+                    0 => Gas::Voc,
+                    1 => Gas::Nox,
+                    _ => return None,
+                };
+                let mut state = [0u8; STATE_LEN];
+                state.copy_from_slice(&bytes[2..]);
+                ControlCommand::ImportAlgorithmState { gas, state }
+            }
+            0x04 => ControlCommand::CleanAirReset { recondition_secs: bytes[1] },
+            0x05 => ControlCommand::StartSoak {
+                duration_s: u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]),
+            },
+            0x06 => ControlCommand::ReadSerial,
+            0x07 => ControlCommand::SetCalibration {
+                offset: IndexOffset {
+                    voc: i16::from_le_bytes([bytes[1], bytes[2]]),
+                    nox: i16::from_le_bytes([bytes[3], bytes[4]]),
+                },
+            },
+            _ => return None,
+        })
+    }
+}
@@ -0,0 +1,93 @@
+//! VOC vs CO₂ cross-validation (feature `co2-crosscheck`).
+//!
+//! Requires a second sensor, an SCD4x, on the same I²C bus; see
+//! `tasks::crosscheck`. Without it the task logs a read failure and no
+//! indicator is ever published.
+//!
+//! Rule: over each `window`, compare the change in VOC index with the change
+//! in CO₂. People raise both together, so a rise in only one of them is
+//! flagged: VOC alone points at a non-human source (solvents, cooking) or a
+//! VOC sensor fault, CO₂ alone at a VOC sensor that stopped responding.
+
+use core::sync::atomic::{AtomicI32, Ordering};
+use defmt::Format;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant};
+
+/// Latest VOC index from the measurement task; 0 until the first sample.
+static LATEST_VOC_INDEX: AtomicI32 = AtomicI32::new(0);
+
+/// Combined indicator, published at the end of every window.
+pub static CROSSCHECK: Signal<CriticalSectionRawMutex, CrossCheck> = Signal::new();
+
+pub fn record_voc_index(voc_index: i32) {
+    LATEST_VOC_INDEX.store(voc_index, Ordering::Relaxed);
+}
+
+pub fn latest_voc_index() -> i32 {
+    LATEST_VOC_INDEX.load(Ordering::Relaxed)
+}
+
+#[derive(Copy, Clone, Format)]
+pub struct DivergenceRule {
+    pub window: Duration,
+    /// VOC index increase that counts as a rise.
+    pub voc_rise: i32,
+    /// CO₂ increase (ppm) that counts as a rise.
+    pub co2_rise_ppm: i32,
+}
+
+impl Default for DivergenceRule {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(300),
+            voc_rise: 50,
+            co2_rise_ppm: 200,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Format)]
+pub enum CrossCheck {
+    /// Neither rose.
+    Steady,
+    /// Both rose: consistent, most likely occupancy.
+    Both,
+    /// VOC rose with CO₂ flat.
+    VocOnly,
+    /// CO₂ rose with VOC flat.
+    Co2Only,
+}
+
+pub struct DivergenceMonitor {
+    rule: DivergenceRule,
+    start: Option<(Instant, i32, u16)>,
+}
+
+impl DivergenceMonitor {
+    pub fn new(rule: DivergenceRule) -> Self {
+        Self { rule, start: None }
+    }
+
+    /// Feed one paired sample; returns the verdict once per elapsed window.
+    pub fn update(&mut self, voc_index: i32, co2_ppm: u16, now: Instant) -> Option<CrossCheck> {
+        let Some((started, voc0, co2_0)) = self.start else {
+            self.start = Some((now, voc_index, co2_ppm));
+            return None;
+        };
+        if now - started < self.rule.window {
+            return None;
+        }
+        self.start = Some((now, voc_index, co2_ppm));
+
+        let voc_up = voc_index - voc0 >= self.rule.voc_rise;
+        let co2_up = co2_ppm as i32 - co2_0 as i32 >= self.rule.co2_rise_ppm;
+        Some(match (voc_up, co2_up) {
+            (false, false) => CrossCheck::Steady,
+            (true, true) => CrossCheck::Both,
+            (true, false) => CrossCheck::VocOnly,
+            (false, true) => CrossCheck::Co2Only,
+        })
+    }
+}
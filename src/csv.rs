@@ -0,0 +1,92 @@
+//! CSV schema shared by every text log of readings (SD card, serial).
+//!
+//! Columns, one row per measurement:
+//!
+//! | column      | unit / format                                   |
+//! |-------------|-------------------------------------------------|
+//! | `uptime_ms` | ms since boot                                   |
+//! | `unix_ms`   | Unix time (ms); empty until a time source is set |
+//! | `voc_raw`   | SGP41 VOC raw ticks                              |
+//! | `nox_raw`   | SGP41 NOx raw ticks                              |
+//! | `voc_index` | gas index 1–500 (0 during the initial blackout)  |
+//! | `nox_index` | gas index 1–500 (0 during the initial blackout)  |
+//! | `compensation` | `compensated`, `default` or `stale` (`CompensationState`) |
+
+use core::fmt::Write;
+
+use crate::readings::Measurement;
+use crate::wall_clock::unix_ms_at;
+
+pub const CSV_HEADER: &str = "uptime_ms,unix_ms,voc_raw,nox_raw,voc_index,nox_index,compensation\n";
+
+/// Longest possible row: 20 + 20 digits for the times, 5 + 5 for the raw
+/// ticks, 11 + 11 for the indices, 11 for the compensation label, 6 commas
+/// and the newline.
+pub const CSV_ROW_MAX: usize = 90;
+
+pub struct CsvRow {
+    /// Wall time of the sample; `None` while no time source is set.
+    pub unix_ms: Option<u64>,
+    pub reading: Measurement,
+}
+
+impl CsvRow {
+    /// Row for a published reading, stamped with the wall time it was taken
+    /// at if the clock has been set.
+    pub fn new(reading: Measurement) -> Self {
+        Self {
+            unix_ms: unix_ms_at(reading.timestamp_ms),
+            reading,
+        }
+    }
+
+    /// Format the row (with trailing newline) into `buf`.
+    pub fn format<'a>(&self, buf: &'a mut [u8; CSV_ROW_MAX]) -> &'a [u8] {
+        let mut cursor = Cursor::new(buf);
+        let r = &self.reading;
+        // Cannot fail: `CSV_ROW_MAX` covers the widest row.
+        let _ = write!(cursor, "{},", r.timestamp_ms);
+        if let Some(unix_ms) = self.unix_ms {
+            let _ = write!(cursor, "{}", unix_ms);
+        }
+        let _ = writeln!(
+            cursor,
+            ",{},{},{},{},{}",
+            r.voc_raw,
+            r.nox_raw,
+            r.voc_index,
+            r.nox_index,
+            r.compensation.label()
+        );
+        cursor.into_bytes()
+    }
+}
+
+/// `fmt::Write` into a fixed buffer; fails instead of truncating.
+pub(crate) struct Cursor<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    pub(crate) fn into_bytes(self) -> &'a [u8] {
+        let Cursor { buf, len } = self;
+        &buf[..len]
+    }
+}
+
+impl Write for Cursor<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            return Err(core::fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+//! SGP41 command sequences in one place: command bytes, the wait before
+//! reading, and CRC validation of every response.
+//!
+//! The driver owns its I²C handle. Tasks share the bus behind a mutex, so
+//! they lend it for one command: `Sgp41::new(sensor.lock().await)` (see
+//! `mux::SensorBus`, which also routes a muxed sensor's channel). The lock is
+//! held across the command's wait, keeping the write/read pair atomic.
+//!
+//! `Sgp41Async` is the same driver over `embedded-hal-async` I²C (e.g.
+//! `hal::AsyncI2cCompat`): transfers are awaited instead of blocking the
+//! executor. Both are `Driver` over a `Transport`, so each command sequence
+//! exists once.
+
+use core::future::Future;
+
+use defmt::Format;
+use embassy_time::Timer;
+use embedded_hal_02::blocking::i2c::{Read, Write};
+use embedded_hal_async::i2c::I2c as AsyncI2c;
+
+use crate::tasks::conditioning::{
+    CMD_EXECUTE_CONDITIONING, CMD_EXECUTE_SELF_TEST, CMD_GET_FEATURE_SET, CMD_GET_SERIAL_NUMBER,
+    CMD_MEASURE_RAW_SIGNALS, CMD_SOFT_RESET, CMD_TURN_HEATER_OFF, GENERAL_CALL_ADDR, SGP41_ADDR,
+};
+use crate::timing::{
+    CONDITIONING_TIME, FEATURE_SET_TIME, HEATER_OFF_TIME, MEASURE_RAW_TIME, SELF_TEST_TIME,
+    SERIAL_NUMBER_TIME, SOFT_RESET_TIME,
+};
+use crate::{calculate_crc, check_word, prepare_temp_hum_params};
+
+/// Why a driver call failed, so callers can tell a transient bus glitch from
+/// a corrupted frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub enum Sgp41Error<E> {
+    /// The I²C transfer itself failed (NACK, arbitration loss, timeout, ...).
+    I2c(E),
+    /// A response word arrived but its CRC byte didn't match: `expected` is
+    /// the CRC computed over the received word, `got` the byte on the wire.
+    CrcMismatch { expected: u8, got: u8 },
+    /// The on-chip self-test reported a failed pixel.
+    SelfTestFailed,
+}
+
+// Self-test word failure flags (bit set = pixel failed)
+const SELF_TEST_VOC_FAILED: u16 = 1 << 0;
+const SELF_TEST_NOX_FAILED: u16 = 1 << 1;
+
+/// Decoded on-chip self-test result, one flag per sensing pixel.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub struct SelfTestResult {
+    pub voc_ok: bool,
+    pub nox_ok: bool,
+}
+
+impl SelfTestResult {
+    /// Decode the self-test word: the low two bits flag VOC and NOx pixel
+    /// failures, the upper bits are reserved.
+    pub fn from_word(word: u16) -> Self {
+        Self {
+            voc_ok: word & SELF_TEST_VOC_FAILED == 0,
+            nox_ok: word & SELF_TEST_NOX_FAILED == 0,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.voc_ok && self.nox_ok
+    }
+
+    /// `Err(SelfTestFailed)` unless both pixels passed.
+    pub fn check<E>(self) -> Result<(), Sgp41Error<E>> {
+        if self.passed() {
+            Ok(())
+        } else {
+            Err(Sgp41Error::SelfTestFailed)
+        }
+    }
+}
+
+/// Decoded feature set word: product type in bits 15..12, firmware
+/// (product) version in bits 7..0. Bits 11..8 are reserved.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub struct FeatureSet {
+    pub product_type: u8,
+    pub firmware_version: u8,
+}
+
+impl FeatureSet {
+    pub fn from_word(word: u16) -> Self {
+        Self {
+            product_type: (word >> 12) as u8,
+            firmware_version: word as u8,
+        }
+    }
+}
+
+/// How the command sequences reach the bus: blocking `embedded-hal` 0.2 I²C
+/// (`Blocking`) or `embedded-hal-async` I²C (`Async`). Every command is
+/// written once, in `Driver`, against this trait.
+pub trait Transport {
+    type Error;
+    fn write(&mut self, address: u8, bytes: &[u8]) -> impl Future<Output = Result<(), Self::Error>>;
+    fn read(&mut self, address: u8, buf: &mut [u8]) -> impl Future<Output = Result<(), Self::Error>>;
+}
+
+/// Blocking I²C; each transfer completes before the future is first polled.
+pub struct Blocking<I2C>(I2C);
+
+impl<I2C, E> Transport for Blocking<I2C>
+where
+    I2C: Read<Error = E> + Write<Error = E>,
+{
+    type Error = E;
+
+    async fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), E> {
+        self.0.write(address, bytes)
+    }
+
+    async fn read(&mut self, address: u8, buf: &mut [u8]) -> Result<(), E> {
+        self.0.read(address, buf)
+    }
+}
+
+/// `embedded-hal-async` I²C; transfers yield to other tasks.
+pub struct Async<I2C>(I2C);
+
+impl<I2C: AsyncI2c> Transport for Async<I2C> {
+    type Error = I2C::Error;
+
+    async fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), I2C::Error> {
+        self.0.write(address, bytes).await
+    }
+
+    async fn read(&mut self, address: u8, buf: &mut [u8]) -> Result<(), I2C::Error> {
+        self.0.read(address, buf).await
+    }
+}
+
+pub struct Driver<T> {
+    bus: T,
+    address: u8,
+}
+
+/// The driver over blocking I²C, as the tasks use it.
+pub type Sgp41<I2C> = Driver<Blocking<I2C>>;
+
+/// The driver over `embedded-hal-async` I²C; same commands, waits and CRC
+/// checks.
+pub type Sgp41Async<I2C> = Driver<Async<I2C>>;
+
+impl<I2C, E> Sgp41<I2C>
+where
+    I2C: Read<Error = E> + Write<Error = E>,
+{
+    /// Driver for a sensor at the default address (`SGP41_ADDR`).
+    pub fn new(i2c: I2C) -> Self {
+        Self::with_address(i2c, SGP41_ADDR)
+    }
+
+    pub fn with_address(i2c: I2C, address: u8) -> Self {
+        Self {
+            bus: Blocking(i2c),
+            address,
+        }
+    }
+
+    /// Give the I²C handle back.
+    pub fn release(self) -> I2C {
+        self.bus.0
+    }
+}
+
+impl<I2C: AsyncI2c> Sgp41Async<I2C> {
+    /// Driver for a sensor at the default address (`SGP41_ADDR`).
+    pub fn new(i2c: I2C) -> Self {
+        Self::with_address(i2c, SGP41_ADDR)
+    }
+
+    pub fn with_address(i2c: I2C, address: u8) -> Self {
+        Self {
+            bus: Async(i2c),
+            address,
+        }
+    }
+
+    /// Give the I²C handle back.
+    pub fn release(self) -> I2C {
+        self.bus.0
+    }
+}
+
+impl<T: Transport> Driver<T> {
+    /// Measure with temperature (°C) and humidity (%) compensation; returns
+    /// (VOC, NOx) raw ticks.
+    pub async fn measure_raw_signals(
+        &mut self,
+        temp_c: f32,
+        humidity_pct: f32,
+    ) -> Result<(u16, u16), Sgp41Error<T::Error>> {
+        self.measure_raw_signals_with(prepare_temp_hum_params(temp_c, humidity_pct)).await
+    }
+
+    /// Measure with pre-encoded compensation params (e.g. from
+    /// `CompensationMode::params`, which may be the uncompensated defaults).
+    pub async fn measure_raw_signals_with(&mut self, params: [u8; 6]) -> Result<(u16, u16), Sgp41Error<T::Error>> {
+        self.command(CMD_MEASURE_RAW_SIGNALS, params).await?;
+        Timer::after(MEASURE_RAW_TIME).await;
+        let [voc_raw, nox_raw] = self.read_words::<2, 6>().await?;
+        Ok((voc_raw, nox_raw))
+    }
+
+    /// One conditioning step; returns the VOC raw ticks it produced (NOx is
+    /// not measured while conditioning).
+    pub async fn execute_conditioning(&mut self, params: [u8; 6]) -> Result<u16, Sgp41Error<T::Error>> {
+        self.command(CMD_EXECUTE_CONDITIONING, params).await?;
+        Timer::after(CONDITIONING_TIME).await;
+        let [voc_raw] = self.read_words::<1, 3>().await?;
+        Ok(voc_raw)
+    }
+
+    /// Switch the hotplate off; the next measure or conditioning command
+    /// turns it back on.
+    pub async fn turn_heater_off(&mut self) -> Result<(), Sgp41Error<T::Error>> {
+        self.write(self.address, &CMD_TURN_HEATER_OFF).await?;
+        Timer::after(HEATER_OFF_TIME).await;
+        Ok(())
+    }
+
+    /// Run the on-chip self-test (~320 ms, heater on) and decode the result.
+    pub async fn execute_self_test(&mut self) -> Result<SelfTestResult, Sgp41Error<T::Error>> {
+        self.write(self.address, &CMD_EXECUTE_SELF_TEST).await?;
+        Timer::after(SELF_TEST_TIME).await;
+        let [word] = self.read_words::<1, 3>().await?;
+        Ok(SelfTestResult::from_word(word))
+    }
+
+    /// The sensor's 48-bit serial number as three words, each CRC-checked.
+    pub async fn read_serial_number(&mut self) -> Result<[u16; 3], Sgp41Error<T::Error>> {
+        self.write(self.address, &CMD_GET_SERIAL_NUMBER).await?;
+        Timer::after(SERIAL_NUMBER_TIME).await;
+        self.read_words::<3, 9>().await
+    }
+
+    /// Product type and firmware version, CRC-checked. Worth logging next to
+    /// the serial number to spot a different sensor variant in the field.
+    pub async fn get_feature_set(&mut self) -> Result<FeatureSet, Sgp41Error<T::Error>> {
+        self.write(self.address, &CMD_GET_FEATURE_SET).await?;
+        Timer::after(FEATURE_SET_TIME).await;
+        let [word] = self.read_words::<1, 3>().await?;
+        Ok(FeatureSet::from_word(word))
+    }
+
+    /// Reset via the I²C general call (`0x06` to `GENERAL_CALL_ADDR`, not the
+    /// sensor's own address), then wait for it to come back. Every device on
+    /// the bus that honors the general call resets too. The heater is off
+    /// afterwards, so re-condition before trusting NOx readings again.
+    pub async fn soft_reset(&mut self) -> Result<(), Sgp41Error<T::Error>> {
+        self.write(GENERAL_CALL_ADDR, &CMD_SOFT_RESET).await?;
+        Timer::after(SOFT_RESET_TIME).await;
+        Ok(())
+    }
+
+    // Send a 2-byte command followed by its 6 parameter bytes.
+    async fn command(&mut self, cmd: [u8; 2], params: [u8; 6]) -> Result<(), Sgp41Error<T::Error>> {
+        self.write(self.address, &command_frame(cmd, params)).await
+    }
+
+    async fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Sgp41Error<T::Error>> {
+        self.bus.write(address, bytes).await.map_err(Sgp41Error::I2c)
+    }
+
+    // Read `N` CRC-protected words (`LEN` = 3 * N bytes).
+    async fn read_words<const N: usize, const LEN: usize>(&mut self) -> Result<[u16; N], Sgp41Error<T::Error>> {
+        let mut buf = [0u8; LEN];
+        self.bus.read(self.address, &mut buf).await.map_err(Sgp41Error::I2c)?;
+        check_words(&buf)
+    }
+}
+
+// A 2-byte command followed by its 6 parameter bytes.
+fn command_frame(cmd: [u8; 2], params: [u8; 6]) -> [u8; 8] {
+    let mut frame = [0u8; 8];
+    frame[0..2].copy_from_slice(&cmd);
+    frame[2..8].copy_from_slice(&params);
+    frame
+}
+
+// Decode `N` CRC-protected words from `buf` (3 * N bytes).
+fn check_words<const N: usize, E>(buf: &[u8]) -> Result<[u16; N], Sgp41Error<E>> {
+    let mut words = [0u16; N];
+    for (word, chunk) in words.iter_mut().zip(buf.chunks_exact(3)) {
+        *word = check_word(&[chunk[0], chunk[1], chunk[2]]).ok_or_else(|| Sgp41Error::CrcMismatch {
+            expected: calculate_crc(&chunk[0..2]),
+            got: chunk[2],
+        })?;
+    }
+    Ok(words)
+}
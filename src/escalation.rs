@@ -0,0 +1,113 @@
+//! Escalation when air quality stays poor for a sustained period.
+//!
+//! A rule fires once the index has stayed at or above `threshold` for
+//! `sustained_for`. It then stays active until the index drops below
+//! `clear_below`, which is set lower than `threshold` (hysteresis) so a value
+//! hovering around the threshold doesn't toggle a fan relay on and off.
+
+use defmt::Format;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant};
+
+/// Desired state of the fan relay output, consumed by `relay_task`.
+pub static FAN_RELAY: Signal<CriticalSectionRawMutex, bool> = Signal::new();
+
+#[derive(Copy, Clone, PartialEq, Eq, Format)]
+pub enum Gas {
+    Voc,
+    Nox,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Format)]
+pub enum EscalationAction {
+    /// Drive the fan relay GPIO while the rule is active.
+    FanRelay,
+    /// Show the LED alarm (fast red blink) while the rule is active.
+    LedAlarm,
+}
+
+#[derive(Copy, Clone, Format)]
+pub struct EscalationRule {
+    pub gas: Gas,
+    pub threshold: i32,
+    pub clear_below: i32,
+    pub sustained_for: Duration,
+    pub action: EscalationAction,
+}
+
+impl Default for EscalationRule {
+    /// Sustained poor VOC; switches the fan on `fan-relay` builds and shows
+    /// the LED alarm otherwise.
+    fn default() -> Self {
+        Self {
+            gas: Gas::Voc,
+            threshold: 250,
+            clear_below: 200,
+            sustained_for: Duration::from_secs(10 * 60),
+            action: if cfg!(feature = "fan-relay") {
+                EscalationAction::FanRelay
+            } else {
+                EscalationAction::LedAlarm
+            },
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Format)]
+pub enum EscalationEvent {
+    Fired,
+    Cleared,
+}
+
+pub struct SustainedMonitor {
+    rule: EscalationRule,
+    above_since: Option<Instant>,
+    active: bool,
+}
+
+impl SustainedMonitor {
+    pub fn new(rule: EscalationRule) -> Self {
+        Self {
+            rule,
+            above_since: None,
+            active: false,
+        }
+    }
+
+    pub fn rule(&self) -> &EscalationRule {
+        &self.rule
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Feed the latest indices; returns an event on a state change.
+    pub fn update(&mut self, voc_index: i32, nox_index: i32, now: Instant) -> Option<EscalationEvent> {
+        let index = match self.rule.gas {
+            Gas::Voc => voc_index,
+            Gas::Nox => nox_index,
+        };
+
+        if self.active {
+            if index < self.rule.clear_below {
+                self.active = false;
+                self.above_since = None;
+                return Some(EscalationEvent::Cleared);
+            }
+            return None;
+        }
+
+        if index < self.rule.threshold {
+            self.above_since = None;
+            return None;
+        }
+        let since = *self.above_since.get_or_insert(now);
+        if now.saturating_duration_since(since) >= self.rule.sustained_for {
+            self.active = true;
+            return Some(EscalationEvent::Fired);
+        }
+        None
+    }
+}
@@ -0,0 +1,63 @@
+/// Mean of the last `N` samples, kept in a ring buffer.
+///
+/// Used to steady the LED color near a threshold. It smooths the index for
+/// display only: the gas index algorithm needs every raw sample at its real
+/// cadence, so raw ticks are never filtered before it.
+#[derive(Copy, Clone, Debug, defmt::Format)]
+pub struct MovingAverage<const N: usize> {
+    samples: [i32; N],
+    next: usize,
+    count: usize,
+    sum: i64,
+}
+
+impl<const N: usize> MovingAverage<N> {
+    pub const fn new() -> Self {
+        Self {
+            samples: [0; N],
+            next: 0,
+            count: 0,
+            sum: 0,
+        }
+    }
+
+    /// Add a sample, dropping the oldest once `N` are held, and return the
+    /// new average.
+    pub fn push(&mut self, value: i32) -> i32 {
+        if self.count == N {
+            self.sum -= self.samples[self.next] as i64;
+        } else {
+            self.count += 1;
+        }
+        self.samples[self.next] = value;
+        self.sum += value as i64;
+        self.next = (self.next + 1) % N;
+        self.average().unwrap_or(value)
+    }
+
+    /// Rounded mean of the held samples; `None` when empty.
+    pub fn average(&self) -> Option<i32> {
+        if self.count == 0 {
+            return None;
+        }
+        let n = self.count as i64;
+        // Round half away from zero.
+        let half = if self.sum < 0 { -n / 2 } else { n / 2 };
+        Some(((self.sum + half) / n) as i32)
+    }
+
+    /// Number of samples currently averaged (at most `N`).
+    pub fn sample_count(&self) -> usize {
+        self.count
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl<const N: usize> Default for MovingAverage<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,73 @@
+//! Detects a frozen sensor: the exact same raw ticks returned many times in a
+//! row (e.g. the bus returning cached data) even though every frame has a
+//! valid CRC. Live SGP41 raw signals jitter by a few ticks every sample, so a
+//! long run of identical values is a strong freeze indicator.
+//!
+//! `FailureStreak` covers the other way a sensor wedges: every transfer
+//! failing (NACK, bad CRC) instead of returning stale data.
+
+/// Default number of identical consecutive readings that flags a freeze.
+pub const DEFAULT_FREEZE_THRESHOLD: u16 = 30;
+
+/// Default number of consecutive failed measurements before recovery.
+pub const DEFAULT_FAILURE_THRESHOLD: u16 = 10;
+
+pub struct FreezeDetector {
+    threshold: u16,
+    last: Option<(u16, u16)>,
+    count: u16,
+}
+
+impl FreezeDetector {
+    pub fn new(threshold: u16) -> Self {
+        Self {
+            threshold,
+            last: None,
+            count: 0,
+        }
+    }
+
+    /// Feed one raw sample; returns the run length once it reaches the threshold.
+    pub fn update(&mut self, voc_raw: u16, nox_raw: u16) -> Option<u16> {
+        if self.last == Some((voc_raw, nox_raw)) {
+            self.count = self.count.saturating_add(1);
+        } else {
+            self.last = Some((voc_raw, nox_raw));
+            self.count = 1;
+        }
+        (self.count >= self.threshold).then_some(self.count)
+    }
+
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.count = 0;
+    }
+}
+
+/// Counts consecutive failed measurements; any success clears the streak.
+pub struct FailureStreak {
+    threshold: u16,
+    count: u16,
+}
+
+impl FailureStreak {
+    pub fn new(threshold: u16) -> Self {
+        Self { threshold, count: 0 }
+    }
+
+    /// Record a failure; returns the streak length once it reaches the
+    /// threshold and starts counting again, so recovery runs once per streak.
+    pub fn record_failure(&mut self) -> Option<u16> {
+        self.count = self.count.saturating_add(1);
+        if self.count < self.threshold {
+            return None;
+        }
+        let count = self.count;
+        self.count = 0;
+        Some(count)
+    }
+
+    pub fn record_success(&mut self) {
+        self.count = 0;
+    }
+}
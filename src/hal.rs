@@ -4,24 +4,58 @@
 // Simple shim that lets an `embedded-hal 1.0` I²C implementation satisfy the
 // *blocking* traits from `embedded-hal 0.2` (needed by SGP41).
 
+use defmt::{info, warn};
+use embassy_time::Timer;
 use embedded_hal_02::blocking::i2c::{Read, Write, WriteRead};
-use esp_hal::i2c::master::I2c;
+use embedded_hal_async::i2c::{ErrorType, I2c as AsyncI2c, Operation};
+use esp_hal::delay::Delay;
+use esp_hal::i2c::master::{Config as I2cConfig, I2c};
+use esp_hal::time::Rate;
+
+use crate::decode_words;
+use crate::tasks::conditioning::{CMD_GET_SERIAL_NUMBER, SGP41_ADDR};
+use crate::timing::SERIAL_NUMBER_TIME;
 
 pub type HalI2c<'a> = I2c<'a, esp_hal::Blocking>;
+pub type HalI2cAsync<'a> = I2c<'a, esp_hal::Async>;
+
+/// Upper bound for the pre-command settle delay. Keeps the added time per
+/// measurement cycle around 1% of the 1 s sampling interval the gas index
+/// algorithm assumes.
+pub const MAX_SETTLE_US: u32 = 10_000;
 
 pub struct I2cCompat<'a> {
     pub inner: &'a mut HalI2c<'a>,
+    settle_us: u32,
 }
 
 impl<'a> I2cCompat<'a> {
     pub fn new(inner: &'a mut HalI2c<'a>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            settle_us: 0,
+        }
+    }
+
+    /// Busy-wait `us` microseconds before every command (write or
+    /// write-read). Off (0) by default; only boards with marginal bus timing
+    /// after a wake or bus recovery need it. Clamped to `MAX_SETTLE_US`.
+    pub fn with_settle_delay_us(mut self, us: u32) -> Self {
+        self.settle_us = us.min(MAX_SETTLE_US);
+        self
+    }
+
+    fn settle(&self) {
+        if self.settle_us > 0 {
+            Delay::new().delay_micros(self.settle_us);
+        }
     }
 }
 
 impl<'a> Write for I2cCompat<'a> {
     type Error = esp_hal::i2c::master::Error;
     fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.settle();
         self.inner.write(addr, bytes)
     }
 }
@@ -36,7 +70,117 @@ impl<'a> Read for I2cCompat<'a> {
 impl<'a> WriteRead for I2cCompat<'a> {
     type Error = esp_hal::i2c::master::Error;
     fn write_read(&mut self, addr: u8, bytes: &[u8], buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.settle();
         self.inner.write_read(addr, bytes, buf)
     }
 }
+
+// Lend a locked bus to a driver that owns its handle: `Sgp41::new(&mut *guard)`.
+impl<'a> Write for &mut I2cCompat<'a> {
+    type Error = esp_hal::i2c::master::Error;
+    fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        (**self).write(addr, bytes)
+    }
+}
+
+impl<'a> Read for &mut I2cCompat<'a> {
+    type Error = esp_hal::i2c::master::Error;
+    fn read(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        (**self).read(addr, buf)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Async counterpart: `embedded-hal-async` I²C over esp-hal's async driver, so
+// a transfer yields to other tasks (BLE, WiFi) instead of blocking the
+// executor. Pairs with `driver::Sgp41Async`; the blocking shim above stays
+// for everything that still uses `Sgp41`.
+
+pub struct AsyncI2cCompat<'a> {
+    pub inner: &'a mut HalI2cAsync<'a>,
+    settle_us: u32,
+}
+
+impl<'a> AsyncI2cCompat<'a> {
+    pub fn new(inner: &'a mut HalI2cAsync<'a>) -> Self {
+        Self {
+            inner,
+            settle_us: 0,
+        }
+    }
+
+    /// Same as `I2cCompat::with_settle_delay_us`, but the wait is an async
+    /// timer rather than a busy-wait.
+    pub fn with_settle_delay_us(mut self, us: u32) -> Self {
+        self.settle_us = us.min(MAX_SETTLE_US);
+        self
+    }
+}
+
+impl<'a> ErrorType for AsyncI2cCompat<'a> {
+    type Error = esp_hal::i2c::master::Error;
+}
+
+impl<'a> AsyncI2c for AsyncI2cCompat<'a> {
+    async fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        // Settle before commands only, as the blocking shim does.
+        if self.settle_us > 0 && matches!(operations.first(), Some(Operation::Write(_))) {
+            Timer::after_micros(self.settle_us as u64).await;
+        }
+        // Fully qualified: esp-hal's inherent `transaction` is the blocking one.
+        AsyncI2c::transaction(&mut *self.inner, address, operations).await
+    }
+}
 // ─────────────────────────────────────────────────────────────────────────────
+
+/// Bus speeds to try at startup, fastest first, and how many consecutive
+/// CRC-verified serial reads each must pass.
+#[derive(Copy, Clone)]
+pub struct SpeedPlan {
+    pub steps_khz: &'static [u32],
+    pub probes: u8,
+}
+
+impl Default for SpeedPlan {
+    fn default() -> Self {
+        Self {
+            steps_khz: &[400, 200, 100],
+            probes: 5,
+        }
+    }
+}
+
+/// Settle on the fastest reliable bus speed: each step in `plan` is applied
+/// to the live peripheral and kept if every probe read succeeds with valid
+/// CRCs. If no step passes, the last (slowest) one stays applied and `None`
+/// is returned so the caller can report the wiring problem.
+pub async fn negotiate_speed(i2c: &mut HalI2c<'_>, plan: &SpeedPlan) -> Option<u32> {
+    for &khz in plan.steps_khz {
+        let config = I2cConfig::default().with_frequency(Rate::from_khz(khz));
+        if i2c.apply_config(&config).is_err() {
+            warn!("I²C rejected {} kHz", khz);
+            continue;
+        }
+        let mut passed = 0;
+        while passed < plan.probes && probe_serial(i2c).await {
+            passed += 1;
+        }
+        if passed == plan.probes {
+            info!("I²C bus negotiated at {} kHz", khz);
+            return Some(khz);
+        }
+        warn!("I²C unreliable at {} kHz ({}/{} probes passed)", khz, passed, plan.probes);
+    }
+    warn!("No reliable I²C speed found; staying at the slowest step");
+    None
+}
+
+// One serial-number read with all three word CRCs checked.
+async fn probe_serial(i2c: &mut HalI2c<'_>) -> bool {
+    if i2c.write(SGP41_ADDR, &CMD_GET_SERIAL_NUMBER).is_err() {
+        return false;
+    }
+    Timer::after(SERIAL_NUMBER_TIME).await;
+    let mut buf = [0u8; 9];
+    i2c.read(SGP41_ADDR, &mut buf).is_ok() && decode_words::<3>(&buf).is_some()
+}
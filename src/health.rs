@@ -0,0 +1,289 @@
+//! Device health counters, updated by the tasks and read by diagnostics.
+
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+use defmt::Format;
+use embassy_time::{Duration, Instant};
+use esp_hal::rtc_cntl::SocResetReason;
+use esp_hal::system::Cpu;
+
+use crate::compensation::{compensation_state, CompensationState};
+use crate::config::get_config;
+use crate::driver::SelfTestResult;
+
+static I2C_ERRORS: AtomicU32 = AtomicU32::new(0);
+static CRC_ERRORS: AtomicU32 = AtomicU32::new(0);
+static LED_WRITE_FAILURES: AtomicU32 = AtomicU32::new(0);
+static LED_FAILURE_STREAK: AtomicU32 = AtomicU32::new(0);
+static LED_COMMANDS_DROPPED: AtomicU32 = AtomicU32::new(0);
+static NOX_DEGRADED: AtomicBool = AtomicBool::new(false);
+static SELF_TEST: AtomicU8 = AtomicU8::new(SelfTestStatus::NotRun as u8);
+
+/// Consecutive failed LED writes (each already retried) that mark the LED unhealthy.
+pub const LED_UNHEALTHY_STREAK: u32 = 3;
+
+pub fn record_i2c_error() {
+    I2C_ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_crc_error() {
+    CRC_ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+// Uptime (ms, wrapping) of the last completed measurement; 0 = none yet.
+static LAST_MEASUREMENT_MS: AtomicU32 = AtomicU32::new(0);
+
+/// A measurement cycle completed; feeds the supervisor's liveness check.
+pub fn record_measurement() {
+    let now = Instant::now().as_millis() as u32;
+    LAST_MEASUREMENT_MS.store(now.max(1), Ordering::Relaxed);
+}
+
+/// Time since the last completed measurement, or `None` before the first one.
+/// Wraps after ~49 days of silence, far beyond any liveness timeout.
+pub fn last_measurement_age() -> Option<Duration> {
+    let last = LAST_MEASUREMENT_MS.load(Ordering::Relaxed);
+    if last == 0 {
+        return None;
+    }
+    let now = Instant::now().as_millis() as u32;
+    Some(Duration::from_millis(now.wrapping_sub(last) as u64))
+}
+
+/// How far a consumer UI should trust the latest reading.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+#[repr(u8)]
+pub enum AgeCategory {
+    Fresh = 0,
+    Recent = 1,
+    Stale = 2,
+    /// Older than `AgeThresholds::stale`, or no reading yet.
+    Dead = 3,
+}
+
+/// Upper age bounds for each `AgeCategory`.
+#[derive(Copy, Clone, PartialEq, Eq, Format)]
+pub struct AgeThresholds {
+    pub fresh: Duration,
+    pub recent: Duration,
+    pub stale: Duration,
+}
+
+impl AgeThresholds {
+    /// Thresholds scaled to the measurement interval: fresh within 2
+    /// intervals, recent within 10, stale within 60. At the default 1 s
+    /// interval that is <2 s, <10 s, <60 s.
+    pub fn for_interval(interval: Duration) -> Self {
+        Self {
+            fresh: interval * 2,
+            recent: interval * 10,
+            stale: interval * 60,
+        }
+    }
+
+    pub fn categorize(&self, age: Option<Duration>) -> AgeCategory {
+        match age {
+            Some(age) if age < self.fresh => AgeCategory::Fresh,
+            Some(age) if age < self.recent => AgeCategory::Recent,
+            Some(age) if age < self.stale => AgeCategory::Stale,
+            _ => AgeCategory::Dead,
+        }
+    }
+}
+
+impl Default for AgeThresholds {
+    fn default() -> Self {
+        Self::for_interval(Duration::from_secs(1))
+    }
+}
+
+/// An LED write failed even after its retry.
+pub fn record_led_failure() {
+    LED_WRITE_FAILURES.fetch_add(1, Ordering::Relaxed);
+    LED_FAILURE_STREAK.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_led_success() {
+    LED_FAILURE_STREAK.store(0, Ordering::Relaxed);
+}
+
+/// An LED command was dropped because the LED queue was full.
+pub fn record_led_command_dropped() {
+    LED_COMMANDS_DROPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The self-test failed the NOx pixel only; the device fell back to VOC-only
+/// reporting for the rest of this boot.
+pub fn record_nox_degraded() {
+    NOX_DEGRADED.store(true, Ordering::Relaxed);
+}
+
+pub fn nox_degraded() -> bool {
+    NOX_DEGRADED.load(Ordering::Relaxed)
+}
+
+/// Outcome of the startup self-test.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+#[repr(u8)]
+pub enum SelfTestStatus {
+    /// Skipped (`startup_self_test` off) or not finished yet.
+    NotRun = 0,
+    Passed = 1,
+    /// At least one pixel failed; see `nox_degraded` for a NOx-only failure.
+    Failed = 2,
+    /// The bus failed or the result word had a bad CRC.
+    Unreadable = 3,
+}
+
+impl SelfTestStatus {
+    pub fn of(result: Option<SelfTestResult>) -> Self {
+        match result {
+            Some(result) if result.passed() => Self::Passed,
+            Some(_) => Self::Failed,
+            None => Self::Unreadable,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::NotRun => "not_run",
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+            Self::Unreadable => "unreadable",
+        }
+    }
+}
+
+pub fn record_self_test(result: Option<SelfTestResult>) {
+    SELF_TEST.store(SelfTestStatus::of(result) as u8, Ordering::Relaxed);
+}
+
+pub fn self_test_status() -> SelfTestStatus {
+    match SELF_TEST.load(Ordering::Relaxed) {
+        1 => SelfTestStatus::Passed,
+        2 => SelfTestStatus::Failed,
+        3 => SelfTestStatus::Unreadable,
+        _ => SelfTestStatus::NotRun,
+    }
+}
+
+/// Why the chip last reset, collapsed from the chip-specific `SocResetReason`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+#[repr(u8)]
+pub enum ResetReason {
+    /// Power applied (or EN pin pulled). The normal first boot.
+    PowerOn = 0,
+    /// Software reset: `software_reset()` or a panic handler that resets.
+    Software = 1,
+    /// Woke from deep sleep.
+    DeepSleep = 2,
+    /// Any watchdog (timer group, RTC or super watchdog). Something hung.
+    Watchdog = 3,
+    /// Supply voltage dropped below the brownout threshold.
+    Brownout = 4,
+    /// Anything else (JTAG, USB, eFuse CRC, ...) or unreadable.
+    Other = 5,
+}
+
+impl ResetReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            ResetReason::PowerOn => "power_on",
+            ResetReason::Software => "software",
+            ResetReason::DeepSleep => "deep_sleep",
+            ResetReason::Watchdog => "watchdog",
+            ResetReason::Brownout => "brownout",
+            ResetReason::Other => "other",
+        }
+    }
+}
+
+/// Reset reason of the core running this code.
+pub fn reset_reason() -> ResetReason {
+    match esp_hal::rtc_cntl::reset_reason(Cpu::current()) {
+        Some(SocResetReason::ChipPowerOn) => ResetReason::PowerOn,
+        Some(SocResetReason::CoreSw | SocResetReason::Cpu0Sw) => ResetReason::Software,
+        Some(SocResetReason::CoreDeepSleep) => ResetReason::DeepSleep,
+        Some(
+            SocResetReason::CoreMwdt0
+            | SocResetReason::CoreMwdt1
+            | SocResetReason::CoreRtcWdt
+            | SocResetReason::Cpu0Mwdt0
+            | SocResetReason::Cpu0Mwdt1
+            | SocResetReason::Cpu0RtcWdt
+            | SocResetReason::SysRtcWdt
+            | SocResetReason::SysSuperWdt,
+        ) => ResetReason::Watchdog,
+        Some(SocResetReason::SysBrownOut) => ResetReason::Brownout,
+        _ => ResetReason::Other,
+    }
+}
+
+/// Time since boot.
+pub fn uptime() -> Duration {
+    Duration::from_ticks(Instant::now().as_ticks())
+}
+
+#[derive(Copy, Clone, Format)]
+pub struct HealthSnapshot {
+    pub uptime_s: u32,
+    pub reset_reason: ResetReason,
+    pub i2c_errors: u32,
+    pub crc_errors: u32,
+    pub led_write_failures: u32,
+    /// Commands dropped on a full LED queue (see `led::send_or_drop`).
+    pub led_commands_dropped: u32,
+    pub led_unhealthy: bool,
+    /// NOx pixel failed its self-test; NOx is suppressed on all outputs.
+    pub nox_degraded: bool,
+    pub self_test: SelfTestStatus,
+    /// Age of the latest reading, against thresholds scaled to the active
+    /// measurement interval.
+    pub measurement_age: AgeCategory,
+    /// Compensation state of the latest measure command.
+    pub compensation: CompensationState,
+}
+
+/// Length of the BLE health characteristic value.
+pub const HEALTH_BLE_LEN: usize = 16;
+
+impl HealthSnapshot {
+    /// Little-endian value of the BLE health characteristic:
+    ///
+    /// | offset | size | field                                  |
+    /// |--------|------|----------------------------------------|
+    /// | 0      | 4    | uptime (s)                             |
+    /// | 4      | 1    | reset reason (`ResetReason` as `u8`)   |
+    /// | 5      | 4    | I²C errors                             |
+    /// | 9      | 4    | CRC errors                             |
+    /// | 13     | 1    | flags: bit0 LED unhealthy, bit1 NOx degraded |
+    /// | 14     | 1    | measurement age (`AgeCategory` as `u8`) |
+    /// | 15     | 1    | compensation (`CompensationState` as `u8`) |
+    pub fn to_ble_bytes(&self) -> [u8; HEALTH_BLE_LEN] {
+        let mut out = [0u8; HEALTH_BLE_LEN];
+        out[0..4].copy_from_slice(&self.uptime_s.to_le_bytes());
+        out[4] = self.reset_reason as u8;
+        out[5..9].copy_from_slice(&self.i2c_errors.to_le_bytes());
+        out[9..13].copy_from_slice(&self.crc_errors.to_le_bytes());
+        out[13] = self.led_unhealthy as u8 | (self.nox_degraded as u8) << 1;
+        out[14] = self.measurement_age as u8;
+        out[15] = self.compensation as u8;
+        out
+    }
+}
+
+pub fn snapshot() -> HealthSnapshot {
+    let interval = Duration::from_millis(get_config().measurement_interval_ms as u64);
+    HealthSnapshot {
+        uptime_s: uptime().as_secs() as u32,
+        reset_reason: reset_reason(),
+        i2c_errors: I2C_ERRORS.load(Ordering::Relaxed),
+        crc_errors: CRC_ERRORS.load(Ordering::Relaxed),
+        led_write_failures: LED_WRITE_FAILURES.load(Ordering::Relaxed),
+        led_commands_dropped: LED_COMMANDS_DROPPED.load(Ordering::Relaxed),
+        led_unhealthy: LED_FAILURE_STREAK.load(Ordering::Relaxed) >= LED_UNHEALTHY_STREAK,
+        nox_degraded: nox_degraded(),
+        self_test: self_test_status(),
+        measurement_age: AgeThresholds::for_interval(interval).categorize(last_measurement_age()),
+        compensation: compensation_state(),
+    }
+}
@@ -0,0 +1,48 @@
+//! Psychrometric helpers for feeding non-RH humidity sources to the SGP41.
+//!
+//! All conversions use the Magnus formula with the Sonntag (1990) constants,
+//! valid over water from -45 °C to 60 °C. Relative humidity depends strongly
+//! on temperature, so always pass the temperature measured alongside the
+//! humidity value (the same one used for SGP41 compensation).
+
+use libm::expf;
+
+const MAGNUS_A: f32 = 17.62;
+const MAGNUS_B: f32 = 243.12; // °C
+const MAGNUS_E0: f32 = 6.112; // hPa
+// Water vapor gas constant factor: 100 / R_v (R_v = 461.5 J/(kg·K)), in g·K/(m³·hPa)
+const VAPOR_FACTOR: f32 = 216.7;
+
+/// Valid temperature range of the Magnus constants (°C).
+pub const MAGNUS_TEMP_RANGE: core::ops::RangeInclusive<f32> = -45.0..=60.0;
+
+/// Saturation vapor pressure over water (hPa) at `temp_c`.
+fn saturation_vapor_pressure(temp_c: f32) -> f32 {
+    MAGNUS_E0 * expf(MAGNUS_A * temp_c / (MAGNUS_B + temp_c))
+}
+
+/// Convert absolute humidity (g/m³) at `temp_c` to relative humidity (%).
+/// The result is clamped to 0..=100 %.
+pub fn abs_humidity_to_rh(abs_humidity_g_m3: f32, temp_c: f32) -> f32 {
+    let vapor_pressure = abs_humidity_g_m3 * (273.15 + temp_c) / VAPOR_FACTOR;
+    (100.0 * vapor_pressure / saturation_vapor_pressure(temp_c)).clamp(0.0, 100.0)
+}
+
+/// Absolute humidity (g/m³) from temperature (°C) and relative humidity (%),
+/// for logging and cross-comparison:
+///
+/// `AH = 216.7 · (RH/100 · 6.112 · exp(17.62·T / (243.12 + T))) / (273.15 + T)`
+///
+/// i.e. the Magnus vapor pressure fed through the ideal gas law. Valid over
+/// `MAGNUS_TEMP_RANGE`; `rh` is clamped to 0..=100 %.
+pub fn absolute_humidity(temp_c: f32, rh: f32) -> f32 {
+    let vapor_pressure = rh.clamp(0.0, 100.0) / 100.0 * saturation_vapor_pressure(temp_c);
+    VAPOR_FACTOR * vapor_pressure / (273.15 + temp_c)
+}
+
+/// Convert a dew point (°C) at air temperature `temp_c` to relative humidity (%).
+/// The result is clamped to 0..=100 %.
+pub fn dewpoint_to_rh(dewpoint_c: f32, temp_c: f32) -> f32 {
+    (100.0 * saturation_vapor_pressure(dewpoint_c) / saturation_vapor_pressure(temp_c))
+        .clamp(0.0, 100.0)
+}
@@ -0,0 +1,118 @@
+//! InfluxDB line-protocol formatting of readings. Transport-independent:
+//! `tasks::influx` ships the lines over UDP to `InfluxConfig::host`.
+//!
+//! One line per reading:
+//!
+//! ```text
+//! <measurement>[,serial=<12 hex digits>] voc_index=<i>i,voc_raw=<i>i[,nox_index=<i>i,nox_raw=<i>i],compensation="<state>" [<timestamp>]
+//! sgp41,serial=00000A3FA3F2 voc_index=100i,voc_raw=30079i,nox_index=1i,nox_raw=17753i,compensation="compensated" 1700000000000
+//! ```
+//!
+//! | part        | content                                                  |
+//! |-------------|----------------------------------------------------------|
+//! | measurement | `InfluxConfig::measurement`                              |
+//! | tag         | `serial`, omitted if the serial number couldn't be read  |
+//! | fields      | indices and raw ticks as integers; NOx omitted in VOC-only mode; `compensation` as a string (`compensated`, `default`, `stale`) |
+//! | timestamp   | Unix time in `InfluxConfig::precision`; omitted until a time source calls `wall_clock::set_unix_time_ms`, so the server stamps the line on arrival |
+
+use core::fmt::Write;
+use core::net::SocketAddrV4;
+use defmt::Format;
+
+use crate::csv::Cursor;
+use crate::readings::Measurement;
+use crate::reporting::voc_only_reporting;
+
+/// Longest line: a 32-byte measurement name, the serial tag, four integer
+/// fields at their widest, the compensation field and a nanosecond timestamp.
+pub const LINE_MAX: usize = 224;
+
+/// Timestamp unit, passed to the server as the `precision` write parameter.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub enum Precision {
+    Seconds,
+    Milliseconds,
+    Nanoseconds,
+}
+
+impl Precision {
+    /// Value of the `precision` query parameter.
+    pub fn as_param(self) -> &'static str {
+        match self {
+            Precision::Seconds => "s",
+            Precision::Milliseconds => "ms",
+            Precision::Nanoseconds => "ns",
+        }
+    }
+
+    fn scale_unix_ms(self, unix_ms: u64) -> u64 {
+        match self {
+            Precision::Seconds => unix_ms / 1000,
+            Precision::Milliseconds => unix_ms,
+            Precision::Nanoseconds => unix_ms.saturating_mul(1_000_000),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Format)]
+pub struct InfluxConfig {
+    /// Measurement name; keep it to plain ASCII without spaces or commas.
+    pub measurement: &'static str,
+    /// `ip:port` of the InfluxDB UDP listener (IPv4; there is no DNS lookup).
+    pub host: &'static str,
+    pub precision: Precision,
+}
+
+impl Default for InfluxConfig {
+    fn default() -> Self {
+        Self {
+            measurement: "sgp41",
+            host: "",
+            precision: Precision::Milliseconds,
+        }
+    }
+}
+
+impl InfluxConfig {
+    /// Defaults with `host` from the `INFLUX_HOST` build-time environment
+    /// variable; `None` if it is unset.
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            host: option_env!("INFLUX_HOST")?,
+            ..Self::default()
+        })
+    }
+
+    /// `host` as IPv4 octets and port; `None` unless it is `a.b.c.d:port`.
+    pub fn endpoint(&self) -> Option<([u8; 4], u16)> {
+        let addr: SocketAddrV4 = self.host.parse().ok()?;
+        Some((addr.ip().octets(), addr.port()))
+    }
+
+    /// Format one reading as a line (with trailing newline) into `buf`;
+    /// pass `wall_clock::unix_time_ms()` as `unix_ms`.
+    /// `None` if the configured measurement name is too long to fit.
+    pub fn format_line<'a>(
+        &self,
+        serial: Option<[u16; 3]>,
+        result: &Measurement,
+        unix_ms: Option<u64>,
+        buf: &'a mut [u8; LINE_MAX],
+    ) -> Option<&'a [u8]> {
+        let mut cursor = Cursor::new(buf);
+        write!(cursor, "{}", self.measurement).ok()?;
+        if let Some([a, b, c]) = serial {
+            write!(cursor, ",serial={:04X}{:04X}{:04X}", a, b, c).ok()?;
+        }
+        write!(cursor, " voc_index={}i,voc_raw={}i", result.voc_index, result.voc_raw).ok()?;
+        if !voc_only_reporting() {
+            write!(cursor, ",nox_index={}i,nox_raw={}i", result.nox_index, result.nox_raw).ok()?;
+        }
+        write!(cursor, ",compensation=\"{}\"", result.compensation.label()).ok()?;
+        if let Some(unix_ms) = unix_ms {
+            write!(cursor, " {}", self.precision.scale_unix_ms(unix_ms)).ok()?;
+        }
+        writeln!(cursor).ok()?;
+        Some(cursor.into_bytes())
+    }
+}
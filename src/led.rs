@@ -1,6 +1,20 @@
-use defmt::debug;
+// LED backend per chip feature:
+//   esp32c6 → WS2812 addressable LED driven by RMT (`esp-hal-smartled`), GPIO8 on the DevKit
+//   esp32s3 → plain GPIO LED (XIAO ESP32-S3 built-in LED on GPIO21), on/off only
+// Exactly one chip feature must be enabled; the guards below turn an invalid
+// combination into a single clear error instead of deep type errors.
+#[cfg(all(feature = "esp32c6", feature = "esp32s3"))]
+compile_error!("features `esp32c6` and `esp32s3` are mutually exclusive: each selects a different LED backend (WS2812/RMT vs GPIO)");
+#[cfg(not(any(feature = "esp32c6", feature = "esp32s3")))]
+compile_error!("select a chip feature: `esp32c6` (WS2812 LED over RMT) or `esp32s3` (GPIO LED)");
+
+use defmt::{debug, warn};
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::channel::Sender;
 use embassy_time::{Duration, Timer};
 
+use crate::health::record_led_command_dropped;
+
 #[cfg(feature = "esp32c6")]
 use esp_hal::gpio::OutputPin;
 #[cfg(feature = "esp32c6")]
@@ -12,9 +26,75 @@ use smart_leds::hsv::{hsv2rgb, Hsv};
 #[cfg(feature = "esp32c6")]
 use smart_leds::{SmartLedsWrite, RGB8};
 
+#[cfg(feature = "esp32c6")]
+use esp_hal::rmt::Channel as RmtChannel;
+#[cfg(feature = "esp32c6")]
+use esp_hal::Blocking;
+
 #[cfg(feature = "esp32s3")]
 use esp_hal::gpio::Output;
 
+/// Concrete LED type for the selected chip, as owned by the LED task.
+#[cfg(feature = "esp32c6")]
+pub type LedDriver = Led<RmtChannel<Blocking, 0>>;
+#[cfg(feature = "esp32s3")]
+pub type LedDriver = Led;
+
+/// Gamma 2.8 lookup (`round(255 * (i / 255)^2.8)`): maps a perceived
+/// brightness to the WS2812 duty cycle, whose light output is roughly linear
+/// in the written value while the eye is not. The low end is strongly
+/// compressed (inputs up to 30 map to 0–1), so palette colors go through
+/// `gamma_color` rather than straight through this table.
+pub const GAMMA8: [u8; 256] = [
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   0,   1,   1,   1,   1,
+      1,   1,   1,   1,   1,   1,   1,   1,   1,   2,   2,   2,   2,   2,   2,   2,
+      2,   3,   3,   3,   3,   3,   3,   3,   4,   4,   4,   4,   4,   5,   5,   5,
+      5,   6,   6,   6,   6,   7,   7,   7,   7,   8,   8,   8,   9,   9,   9,  10,
+     10,  10,  11,  11,  11,  12,  12,  13,  13,  13,  14,  14,  15,  15,  16,  16,
+     17,  17,  18,  18,  19,  19,  20,  20,  21,  21,  22,  22,  23,  24,  24,  25,
+     25,  26,  27,  27,  28,  29,  29,  30,  31,  32,  32,  33,  34,  35,  35,  36,
+     37,  38,  39,  39,  40,  41,  42,  43,  44,  45,  46,  47,  48,  49,  50,  50,
+     51,  52,  54,  55,  56,  57,  58,  59,  60,  61,  62,  63,  64,  66,  67,  68,
+     69,  70,  72,  73,  74,  75,  77,  78,  79,  81,  82,  83,  85,  86,  87,  89,
+     90,  92,  93,  95,  96,  98,  99, 101, 102, 104, 105, 107, 109, 110, 112, 114,
+    115, 117, 119, 120, 122, 124, 126, 127, 129, 131, 133, 135, 137, 138, 140, 142,
+    144, 146, 148, 150, 152, 154, 156, 158, 160, 162, 164, 167, 169, 171, 173, 175,
+    177, 180, 182, 184, 186, 189, 191, 193, 196, 198, 200, 203, 205, 208, 210, 213,
+    215, 218, 220, 223, 225, 228, 231, 233, 236, 239, 241, 244, 247, 249, 252, 255,
+];
+
+/// Gamma-correct one channel with `GAMMA8`.
+pub fn gamma(value: u8) -> u8 {
+    GAMMA8[value as usize]
+}
+
+/// Channel value the LED palette is authored at: full brightness for the
+/// status colors, dim enough for a desk.
+pub const PALETTE_FULL_SCALE: u8 = 30;
+
+/// Gamma-correct a palette color. Each channel is stretched from
+/// `0..=PALETTE_FULL_SCALE` to the full table range, looked up in `GAMMA8`,
+/// then dimmed back down, so the palette keeps its hue steps instead of
+/// collapsing into the bottom of the table. A lit channel stays at least 1,
+/// so dim colors (the initializing white) don't go dark. Applied by
+/// `set_color_rgb`.
+pub fn gamma_color((r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+    let full_scale = PALETTE_FULL_SCALE as u16;
+    let channel = |c: u8| {
+        if c == 0 {
+            return 0;
+        }
+        let stretched = (c as u16 * 255 / full_scale).min(255) as u8;
+        (((gamma(stretched) as u16 * full_scale + 127) / 255) as u8).max(1)
+    };
+    (channel(r), channel(g), channel(b))
+}
+
+/// An LED write did not reach the hardware (e.g. an RMT transmit error).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, defmt::Format)]
+pub struct LedWriteError;
+
 #[cfg(feature = "esp32s3")]
 /// Unified LED API for ESP32-S3 (GPIO LED)
 pub struct Led {
@@ -27,8 +107,40 @@ pub struct Led<TX>
 where
     TX: TxChannel,
 {
+    // RMT pulse buffer for one pixel: a WS2812 frame is 24 bits (8 each for
+    // G, R, B), every bit is one RMT pulse code, plus one end-of-frame marker,
+    // so `smart_led_buffer!(1)` is 1 * 24 + 1 = 25 codes. More pixels on the
+    // chain need `24 * n + 1`.
     ws2812: Option<SmartLedsAdapter<TX, 25>>,
     hue: u8,
+    color_order: ColorOrder,
+}
+
+/// Channel order the LED actually expects, relative to what the adapter sends.
+///
+/// `esp-hal-smartled` already emits the standard WS2812 GRB wire order, so the
+/// ESP32-C6 DevKit/XIAO onboard LED needs `Rgb` (no remapping). To detect a
+/// mismatch, send `Solid(30, 0, 0)`: if the LED shows green instead of red,
+/// the part wants `Grb`; if it shows blue, try `Bgr`.
+#[cfg(feature = "esp32c6")]
+#[derive(Copy, Clone, Default, PartialEq, Eq, defmt::Format)]
+pub enum ColorOrder {
+    #[default]
+    Rgb,
+    Grb,
+    Bgr,
+}
+
+#[cfg(feature = "esp32c6")]
+impl ColorOrder {
+    /// Remap a color so it renders correctly on an LED with this channel order.
+    pub fn remap(self, color: RGB8) -> RGB8 {
+        match self {
+            ColorOrder::Rgb => color,
+            ColorOrder::Grb => RGB8::new(color.g, color.r, color.b),
+            ColorOrder::Bgr => RGB8::new(color.b, color.g, color.r),
+        }
+    }
 }
 
 #[cfg(feature = "esp32s3")]
@@ -47,7 +159,7 @@ where
     TX: TxChannel,
 {
     /// Create a new LED instance for ESP32-C6 (WS2812)
-    pub fn new_ws2812<C, O>(channel: C, pin: O) -> Self
+    pub fn new_ws2812<C, O>(channel: C, pin: O, color_order: ColorOrder) -> Self
     where
         C: TxChannelCreator<'static, TX>,
         O: OutputPin + 'static,
@@ -56,6 +168,7 @@ where
         Self {
             ws2812: Some(led_adapter),
             hue: 0,
+            color_order,
         }
     }
 }
@@ -73,6 +186,17 @@ impl Led {
         }
     }
 
+    /// GPIO LED has no color: any non-zero channel turns it on. GPIO writes
+    /// cannot fail. No gamma correction: it would only turn dim colors off.
+    pub fn set_color_rgb(&mut self, r: u8, g: u8, b: u8) -> Result<(), LedWriteError> {
+        self.set_color_rgb_raw(r, g, b)
+    }
+
+    pub fn set_color_rgb_raw(&mut self, r: u8, g: u8, b: u8) -> Result<(), LedWriteError> {
+        self.set_color(r.max(g).max(b));
+        Ok(())
+    }
+
     /// Cycle LED color/state with logging
     pub async fn cycle_color(&mut self, brightness: u8) {
         if self.gpio.is_some() {
@@ -111,15 +235,24 @@ where
                 RGB8::new(0, 0, 0)
             };
             // Send color, ignore any errors
-            let _ = ws2812.write([rgb].iter().cloned());
+            let _ = ws2812.write([self.color_order.remap(rgb)].iter().cloned());
         }
     }
 
-    pub fn set_color_rgb(&mut self, r: u8, g: u8, b: u8)  {
-        let _ =self.ws2812
+    /// Write a palette color, gamma-corrected through `gamma_color`.
+    pub fn set_color_rgb(&mut self, r: u8, g: u8, b: u8) -> Result<(), LedWriteError> {
+        let (r, g, b) = gamma_color((r, g, b));
+        self.set_color_rgb_raw(r, g, b)
+    }
+
+    /// Write a color exactly as given, without gamma correction.
+    pub fn set_color_rgb_raw(&mut self, r: u8, g: u8, b: u8) -> Result<(), LedWriteError> {
+        let rgb = self.color_order.remap(RGB8::new(r, g, b));
+        self.ws2812
             .as_mut()
-            .map(|ws2812| ws2812.write([RGB8::new(r, g, b)].iter().cloned()).map_err(|_| ()))
-            .unwrap_or(Err(()));
+            .ok_or(LedWriteError)?
+            .write([rgb].iter().cloned())
+            .map_err(|_| LedWriteError)
     }
 
 
@@ -143,4 +276,394 @@ where
 pub enum LedCommand {
     Solid(u8, u8, u8),
     Blink(u8, u8, u8, Option<u16>),  // r, g, b, period_ms
+    Connection(ConnectionStatus),    // radio link transition, shown as a brief blip
+    Conditioning(ConditioningAnimation), // runs until the next command arrives
+    Ready,                           // warm-up finished: plays `StatusLedConfig::ready_flash` once
+    Off,                             // dark; also ends any running blink or animation
+    // Smooth triangular brightness ramp, repeating until the next command arrives
+    Pulse { r: u8, g: u8, b: u8, period_ms: u16 },
+    // `count` blinks (on then off, half a period each), then back to the
+    // prior color; a newer command cuts the burst short
+    BlinkN { r: u8, g: u8, b: u8, period_ms: u16, count: u8 },
+    // Air-quality state shown as two colors, each for half a period (the
+    // combined VOC + NOx alarm), repeating until the next command arrives
+    Alternate { first: (u8, u8, u8), second: (u8, u8, u8), period_ms: u16 },
+}
+
+impl LedCommand {
+    /// A burst of `count` blinks in `color`, e.g. `blink_n((30, 0, 0), 200, 3)`
+    /// for three quick red blinks.
+    pub const fn blink_n(color: (u8, u8, u8), period_ms: u16, count: u8) -> Self {
+        let (r, g, b) = color;
+        LedCommand::BlinkN { r, g, b, period_ms, count }
+    }
+}
+
+/// LED animation shown while the sensor is conditioning. The LED task renders
+/// it on its own frame timer, independent of the conditioning read schedule.
+#[derive(Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub enum ConditioningAnimation {
+    /// Hold a single color.
+    Solid((u8, u8, u8)),
+    /// Switch between two colors every half period.
+    Alternate {
+        first: (u8, u8, u8),
+        second: (u8, u8, u8),
+        period_ms: u16,
+    },
+    /// Triangular brightness ramp up and down over one period.
+    Breathe { color: (u8, u8, u8), period_ms: u16 },
+}
+
+impl Default for ConditioningAnimation {
+    /// Red/magenta alternation, matching the original conditioning indicator.
+    fn default() -> Self {
+        ConditioningAnimation::Alternate {
+            first: (30, 0, 0),
+            second: (30, 0, 30),
+            period_ms: 1000,
+        }
+    }
+}
+
+impl ConditioningAnimation {
+    /// Slow white breathing: a "working, please wait" indicator that can't be
+    /// mistaken for any air-quality or alarm color.
+    pub const PLEASE_WAIT: Self = ConditioningAnimation::Breathe {
+        color: (20, 20, 20),
+        period_ms: 2000,
+    };
+
+    /// Interval between rendered frames.
+    pub fn frame_ms(&self) -> u16 {
+        match *self {
+            ConditioningAnimation::Solid(_) => 1000,
+            ConditioningAnimation::Alternate { period_ms, .. } => (period_ms / 2).max(1),
+            ConditioningAnimation::Breathe { .. } => 20,
+        }
+    }
+
+    /// Color shown `elapsed_ms` after the animation started.
+    pub fn color_at(&self, elapsed_ms: u32) -> (u8, u8, u8) {
+        match *self {
+            ConditioningAnimation::Solid(color) => color,
+            ConditioningAnimation::Alternate {
+                first,
+                second,
+                period_ms,
+            } => {
+                let period = (period_ms as u32).max(1);
+                if elapsed_ms % period < period / 2 {
+                    first
+                } else {
+                    second
+                }
+            }
+            ConditioningAnimation::Breathe { color, period_ms } => {
+                let period = (period_ms as u32).max(2);
+                let half = period / 2;
+                let t = elapsed_ms % period;
+                let ramp = if t < half { t } else { period - t };
+                let level = ramp * 255 / half;
+                scale_color(color, level.min(255) as u8)
+            }
+        }
+    }
+}
+
+/// One-shot flash marking the end of warm-up, played when the first valid
+/// measurement arrives after conditioning.
+#[derive(Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub struct ReadyFlash {
+    pub color: (u8, u8, u8),
+    /// Number of on/off pulses.
+    pub flashes: u8,
+    /// Duration of each on and each off phase.
+    pub on_ms: u16,
+}
+
+impl Default for ReadyFlash {
+    fn default() -> Self {
+        Self {
+            color: (0, 30, 0), // green
+            flashes: 2,
+            on_ms: 150,
+        }
+    }
+}
+
+/// One step of LED output: show `color`, then hold it for `hold_ms` before
+/// the next frame. `None` holds until the next command.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, defmt::Format)]
+pub struct Frame {
+    pub color: (u8, u8, u8),
+    pub hold_ms: Option<u32>,
+}
+
+/// The frames a command renders, independent of real time. The LED task
+/// plays them against the embassy timer; tests can step through them with a
+/// fake clock and check the exact color sequence.
+pub struct Frames {
+    command: LedCommand,
+    // Color to return to after a connection blip.
+    restore: (u8, u8, u8),
+    blip_ms: u16,
+    status: (u8, u8, u8),
+    ready: Option<ReadyFlash>,
+    step: u32,
+    elapsed_ms: u32,
+}
+
+impl LedCommand {
+    /// Frames for this command. `current` is the last air-quality color,
+    /// restored after a `Connection` blip.
+    pub fn frames(self, config: &StatusLedConfig, current: (u8, u8, u8)) -> Frames {
+        let status = match self {
+            LedCommand::Connection(status) => config.color(status),
+            _ => (0, 0, 0),
+        };
+        Frames {
+            command: self,
+            restore: current,
+            blip_ms: config.blip_ms,
+            status,
+            ready: config.ready_flash,
+            step: 0,
+            elapsed_ms: 0,
+        }
+    }
+}
+
+impl Iterator for Frames {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        let step = self.step;
+        self.step += 1;
+        match (self.command, step) {
+            (LedCommand::Solid(r, g, b), 0) => Some(Frame { color: (r, g, b), hold_ms: None }),
+            (LedCommand::Off, 0) => Some(Frame { color: (0, 0, 0), hold_ms: None }),
+            (LedCommand::Blink(_, _, _, period_ms), 0) => Some(Frame {
+                color: (0, 0, 0),
+                hold_ms: Some(period_ms.unwrap_or(300) as u32),
+            }),
+            (LedCommand::Blink(r, g, b, _), 1) => Some(Frame { color: (r, g, b), hold_ms: None }),
+            (LedCommand::Connection(_), 0) => Some(Frame {
+                color: self.status,
+                hold_ms: Some(self.blip_ms as u32),
+            }),
+            (LedCommand::Connection(_), 1) => Some(Frame { color: self.restore, hold_ms: None }),
+            // Ends dark; the air-quality color sent right after takes over.
+            (LedCommand::Ready, step) => {
+                let flash = self.ready?;
+                if step >= 2 * flash.flashes as u32 {
+                    return None;
+                }
+                let color = if step % 2 == 0 { flash.color } else { (0, 0, 0) };
+                Some(Frame { color, hold_ms: Some(flash.on_ms as u32) })
+            }
+            // Animations never end on their own; a newer command preempts them.
+            (LedCommand::Conditioning(animation), _) => Some(self.animate(animation)),
+            (LedCommand::Pulse { r, g, b, period_ms }, _) => {
+                Some(self.animate(ConditioningAnimation::Breathe { color: (r, g, b), period_ms }))
+            }
+            (LedCommand::Alternate { first, second, period_ms }, _) => {
+                Some(self.animate(ConditioningAnimation::Alternate { first, second, period_ms }))
+            }
+            (LedCommand::BlinkN { r, g, b, period_ms, count }, step) => {
+                let toggles = 2 * count as u32;
+                if step > toggles {
+                    return None;
+                }
+                if step == toggles {
+                    return Some(Frame { color: self.restore, hold_ms: None });
+                }
+                let color = if step % 2 == 0 { (r, g, b) } else { (0, 0, 0) };
+                Some(Frame { color, hold_ms: Some((period_ms / 2).max(1) as u32) })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Frames {
+    fn animate(&mut self, animation: ConditioningAnimation) -> Frame {
+        let frame_ms = animation.frame_ms() as u32;
+        let color = animation.color_at(self.elapsed_ms);
+        self.elapsed_ms = self.elapsed_ms.wrapping_add(frame_ms);
+        Frame { color, hold_ms: Some(frame_ms) }
+    }
+}
+
+// Samples averaged before picking the air-quality color, so an index hovering
+// at a threshold doesn't flicker between colors (see `filter::MovingAverage`).
+pub const LED_INDEX_WINDOW: usize = 5;
+
+// VOC index color ladder: each color applies above its threshold.
+pub const VOC_ALARM_THRESHOLD: i32 = 155;
+pub const VOC_ELEVATED_THRESHOLD: i32 = 114;
+pub const VOC_MODERATE_THRESHOLD: i32 = 92;
+// NOx index above which the NOx alarm color takes over.
+pub const NOX_ALARM_THRESHOLD: i32 = 30;
+
+pub const VOC_ALARM_COLOR: (u8, u8, u8) = (30, 0, 0); // red
+pub const VOC_ELEVATED_COLOR: (u8, u8, u8) = (30, 10, 20); // pink
+pub const VOC_MODERATE_COLOR: (u8, u8, u8) = (30, 30, 0); // yellow
+pub const VOC_GOOD_COLOR: (u8, u8, u8) = (21, 27, 28); // royal concerto, kinda green
+pub const NOX_ALARM_COLOR: (u8, u8, u8) = (30, 0, 30); // magenta
+
+/// Color for a VOC index:
+///
+/// | VOC index | color  |
+/// |-----------|--------|
+/// | > 155     | red    |
+/// | 115–155   | pink   |
+/// | 93–114    | yellow |
+/// | ≤ 92      | green  |
+pub fn voc_index_color(voc_index: i32) -> (u8, u8, u8) {
+    if voc_index > VOC_ALARM_THRESHOLD {
+        VOC_ALARM_COLOR
+    } else if voc_index > VOC_ELEVATED_THRESHOLD {
+        VOC_ELEVATED_COLOR
+    } else if voc_index > VOC_MODERATE_THRESHOLD {
+        VOC_MODERATE_COLOR
+    } else {
+        VOC_GOOD_COLOR
+    }
+}
+
+/// Color for a NOx index: magenta above 30, `None` (no say) otherwise.
+pub fn nox_index_color(nox_index: i32) -> Option<(u8, u8, u8)> {
+    (nox_index > NOX_ALARM_THRESHOLD).then_some(NOX_ALARM_COLOR)
+}
+
+/// LED color for a sample. A NOx alarm overrides the VOC ladder unless
+/// `nox_override` is off (VOC-only reporting).
+pub fn air_quality_color(voc_index: i32, nox_index: i32, nox_override: bool) -> (u8, u8, u8) {
+    match nox_index_color(nox_index) {
+        Some(color) if nox_override => color,
+        _ => voc_index_color(voc_index),
+    }
+}
+
+/// How the LED shows VOC (index > 155) and NOx (index > 30) alarms at once.
+/// Single-gas alarms always use `air_quality_color`.
+#[derive(Copy, Clone, Default, PartialEq, Eq, defmt::Format)]
+pub enum CombinedAlarm {
+    /// NOx magenta masks the VOC alarm, as for a NOx-only alarm.
+    #[default]
+    NoxOverrides,
+    /// A dedicated color for "both bad".
+    Solid((u8, u8, u8)),
+    /// Alternate VOC red and NOx magenta, each for half of `period_ms`.
+    Alternate { period_ms: u16 },
+}
+
+/// LED command for a sample, handling the combined-alarm case per `combined`.
+pub fn air_quality_command(
+    voc_index: i32,
+    nox_index: i32,
+    nox_override: bool,
+    combined: CombinedAlarm,
+) -> LedCommand {
+    let both = voc_index > VOC_ALARM_THRESHOLD && nox_override && nox_index > NOX_ALARM_THRESHOLD;
+    match combined {
+        CombinedAlarm::Solid((r, g, b)) if both => LedCommand::Blink(r, g, b, None),
+        // Alternates until the next sample's command replaces it.
+        CombinedAlarm::Alternate { period_ms } if both => LedCommand::Alternate {
+            first: VOC_ALARM_COLOR,
+            second: NOX_ALARM_COLOR,
+            period_ms,
+        },
+        _ => {
+            let (r, g, b) = air_quality_color(voc_index, nox_index, nox_override);
+            LedCommand::Blink(r, g, b, None)
+        }
+    }
+}
+
+/// Scale an RGB color by `level` / 255.
+pub fn scale_color((r, g, b): (u8, u8, u8), level: u8) -> (u8, u8, u8) {
+    let scale = |c: u8| ((c as u16 * level as u16) / 255) as u8;
+    (scale(r), scale(g), scale(b))
+}
+
+/// Pull a color halfway toward its own gray level: same brightness, visibly
+/// washed out, without changing the air-quality hue it conveys.
+pub fn desaturate((r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+    let gray = ((r as u16 + g as u16 + b as u16) / 3) as u8;
+    let mix = |c: u8| ((c as u16 + gray as u16) / 2) as u8;
+    (mix(r), mix(g), mix(b))
+}
+
+/// Queue `command` for the LED task without waiting. If the queue is full
+/// (the LED task is stalled, e.g. a hung WS2812 write) the command is dropped
+/// with a warning and counted in `health`, so the display never holds up
+/// the sensor tasks' timing.
+pub fn send_or_drop<const N: usize>(sender: &Sender<'_, NoopRawMutex, LedCommand, N>, command: LedCommand) {
+    if sender.try_send(command).is_err() {
+        warn!("LED queue full; command dropped");
+        record_led_command_dropped();
+    }
+}
+
+/// Radio (Wi-Fi/BLE) link state reported by the radio tasks.
+///
+/// Precedence: the air-quality color (`Solid`/`Blink`) is the persistent LED
+/// state. A `Connection` command only blips the status color for
+/// `StatusLedConfig::blip_ms` and then restores the last air-quality color,
+/// so connectivity never permanently overrides the air-quality display.
+/// While the link is down the disconnected blip repeats every
+/// `StatusLedConfig::link_down_every_ms` until `Connected` arrives.
+#[derive(Copy, Clone, PartialEq, Eq, defmt::Format)]
+pub enum ConnectionStatus {
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+/// Colors and timing for status indications.
+#[derive(Copy, Clone)]
+pub struct StatusLedConfig {
+    /// Shown from power-on until the first device-state transition: steady,
+    /// so it can't be confused with the breathing conditioning animation, and
+    /// dim, so it can't be confused with an alarm or fault.
+    pub initializing: (u8, u8, u8),
+    pub connecting: (u8, u8, u8),
+    pub connected: (u8, u8, u8),
+    pub disconnected: (u8, u8, u8),
+    pub blip_ms: u16,
+    /// Repeat period of the disconnected blip while the link stays down.
+    pub link_down_every_ms: u16,
+    /// Opt-in: `desaturate` air-quality colors while live compensation is
+    /// stale and the sensor runs on default temperature/humidity.
+    pub desaturate_when_uncompensated: bool,
+    /// Transition played once at the conditioning→measuring boundary;
+    /// `None` goes straight to the air-quality color.
+    pub ready_flash: Option<ReadyFlash>,
+}
+
+impl Default for StatusLedConfig {
+    fn default() -> Self {
+        Self {
+            initializing: (6, 6, 6),    // dim white
+            connecting: (0, 0, 30),     // blue
+            connected: (0, 30, 30),     // cyan
+            disconnected: (30, 15, 0),  // orange
+            blip_ms: 150,
+            link_down_every_ms: 3000,
+            desaturate_when_uncompensated: false,
+            ready_flash: Some(ReadyFlash::default()),
+        }
+    }
+}
+
+impl StatusLedConfig {
+    pub fn color(&self, status: ConnectionStatus) -> (u8, u8, u8) {
+        match status {
+            ConnectionStatus::Connecting => self.connecting,
+            ConnectionStatus::Connected => self.connected,
+            ConnectionStatus::Disconnected => self.disconnected,
+        }
+    }
 }
@@ -1,17 +1,66 @@
 #![no_std]
 
+pub mod algo;
+pub mod baseline;
+pub mod ble;
+pub mod calibration;
+pub mod category;
+pub mod commission;
+pub mod compensation;
+pub mod config;
+pub mod control;
+pub mod csv;
+#[cfg(feature = "co2-crosscheck")]
+pub mod crosscheck;
+pub mod driver;
+pub mod escalation;
+pub mod filter;
+pub mod freeze;
 pub mod hal;
+pub mod health;
+pub mod humidity;
+#[cfg(feature = "influx")]
+pub mod influx;
 pub mod tasks;
+pub mod timing;
+pub mod wall_clock;
+pub mod wire;
 pub mod led;
+pub mod measurement;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod mux;
+pub mod persistence;
+#[cfg(feature = "low-power")]
+pub mod power;
+pub mod power_cycle;
+pub mod prometheus;
+pub mod quality;
+pub mod readings;
+pub mod reporting;
+pub mod run_limit;
+#[cfg(feature = "sdcard")]
+pub mod sdlog;
+pub mod sampling;
+#[cfg(feature = "sntp")]
+pub mod sntp;
+pub mod soak;
+pub mod state;
+pub mod stats;
+pub mod supervisor;
 
-// CRC calculation for SGP41
+// SGP41 CRC-8 polynomial (x^8 + x^5 + x^4 + 1), init 0xFF
+const CRC_POLYNOMIAL: u8 = 0x31;
+
+// CRC calculation for SGP41, bit by bit. Kept as the reference for
+// `calculate_crc_table`.
 pub fn calculate_crc(data: &[u8]) -> u8 {
     let mut crc: u8 = 0xFF;
     for &byte in data {
         crc ^= byte;
         for _ in 0..8 {
             if crc & 0x80 != 0 {
-                crc = (crc << 1) ^ 0x31;
+                crc = (crc << 1) ^ CRC_POLYNOMIAL;
             } else {
                 crc <<= 1;
             }
@@ -20,12 +69,122 @@ pub fn calculate_crc(data: &[u8]) -> u8 {
     crc
 }
 
-// Helper function to prepare temperature and humidity parameters
+// CRC of every byte value after its 8 shift steps, generated at compile time
+pub const CRC_TABLE: [u8; 256] = crc_table();
+
+const fn crc_table() -> [u8; 256] {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ CRC_POLYNOMIAL } else { crc << 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+// Same result as `calculate_crc`, one table lookup per byte
+pub fn calculate_crc_table(data: &[u8]) -> u8 {
+    data.iter().fold(0xFF, |crc, &byte| CRC_TABLE[(crc ^ byte) as usize])
+}
+
+// Whether `expected` is the CRC of `data`
+pub fn verify_crc(data: &[u8], expected: u8) -> bool {
+    calculate_crc_table(data) == expected
+}
+
+// Decode one word + CRC triple from a sensor response; `None` on a CRC mismatch
+pub fn check_word(bytes: &[u8; 3]) -> Option<u16> {
+    verify_crc(&bytes[0..2], bytes[2]).then(|| u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+// Datasheet default compensation ticks (50 %RH / 25 °C). Sending these tells
+// the SGP41 to run without humidity compensation.
+pub const DEFAULT_HUMIDITY_TICKS: u16 = 0x8000;
+pub const DEFAULT_TEMPERATURE_TICKS: u16 = 0x6666;
+
+// Decode CRC-protected words from a sensor response; `None` on any CRC mismatch
+pub fn decode_words<const N: usize>(buf: &[u8]) -> Option<[u16; N]> {
+    let mut words = [0u16; N];
+    for (word, chunk) in words.iter_mut().zip(buf.chunks_exact(3)) {
+        *word = check_word(&[chunk[0], chunk[1], chunk[2]])?;
+    }
+    Some(words)
+}
+
+// Compensation input ranges the SGP41 accepts
+pub const TEMP_MIN_C: f32 = -45.0;
+pub const TEMP_MAX_C: f32 = 130.0;
+pub const HUMIDITY_MIN_PCT: f32 = 0.0;
+pub const HUMIDITY_MAX_PCT: f32 = 100.0;
+
+// Why a temperature/humidity pair was rejected as compensation input
+#[derive(Copy, Clone, Debug, PartialEq, Eq, defmt::Format)]
+pub enum ParamError {
+    // NaN or infinite temperature or humidity
+    NotFinite,
+    TemperatureOutOfRange,
+    HumidityOutOfRange,
+}
+
+// Helper function to prepare temperature and humidity parameters.
+// Out-of-range inputs are clamped; use the checked variant for sensor readings.
 pub fn prepare_temp_hum_params(temp_celsius: f32, humidity_percent: f32) -> [u8; 6] {
-    // Convert temperature and humidity to SGP41 format
-    let humidity_ticks = ((humidity_percent / 100.0) * 65535.0) as u16;
-    let temp_ticks = (((temp_celsius + 45.0) / 175.0) * 65535.0) as u16;
+    let (humidity_ticks, temp_ticks) = temp_hum_ticks(temp_celsius, humidity_percent);
+    encode_ticks(humidity_ticks, temp_ticks)
+}
+
+// Like `prepare_temp_hum_params`, but rejects NaN/infinity and values outside
+// -45..=130 °C / 0..=100 % instead of clamping them
+pub fn prepare_temp_hum_params_checked(temp_celsius: f32, humidity_percent: f32) -> Result<[u8; 6], ParamError> {
+    let (humidity_ticks, temp_ticks) = temp_hum_ticks_checked(temp_celsius, humidity_percent)?;
+    Ok(encode_ticks(humidity_ticks, temp_ticks))
+}
+
+// Convert temperature and humidity to the SGP41 (humidity, temperature) tick words,
+// clamping to the accepted ranges (NaN encodes as the range minimum)
+pub fn temp_hum_ticks(temp_celsius: f32, humidity_percent: f32) -> (u16, u16) {
+    let humidity = humidity_percent.clamp(HUMIDITY_MIN_PCT, HUMIDITY_MAX_PCT);
+    let temp = temp_celsius.clamp(TEMP_MIN_C, TEMP_MAX_C);
+    let humidity_ticks = ((humidity / 100.0) * 65535.0) as u16;
+    let temp_ticks = (((temp + 45.0) / 175.0) * 65535.0) as u16;
+    (humidity_ticks, temp_ticks)
+}
+
+// Inverse of `temp_hum_ticks`: the (temperature °C, humidity %) the sensor
+// actually receives for the given tick words
+pub fn ticks_to_temp_hum(humidity_ticks: u16, temp_ticks: u16) -> (f32, f32) {
+    let temp_celsius = (temp_ticks as f32 / 65535.0) * 175.0 - 45.0;
+    let humidity_percent = (humidity_ticks as f32 / 65535.0) * 100.0;
+    (temp_celsius, humidity_percent)
+}
+
+// Validating variant of `temp_hum_ticks`
+pub fn temp_hum_ticks_checked(temp_celsius: f32, humidity_percent: f32) -> Result<(u16, u16), ParamError> {
+    if !temp_celsius.is_finite() || !humidity_percent.is_finite() {
+        return Err(ParamError::NotFinite);
+    }
+    if !(TEMP_MIN_C..=TEMP_MAX_C).contains(&temp_celsius) {
+        return Err(ParamError::TemperatureOutOfRange);
+    }
+    if !(HUMIDITY_MIN_PCT..=HUMIDITY_MAX_PCT).contains(&humidity_percent) {
+        return Err(ParamError::HumidityOutOfRange);
+    }
+    Ok(temp_hum_ticks(temp_celsius, humidity_percent))
+}
+
+// Parameters for the "no compensation" measure command, exactly as the datasheet specifies
+pub fn prepare_default_params() -> [u8; 6] {
+    encode_ticks(DEFAULT_HUMIDITY_TICKS, DEFAULT_TEMPERATURE_TICKS)
+}
 
+// Encode humidity and temperature ticks as two CRC-protected words
+pub fn encode_ticks(humidity_ticks: u16, temp_ticks: u16) -> [u8; 6] {
     [
         (humidity_ticks >> 8) as u8,
         (humidity_ticks & 0xFF) as u8,
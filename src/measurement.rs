@@ -0,0 +1,78 @@
+use crate::calculate_crc;
+use crate::compensation::CompensationState;
+
+/// Length of a serialized `MeasurementResult` (without checksum).
+pub const MEASUREMENT_LEN: usize = 12;
+/// Length of a stored/transmitted record: payload followed by its CRC-8.
+pub const RECORD_LEN: usize = MEASUREMENT_LEN + 1;
+
+/// One processed SGP41 sample: raw ticks plus the gas index derived from them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, defmt::Format)]
+pub struct MeasurementResult {
+    pub voc_raw: u16,
+    pub nox_raw: u16,
+    pub voc_index: i32,
+    pub nox_index: i32,
+    /// Humidity compensation ticks sent with the measure command that
+    /// produced this sample, exactly as `prepare_temp_hum_params` encoded
+    /// them. `None` when no compensation was active (the datasheet defaults
+    /// went out). Not part of the serialized record.
+    pub humidity_comp_ticks: Option<u16>,
+    /// Temperature compensation ticks sent alongside `humidity_comp_ticks`.
+    pub temp_comp_ticks: Option<u16>,
+    /// 0–100 data-quality score, see `quality::QualityFactors::score`. 0 when
+    /// unscored, including after decoding a record (it is not serialized).
+    pub quality: u8,
+    /// Whether this sample was temperature/humidity compensated. Not
+    /// serialized; `Default` after decoding a record.
+    pub compensation: CompensationState,
+}
+
+impl MeasurementResult {
+    /// Serialize to big-endian bytes (same byte order the sensor uses).
+    pub fn to_bytes(&self) -> [u8; MEASUREMENT_LEN] {
+        let mut out = [0u8; MEASUREMENT_LEN];
+        out[0..2].copy_from_slice(&self.voc_raw.to_be_bytes());
+        out[2..4].copy_from_slice(&self.nox_raw.to_be_bytes());
+        out[4..8].copy_from_slice(&self.voc_index.to_be_bytes());
+        out[8..12].copy_from_slice(&self.nox_index.to_be_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8; MEASUREMENT_LEN]) -> Self {
+        Self {
+            voc_raw: u16::from_be_bytes([bytes[0], bytes[1]]),
+            nox_raw: u16::from_be_bytes([bytes[2], bytes[3]]),
+            voc_index: i32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]),
+            nox_index: i32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]),
+            humidity_comp_ticks: None,
+            temp_comp_ticks: None,
+            quality: 0,
+            compensation: CompensationState::Default,
+        }
+    }
+
+    /// CRC-8 (same polynomial as the SGP41 frames) over the serialized bytes.
+    pub fn checksum(&self) -> u8 {
+        calculate_crc(&self.to_bytes())
+    }
+
+    /// Serialized bytes with the checksum appended, ready for flash or radio.
+    pub fn to_record(&self) -> [u8; RECORD_LEN] {
+        let mut out = [0u8; RECORD_LEN];
+        out[..MEASUREMENT_LEN].copy_from_slice(&self.to_bytes());
+        out[MEASUREMENT_LEN] = self.checksum();
+        out
+    }
+
+    /// Decode a record, returning `None` if the checksum doesn't match
+    /// (flash bit-rot, radio corruption, ...).
+    pub fn from_record(record: &[u8; RECORD_LEN]) -> Option<Self> {
+        let mut payload = [0u8; MEASUREMENT_LEN];
+        payload.copy_from_slice(&record[..MEASUREMENT_LEN]);
+        if calculate_crc(&payload) != record[MEASUREMENT_LEN] {
+            return None;
+        }
+        Some(Self::from_bytes(&payload))
+    }
+}
@@ -0,0 +1,367 @@
+//! MQTT topic layout, per-topic QoS and payloads. Transport-independent: the
+//! publisher formats everything here and hands it to the MQTT client.
+//!
+//! Topics live under `<base>/<device>`, where `<base>` is
+//! `MqttConfig::base_topic` and `<device>` the BLE device name (e.g.
+//! `sgp41/SGP41-A3F2`):
+//!
+//! | topic          | payload                                           | retained |
+//! |----------------|---------------------------------------------------|----------|
+//! | `availability` | `online` / `offline`                              | yes      |
+//! | `voc`          | VOC index, decimal                                | no       |
+//! | `nox`          | NOx index, decimal (never sent in VOC-only mode)  | no       |
+//! | `category`     | air-quality category label (`Good`, `Poor`, ...)   | no       |
+//! | `raw`          | `{"voc_raw":N,"nox_raw":N}`                       | no       |
+//! | `measurement`  | `readings::Measurement` as JSON (`to_json`)       | no       |
+//! | `health`       | `{"uptime_s":N,"reset_reason":S,"i2c_errors":N,"crc_errors":N,"led_unhealthy":B,"nox_degraded":B,"compensation":S}` | no |
+//!
+//! Availability follows the Last-Will-and-Testament pattern Home Assistant
+//! expects: the client registers `MqttConfig::last_will` (retained `offline`)
+//! when it connects and then publishes retained `online` itself. If the
+//! device drops off without a clean disconnect the broker publishes the will,
+//! so subscribers (and HA's `availability_topic` with
+//! `payload_available: online` / `payload_not_available: offline`) always
+//! see the current state.
+//!
+//! ## Home Assistant discovery
+//!
+//! On by default under HA's own `homeassistant` prefix; set
+//! `MqttConfig::discovery_prefix` to `None` to turn it off. Right after connecting, the publisher sends one retained
+//! config message per `DiscoveryEntity` to
+//! `<prefix>/sensor/<device>/<entity>/config`, e.g.
+//!
+//! ```text
+//! homeassistant/sensor/SGP41-A3F2/voc_index/config
+//! {"name":"VOC index","unique_id":"SGP41-A3F2_voc_index",
+//!  "state_topic":"sgp41/SGP41-A3F2/voc","state_class":"measurement",
+//!  "availability_topic":"sgp41/SGP41-A3F2/availability",
+//!  "device":{"identifiers":["SGP41-A3F2"],"name":"SGP41-A3F2",
+//!   "manufacturer":"Sensirion","model":"SGP41",
+//!   "serial_number":"00000A3FA3F2","sw_version":"0.1.0"}}
+//! ```
+//!
+//! The shared `device` block groups the VOC index, NOx index and category
+//! entities under one HA device. The category entity has no `state_class`
+//! since its state is text.
+
+use core::fmt::Write;
+use defmt::Format;
+
+use crate::ble::DeviceName;
+use crate::category::voc_category;
+use crate::csv::Cursor;
+use crate::health::HealthSnapshot;
+use crate::readings::Measurement;
+use crate::reporting::voc_only_reporting;
+
+pub const ONLINE: &[u8] = b"online";
+pub const OFFLINE: &[u8] = b"offline";
+
+/// Longest topic name: base, device name, separators and suffix.
+pub const TOPIC_MAX: usize = 64;
+/// Longest payload (the health JSON with every counter at `u32::MAX`).
+pub const PAYLOAD_MAX: usize = 192;
+/// Longest discovery config payload.
+pub const DISCOVERY_MAX: usize = 512;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+#[repr(u8)]
+pub enum QoS {
+    AtMostOnce = 0,
+    AtLeastOnce = 1,
+    ExactlyOnce = 2,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub enum Topic {
+    Availability,
+    Voc,
+    Nox,
+    Category,
+    Raw,
+    Measurement,
+    Health,
+}
+
+impl Topic {
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Topic::Availability => "availability",
+            Topic::Voc => "voc",
+            Topic::Nox => "nox",
+            Topic::Category => "category",
+            Topic::Raw => "raw",
+            Topic::Measurement => "measurement",
+            Topic::Health => "health",
+        }
+    }
+
+    /// Only availability is retained: a late subscriber must see the current
+    /// state, while a stale reading would be misleading.
+    pub fn retained(self) -> bool {
+        self == Topic::Availability
+    }
+}
+
+#[derive(Copy, Clone, Format)]
+pub struct MqttConfig {
+    pub base_topic: &'static str,
+    /// Publish Home Assistant discovery configs under this prefix on every
+    /// connect; `None` disables discovery.
+    pub discovery_prefix: Option<&'static str>,
+    pub availability_qos: QoS,
+    pub voc_qos: QoS,
+    pub nox_qos: QoS,
+    pub raw_qos: QoS,
+    pub health_qos: QoS,
+}
+
+impl Default for MqttConfig {
+    /// Indices and availability at least once; raw ticks and health are
+    /// frequent diagnostics, so losing one is fine.
+    fn default() -> Self {
+        Self {
+            base_topic: "sgp41",
+            discovery_prefix: Some("homeassistant"),
+            availability_qos: QoS::AtLeastOnce,
+            voc_qos: QoS::AtLeastOnce,
+            nox_qos: QoS::AtLeastOnce,
+            raw_qos: QoS::AtMostOnce,
+            health_qos: QoS::AtMostOnce,
+        }
+    }
+}
+
+/// Network and broker settings for the `wifi-mqtt` publisher. The broker is
+/// given as an IPv4 address; there is no DNS lookup.
+#[derive(Copy, Clone, Format)]
+pub struct WifiConfig {
+    pub ssid: &'static str,
+    pub password: &'static str,
+    pub broker_addr: [u8; 4],
+    pub broker_port: u16,
+}
+
+impl WifiConfig {
+    /// From the `WIFI_SSID`, `WIFI_PASSWORD`, `MQTT_BROKER` (dotted IPv4)
+    /// and `MQTT_PORT` build-time environment variables, so credentials stay
+    /// out of the source. `None` if SSID or broker are unset or invalid.
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            ssid: option_env!("WIFI_SSID")?,
+            password: option_env!("WIFI_PASSWORD").unwrap_or(""),
+            broker_addr: parse_ipv4(option_env!("MQTT_BROKER")?)?,
+            broker_port: match option_env!("MQTT_PORT") {
+                Some(port) => port.parse().ok()?,
+                None => 1883,
+            },
+        })
+    }
+}
+
+fn parse_ipv4(s: &str) -> Option<[u8; 4]> {
+    let mut out = [0u8; 4];
+    let mut parts = s.split('.');
+    for byte in out.iter_mut() {
+        *byte = parts.next()?.parse().ok()?;
+    }
+    parts.next().is_none().then_some(out)
+}
+
+/// A formatted topic name.
+pub struct TopicName {
+    buf: [u8; TOPIC_MAX],
+    len: usize,
+}
+
+impl TopicName {
+    pub fn as_str(&self) -> &str {
+        // Built only from `&str` pieces, so always valid UTF-8.
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+}
+
+/// Will registered at connect time: the broker publishes it (retained) if
+/// the device disappears without disconnecting.
+pub struct LastWill {
+    pub topic: TopicName,
+    pub payload: &'static [u8],
+    pub qos: QoS,
+    pub retain: bool,
+}
+
+impl MqttConfig {
+    pub fn qos(&self, topic: Topic) -> QoS {
+        match topic {
+            Topic::Availability => self.availability_qos,
+            // The category is derived from the VOC index, and the full
+            // measurement is mostly read for it.
+            Topic::Voc | Topic::Category | Topic::Measurement => self.voc_qos,
+            Topic::Nox => self.nox_qos,
+            Topic::Raw => self.raw_qos,
+            Topic::Health => self.health_qos,
+        }
+    }
+
+    /// `<base>/<device>/<suffix>`. Truncated names come back empty, which
+    /// the client rejects, rather than publishing to the wrong topic.
+    pub fn topic(&self, device: &DeviceName, topic: Topic) -> TopicName {
+        let mut buf = [0u8; TOPIC_MAX];
+        let mut cursor = Cursor::new(&mut buf);
+        let len = match write!(cursor, "{}/{}/{}", self.base_topic, device.as_str(), topic.suffix()) {
+            Ok(()) => cursor.into_bytes().len(),
+            Err(_) => 0,
+        };
+        TopicName { buf, len }
+    }
+
+    pub fn last_will(&self, device: &DeviceName) -> LastWill {
+        LastWill {
+            topic: self.topic(device, Topic::Availability),
+            payload: OFFLINE,
+            qos: self.availability_qos,
+            retain: true,
+        }
+    }
+}
+
+/// Payload of a reading topic (`Voc`, `Nox`, `Category` or `Raw`). `None` when there is
+/// nothing to publish: other topics, or `Nox` in VOC-only mode.
+pub fn reading_payload<'a>(
+    topic: Topic,
+    result: &Measurement,
+    buf: &'a mut [u8; PAYLOAD_MAX],
+) -> Option<&'a [u8]> {
+    let mut cursor = Cursor::new(buf);
+    match topic {
+        Topic::Voc => write!(cursor, "{}", result.voc_index).ok()?,
+        Topic::Nox if !voc_only_reporting() => write!(cursor, "{}", result.nox_index).ok()?,
+        Topic::Category => write!(cursor, "{}", voc_category(result.voc_index).label()).ok()?,
+        Topic::Raw => write!(
+            cursor,
+            "{{\"voc_raw\":{},\"nox_raw\":{}}}",
+            result.voc_raw, result.nox_raw
+        )
+        .ok()?,
+        _ => return None,
+    }
+    Some(cursor.into_bytes())
+}
+
+/// Payload of the `Health` topic.
+pub fn health_payload<'a>(health: &HealthSnapshot, buf: &'a mut [u8; PAYLOAD_MAX]) -> &'a [u8] {
+    let mut cursor = Cursor::new(buf);
+    // Cannot fail: `PAYLOAD_MAX` covers the widest counters.
+    let _ = write!(
+        cursor,
+        "{{\"uptime_s\":{},\"reset_reason\":\"{}\",\"i2c_errors\":{},\"crc_errors\":{},\"led_unhealthy\":{},\"nox_degraded\":{},\"compensation\":\"{}\"}}",
+        health.uptime_s,
+        health.reset_reason.label(),
+        health.i2c_errors,
+        health.crc_errors,
+        health.led_unhealthy,
+        health.nox_degraded,
+        health.compensation.label()
+    );
+    cursor.into_bytes()
+}
+
+/// Entities announced through Home Assistant discovery.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub enum DiscoveryEntity {
+    VocIndex,
+    NoxIndex,
+    Category,
+}
+
+impl DiscoveryEntity {
+    /// Everything to announce; NOx is left out in VOC-only mode since its
+    /// topic never receives a value.
+    pub fn all() -> &'static [DiscoveryEntity] {
+        if voc_only_reporting() {
+            &[DiscoveryEntity::VocIndex, DiscoveryEntity::Category]
+        } else {
+            &[DiscoveryEntity::VocIndex, DiscoveryEntity::NoxIndex, DiscoveryEntity::Category]
+        }
+    }
+
+    pub fn object_id(self) -> &'static str {
+        match self {
+            DiscoveryEntity::VocIndex => "voc_index",
+            DiscoveryEntity::NoxIndex => "nox_index",
+            DiscoveryEntity::Category => "air_quality",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            DiscoveryEntity::VocIndex => "VOC index",
+            DiscoveryEntity::NoxIndex => "NOx index",
+            DiscoveryEntity::Category => "Air quality",
+        }
+    }
+
+    fn state_topic(self) -> Topic {
+        match self {
+            DiscoveryEntity::VocIndex => Topic::Voc,
+            DiscoveryEntity::NoxIndex => Topic::Nox,
+            DiscoveryEntity::Category => Topic::Category,
+        }
+    }
+}
+
+impl MqttConfig {
+    /// `<prefix>/sensor/<device>/<object_id>/config`, or `None` when
+    /// discovery is disabled.
+    pub fn discovery_topic(&self, device: &DeviceName, entity: DiscoveryEntity) -> Option<TopicName> {
+        let prefix = self.discovery_prefix?;
+        let mut buf = [0u8; TOPIC_MAX];
+        let mut cursor = Cursor::new(&mut buf);
+        let len = match write!(cursor, "{}/sensor/{}/{}/config", prefix, device.as_str(), entity.object_id()) {
+            Ok(()) => cursor.into_bytes().len(),
+            Err(_) => 0,
+        };
+        Some(TopicName { buf, len })
+    }
+
+    /// Retained discovery config for `entity` (see the module docs for the
+    /// layout). `serial` is the full 48-bit serial when it was read.
+    pub fn discovery_payload<'a>(
+        &self,
+        device: &DeviceName,
+        serial: Option<[u16; 3]>,
+        entity: DiscoveryEntity,
+        buf: &'a mut [u8; DISCOVERY_MAX],
+    ) -> Option<&'a [u8]> {
+        let name = device.as_str();
+        let state = self.topic(device, entity.state_topic());
+        let availability = self.topic(device, Topic::Availability);
+        let mut cursor = Cursor::new(buf);
+        write!(
+            cursor,
+            "{{\"name\":\"{}\",\"unique_id\":\"{}_{}\",\"state_topic\":\"{}\",",
+            entity.name(),
+            name,
+            entity.object_id(),
+            state.as_str()
+        )
+        .ok()?;
+        if entity != DiscoveryEntity::Category {
+            write!(cursor, "\"state_class\":\"measurement\",").ok()?;
+        }
+        write!(
+            cursor,
+            "\"availability_topic\":\"{}\",\"device\":{{\"identifiers\":[\"{}\"],\"name\":\"{}\",\
+             \"manufacturer\":\"Sensirion\",\"model\":\"SGP41\",",
+            availability.as_str(),
+            name,
+            name
+        )
+        .ok()?;
+        if let Some([a, b, c]) = serial {
+            write!(cursor, "\"serial_number\":\"{:04X}{:04X}{:04X}\",", a, b, c).ok()?;
+        }
+        write!(cursor, "\"sw_version\":\"{}\"}}}}", env!("CARGO_PKG_VERSION")).ok()?;
+        Some(cursor.into_bytes())
+    }
+}
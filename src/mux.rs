@@ -0,0 +1,150 @@
+//! Several SGP41s on one bus. They all answer at `SGP41_ADDR`, so each sits
+//! on its own channel of a TCA9548A I²C mux and every transaction is preceded
+//! by a channel select.
+//!
+//! Tasks get a `SensorBus` instead of the bare bus mutex:
+//! `Sgp41::new(sensor.lock().await)` locks the bus and returns it wrapped in
+//! `Muxed`, which re-selects the sensor's channel before each read and write.
+//! Selecting per transaction rather than per lock costs one extra byte on the
+//! wire, but a write/read pair can never land on another sensor's channel.
+
+use core::ops::DerefMut;
+
+use defmt::Format;
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::mutex::{Mutex, MutexGuard};
+use embedded_hal_02::blocking::i2c::{Read, Write};
+
+use crate::hal::I2cCompat;
+
+/// TCA9548A address with A0..A2 tied low.
+pub const TCA9548A_ADDR: u8 = 0x70;
+pub const TCA9548A_CHANNELS: u8 = 8;
+
+/// Most SGP41s the tasks are sized for (`pool_size` of the sensor tasks).
+pub const MAX_SENSORS: usize = 2;
+/// The sensor that drives the LED and handles control commands.
+pub const PRIMARY_SENSOR: u8 = 0;
+
+/// One downstream channel of a TCA9548A-style mux.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub struct MuxChannel {
+    pub address: u8,
+    pub channel: u8,
+}
+
+impl MuxChannel {
+    /// Channel `channel` (0..=7) of a mux at `TCA9548A_ADDR`.
+    pub const fn tca9548a(channel: u8) -> Self {
+        assert!(channel < TCA9548A_CHANNELS, "TCA9548A has channels 0..=7");
+        Self {
+            address: TCA9548A_ADDR,
+            channel,
+        }
+    }
+
+    /// Control register value enabling only this channel.
+    pub fn control_byte(&self) -> u8 {
+        1 << self.channel
+    }
+
+    /// Route the bus to this channel (all others disabled).
+    pub fn select<W: Write>(&self, i2c: &mut W) -> Result<(), W::Error> {
+        i2c.write(self.address, &[self.control_byte()])
+    }
+}
+
+/// An I²C handle that selects `mux` (if any) before every transaction.
+/// Wraps anything that derefs to the bus: `&mut I2cCompat`, a mutex guard,
+/// or a mock in tests.
+pub struct Muxed<I2C> {
+    i2c: I2C,
+    mux: Option<MuxChannel>,
+}
+
+impl<I2C> Muxed<I2C> {
+    pub fn new(i2c: I2C, mux: Option<MuxChannel>) -> Self {
+        Self { i2c, mux }
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C, E> Muxed<I2C>
+where
+    I2C: DerefMut,
+    I2C::Target: Write<Error = E>,
+{
+    fn select(&mut self) -> Result<(), E> {
+        match self.mux {
+            Some(mux) => mux.select(&mut *self.i2c),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<I2C, E> Write for Muxed<I2C>
+where
+    I2C: DerefMut,
+    I2C::Target: Write<Error = E>,
+{
+    type Error = E;
+    fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), E> {
+        self.select()?;
+        self.i2c.write(addr, bytes)
+    }
+}
+
+impl<I2C, E> Read for Muxed<I2C>
+where
+    I2C: DerefMut,
+    I2C::Target: Read<Error = E> + Write<Error = E>,
+{
+    type Error = E;
+    fn read(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), E> {
+        self.select()?;
+        self.i2c.read(addr, buf)
+    }
+}
+
+/// The shared bus as seen by one sensor's tasks.
+#[derive(Copy, Clone)]
+pub struct SensorBus {
+    /// Tags this sensor's readings (`readings::Measurement::sensor_id`) and
+    /// picks its `CONDITION_DONE` slot; below `MAX_SENSORS`.
+    pub id: u8,
+    pub bus: &'static Mutex<NoopRawMutex, I2cCompat<'static>>,
+    /// `None` when the sensor is wired straight to the bus.
+    pub mux: Option<MuxChannel>,
+}
+
+impl SensorBus {
+    /// The only sensor, no mux.
+    pub const fn single(bus: &'static Mutex<NoopRawMutex, I2cCompat<'static>>) -> Self {
+        Self {
+            id: PRIMARY_SENSOR,
+            bus,
+            mux: None,
+        }
+    }
+
+    pub const fn on_channel(
+        id: u8,
+        bus: &'static Mutex<NoopRawMutex, I2cCompat<'static>>,
+        mux: MuxChannel,
+    ) -> Self {
+        assert!((id as usize) < MAX_SENSORS, "sensor id out of range");
+        Self { id, bus, mux: Some(mux) }
+    }
+
+    pub fn is_primary(&self) -> bool {
+        self.id == PRIMARY_SENSOR
+    }
+
+    /// Lock the bus for one driver command, routed to this sensor.
+    pub async fn lock(&self) -> Muxed<MutexGuard<'static, NoopRawMutex, I2cCompat<'static>>> {
+        Muxed::new(self.bus.lock().await, self.mux)
+    }
+}
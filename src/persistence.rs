@@ -0,0 +1,135 @@
+//! Gas index algorithm state saved to flash so a power cycle doesn't throw
+//! away the learned baseline (the flash task is feature `persistence`).
+//!
+//! The record holds both algorithms' `algo::export_state` snapshots behind a
+//! magic/version header and a CRC-8. Only what `get_states` exposes (mean and
+//! standard deviation estimate) is saved; gating timers start fresh after a
+//! restore. Erased flash, an old layout or a torn write all fail to decode,
+//! and the caller falls back to fresh algorithms.
+//!
+//! The per-device index offset (`calibration::IndexOffset`) has its own
+//! record in the next sector, tied to the sensor serial it was measured for,
+//! so a swapped sensor starts uncorrected.
+
+use defmt::Format;
+use embassy_time::Duration;
+
+use crate::algo::STATE_LEN;
+use crate::calibration::IndexOffset;
+use crate::calculate_crc;
+
+const MAGIC: [u8; 4] = *b"SGPA";
+// Bump when the record layout changes; older records are then ignored.
+const VERSION: u8 = 1;
+
+/// magic, version, VOC state, NOx state, CRC-8.
+pub const SNAPSHOT_RECORD_LEN: usize = MAGIC.len() + 1 + 2 * STATE_LEN + 1;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub struct AlgorithmSnapshot {
+    pub voc: [u8; STATE_LEN],
+    pub nox: [u8; STATE_LEN],
+}
+
+impl AlgorithmSnapshot {
+    pub fn to_record(&self) -> [u8; SNAPSHOT_RECORD_LEN] {
+        let mut out = [0u8; SNAPSHOT_RECORD_LEN];
+        out[0..4].copy_from_slice(&MAGIC);
+        out[4] = VERSION;
+        out[5..5 + STATE_LEN].copy_from_slice(&self.voc);
+        out[5 + STATE_LEN..5 + 2 * STATE_LEN].copy_from_slice(&self.nox);
+        out[SNAPSHOT_RECORD_LEN - 1] = calculate_crc(&out[..SNAPSHOT_RECORD_LEN - 1]);
+        out
+    }
+
+    /// `None` for erased flash, another layout version or a bad checksum.
+    pub fn from_record(record: &[u8; SNAPSHOT_RECORD_LEN]) -> Option<Self> {
+        if record[0..4] != MAGIC || record[4] != VERSION {
+            return None;
+        }
+        if calculate_crc(&record[..SNAPSHOT_RECORD_LEN - 1]) != record[SNAPSHOT_RECORD_LEN - 1] {
+            return None;
+        }
+        let mut voc = [0u8; STATE_LEN];
+        let mut nox = [0u8; STATE_LEN];
+        voc.copy_from_slice(&record[5..5 + STATE_LEN]);
+        nox.copy_from_slice(&record[5 + STATE_LEN..5 + 2 * STATE_LEN]);
+        Some(Self { voc, nox })
+    }
+}
+
+const CALIBRATION_MAGIC: [u8; 4] = *b"SGPC";
+const CALIBRATION_VERSION: u8 = 1;
+
+/// magic, version, serial (3 words), VOC and NOx offset, CRC-8.
+pub const CALIBRATION_RECORD_LEN: usize = CALIBRATION_MAGIC.len() + 1 + 6 + 4 + 1;
+
+/// Index offset for the sensor with `serial`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub struct CalibrationRecord {
+    pub serial: [u16; 3],
+    pub offset: IndexOffset,
+}
+
+impl CalibrationRecord {
+    pub fn to_record(&self) -> [u8; CALIBRATION_RECORD_LEN] {
+        let mut out = [0u8; CALIBRATION_RECORD_LEN];
+        out[0..4].copy_from_slice(&CALIBRATION_MAGIC);
+        out[4] = CALIBRATION_VERSION;
+        for (chunk, word) in out[5..11].chunks_exact_mut(2).zip(self.serial) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        out[11..13].copy_from_slice(&self.offset.voc.to_le_bytes());
+        out[13..15].copy_from_slice(&self.offset.nox.to_le_bytes());
+        out[CALIBRATION_RECORD_LEN - 1] = calculate_crc(&out[..CALIBRATION_RECORD_LEN - 1]);
+        out
+    }
+
+    /// `None` for erased flash, another layout version or a bad checksum.
+    pub fn from_record(record: &[u8; CALIBRATION_RECORD_LEN]) -> Option<Self> {
+        if record[0..4] != CALIBRATION_MAGIC || record[4] != CALIBRATION_VERSION {
+            return None;
+        }
+        if calculate_crc(&record[..CALIBRATION_RECORD_LEN - 1]) != record[CALIBRATION_RECORD_LEN - 1] {
+            return None;
+        }
+        let word = |i: usize| u16::from_le_bytes([record[i], record[i + 1]]);
+        Some(Self {
+            serial: [word(5), word(7), word(9)],
+            offset: IndexOffset {
+                voc: i16::from_le_bytes([record[11], record[12]]),
+                nox: i16::from_le_bytes([record[13], record[14]]),
+            },
+        })
+    }
+
+    /// The offset if this record was written for `serial`.
+    pub fn offset_for(&self, serial: Option<[u16; 3]>) -> Option<IndexOffset> {
+        (serial == Some(self.serial)).then_some(self.offset)
+    }
+}
+
+#[derive(Copy, Clone, Format)]
+pub struct PersistenceConfig {
+    /// Flash offset of the record. The default is the start of the `nvs`
+    /// partition in the standard ESP-IDF partition table; nothing else in
+    /// this firmware uses it. The record is raw, not ESP-IDF NVS key/value
+    /// format.
+    pub flash_offset: u32,
+    /// Flash offset of the calibration record; its own sector, so saving the
+    /// algorithm state never erases it.
+    pub calibration_offset: u32,
+    /// How often the state is saved. Each save rewrites a flash sector, so
+    /// keep this in minutes: at 5 min a 100k-cycle sector lasts ~1 year.
+    pub save_interval: Duration,
+}
+
+impl Default for PersistenceConfig {
+    fn default() -> Self {
+        Self {
+            flash_offset: 0x9000,
+            calibration_offset: 0xA000,
+            save_interval: Duration::from_secs(5 * 60),
+        }
+    }
+}
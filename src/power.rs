@@ -0,0 +1,103 @@
+//! Deep-sleep duty cycling for battery builds (feature `low-power`).
+//!
+//! Each wake is a fresh boot: condition, take one reading, let the readings
+//! consumers publish it, switch the heater off, then deep-sleep until the
+//! next slot. Waking resets the chip, so conditioning always runs again; the
+//! hotplate has cooled and NOx is unusable until it has.
+//!
+//! Accuracy tradeoff: the gas index algorithm is built for an uninterrupted
+//! 1 Hz sample stream and learns its baseline over hours. RAM is lost in deep
+//! sleep, so every wake starts a fresh algorithm (or, with `persistence`, the
+//! last saved state), and it sees one or two samples before the device sleeps
+//! again. The indices therefore stay in their warm-up range and cannot track
+//! trends; raw ticks are the meaningful output. Use this only where battery
+//! life matters more than the index.
+
+// `duty_cycle` runs in `main` on core 0 and locks the sensor bus, whose
+// `NoopRawMutex` the sensing tasks hold on the app core under `dual-core`.
+#[cfg(feature = "dual-core")]
+compile_error!("`low-power` needs a single-core build: the duty cycle shares the core-local I2C bus, which `dual-core` moves to the app core");
+
+use defmt::{info, warn};
+use embassy_futures::select::{select, Either};
+use embassy_time::{Duration, Timer};
+use esp_hal::rtc_cntl::sleep::TimerWakeupSource;
+use esp_hal::rtc_cntl::Rtc;
+
+use crate::health::uptime;
+use crate::mux::{SensorBus, PRIMARY_SENSOR};
+use crate::readings::{Measurement, ReadingsSubscriber};
+use crate::tasks::conditioning::turn_heater_off;
+
+// Never sleep for less than this, even when the wake ran long.
+const MIN_SLEEP: Duration = Duration::from_secs(1);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, defmt::Format)]
+pub struct DutyCycle {
+    /// Time from one wake to the next, awake time included.
+    pub interval: Duration,
+    /// Time after the reading for MQTT/BLE/HTTP to send it before sleeping.
+    pub publish_grace: Duration,
+    /// Sleep anyway if no reading arrived by then (conditioning failed or
+    /// the sensor is missing). Stands in for the watchdog supervisor, which
+    /// doesn't run in this mode.
+    pub awake_timeout: Duration,
+}
+
+impl DutyCycle {
+    /// One reading every `measurement_interval`. Keep it well above the
+    /// conditioning time plus `publish_grace` (a few minutes is typical).
+    pub fn every(measurement_interval: Duration) -> Self {
+        Self {
+            interval: measurement_interval,
+            publish_grace: Duration::from_secs(3),
+            awake_timeout: Duration::from_secs(60),
+        }
+    }
+
+    /// Deep-sleep time left in this slot once the device has been awake
+    /// for `awake`.
+    pub fn sleep_time(&self, awake: Duration) -> Duration {
+        let remaining = if self.interval > awake {
+            self.interval - awake
+        } else {
+            Duration::from_ticks(0)
+        };
+        remaining.max(MIN_SLEEP)
+    }
+}
+
+/// Wait for the first reading on `readings`, give it `publish_grace` to go
+/// out, switch the heater off and deep-sleep until the next slot. Radios are
+/// powered down with the rest of the chip.
+pub async fn duty_cycle(
+    rtc: &mut Rtc<'_>,
+    bus: &SensorBus,
+    readings: &mut ReadingsSubscriber,
+    config: DutyCycle,
+) -> ! {
+    match select(next_primary(readings), Timer::after(config.awake_timeout)).await {
+        Either::First(reading) => {
+            info!("Duty cycle: got {}", reading);
+            Timer::after(config.publish_grace).await;
+        }
+        Either::Second(()) => warn!("Duty cycle: no reading within {} s", config.awake_timeout.as_secs()),
+    }
+
+    turn_heater_off(bus).await;
+    let sleep = config.sleep_time(uptime());
+    info!("Deep sleep for {} s", sleep.as_secs());
+    let wakeup = TimerWakeupSource::new(core::time::Duration::from_millis(sleep.as_millis()));
+    rtc.sleep_deep(&[&wakeup])
+}
+
+// The wake exists for the primary sensor's sample; a second sensor's reading
+// arriving first must not send the chip back to sleep.
+async fn next_primary(readings: &mut ReadingsSubscriber) -> Measurement {
+    loop {
+        let reading = readings.next_message_pure().await;
+        if reading.sensor_id == PRIMARY_SENSOR {
+            return reading;
+        }
+    }
+}
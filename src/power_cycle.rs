@@ -0,0 +1,89 @@
+use defmt::Format;
+
+/// What the measurement task does once a sensor power cycle is suspected.
+#[derive(Copy, Clone, PartialEq, Eq, Format)]
+pub enum PowerCycleResponse {
+    /// Only log the event.
+    LogOnly,
+    /// Re-run conditioning before resuming measurements.
+    Recondition,
+    /// Re-run conditioning and reset both gas index algorithms.
+    ReconditionAndReset,
+}
+
+#[derive(Copy, Clone)]
+pub struct PowerCycleConfig {
+    /// VOC raw step (ticks) between consecutive samples treated as a power cycle.
+    pub voc_jump_ticks: u16,
+    pub response: PowerCycleResponse,
+    /// Conditioning duration used when re-conditioning (s).
+    pub recondition_secs: u8,
+}
+
+impl Default for PowerCycleConfig {
+    fn default() -> Self {
+        Self {
+            voc_jump_ticks: 5000,
+            response: PowerCycleResponse::ReconditionAndReset,
+            recondition_secs: 10,
+        }
+    }
+}
+
+/// Why a power cycle is suspected.
+#[derive(Copy, Clone, PartialEq, Eq, Format)]
+pub enum PowerCycleCause {
+    /// NOx raw fell back to 0 after valid readings. A freshly powered SGP41
+    /// reports 0 NOx ticks until its NOx pixel has been conditioned again.
+    NoxReset,
+    /// VOC raw moved by more than `voc_jump_ticks` in a single sample.
+    VocJump { from: u16, to: u16 },
+}
+
+/// Heuristic detector fed with every raw sample.
+pub struct PowerCycleDetector {
+    voc_jump_ticks: u16,
+    last_voc: Option<u16>,
+    nox_seen: bool,
+}
+
+impl PowerCycleDetector {
+    pub fn new(config: &PowerCycleConfig) -> Self {
+        Self {
+            voc_jump_ticks: config.voc_jump_ticks,
+            last_voc: None,
+            nox_seen: false,
+        }
+    }
+
+    /// Feed one raw sample; returns the cause if it looks like the sensor lost power.
+    pub fn update(&mut self, voc_raw: u16, nox_raw: u16) -> Option<PowerCycleCause> {
+        let cause = if self.nox_seen && nox_raw == 0 {
+            Some(PowerCycleCause::NoxReset)
+        } else {
+            match self.last_voc {
+                Some(last) if last.abs_diff(voc_raw) > self.voc_jump_ticks => {
+                    Some(PowerCycleCause::VocJump {
+                        from: last,
+                        to: voc_raw,
+                    })
+                }
+                _ => None,
+            }
+        };
+
+        if cause.is_some() {
+            self.reset();
+        } else {
+            self.last_voc = Some(voc_raw);
+            self.nox_seen |= nox_raw != 0;
+        }
+        cause
+    }
+
+    /// Forget history, e.g. after re-conditioning.
+    pub fn reset(&mut self) {
+        self.last_voc = None;
+        self.nox_seen = false;
+    }
+}
@@ -0,0 +1,66 @@
+//! Prometheus text exposition format (version 0.0.4) of a reading, for
+//! `GET /prometheus` on the HTTP task. Written into a fixed buffer, no
+//! allocation.
+//!
+//! ```text
+//! # HELP sgp41_voc_index VOC index (1..500, 100 = typical air).
+//! # TYPE sgp41_voc_index gauge
+//! sgp41_voc_index{serial="00000A3FA3F2"} 100
+//! ```
+//!
+//! Four gauges: `sgp41_voc_index`, `sgp41_voc_raw`, `sgp41_nox_index` and
+//! `sgp41_nox_raw`. The `serial` label is omitted if the serial number
+//! couldn't be read; the NOx gauges are omitted in VOC-only mode. No sample
+//! timestamps: Prometheus stamps them at scrape time.
+
+use core::fmt::Write;
+
+use crate::csv::Cursor;
+use crate::readings::Measurement;
+use crate::reporting::voc_only_reporting;
+
+/// `Content-Type` of the exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Longest output: all four gauges with their help text, serial label and
+/// widest values.
+pub const METRICS_MAX: usize = 512;
+
+/// Format `reading` into `buf`. `None` only if `buf` is too small, which
+/// `METRICS_MAX` rules out.
+pub fn format_metrics<'a>(
+    serial: Option<[u16; 3]>,
+    reading: &Measurement,
+    buf: &'a mut [u8; METRICS_MAX],
+) -> Option<&'a [u8]> {
+    let mut cursor = Cursor::new(buf);
+    gauge(&mut cursor, serial, "sgp41_voc_index", VOC_INDEX_HELP, reading.voc_index).ok()?;
+    gauge(&mut cursor, serial, "sgp41_voc_raw", VOC_RAW_HELP, reading.voc_raw as i32).ok()?;
+    if !voc_only_reporting() {
+        gauge(&mut cursor, serial, "sgp41_nox_index", NOX_INDEX_HELP, reading.nox_index).ok()?;
+        gauge(&mut cursor, serial, "sgp41_nox_raw", NOX_RAW_HELP, reading.nox_raw as i32).ok()?;
+    }
+    Some(cursor.into_bytes())
+}
+
+const VOC_INDEX_HELP: &str = "VOC index (1..500, 100 = typical air).";
+const VOC_RAW_HELP: &str = "Raw VOC signal (ticks).";
+const NOX_INDEX_HELP: &str = "NOx index (1..500, 1 = typical air).";
+const NOX_RAW_HELP: &str = "Raw NOx signal (ticks).";
+
+// One gauge: HELP and TYPE lines, then the sample.
+fn gauge(
+    cursor: &mut Cursor,
+    serial: Option<[u16; 3]>,
+    name: &str,
+    help: &str,
+    value: i32,
+) -> core::fmt::Result {
+    writeln!(cursor, "# HELP {} {}", name, help)?;
+    writeln!(cursor, "# TYPE {} gauge", name)?;
+    write!(cursor, "{}", name)?;
+    if let Some([a, b, c]) = serial {
+        write!(cursor, "{{serial=\"{:04X}{:04X}{:04X}\"}}", a, b, c)?;
+    }
+    writeln!(cursor, " {}", value)
+}
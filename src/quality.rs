@@ -0,0 +1,71 @@
+//! Per-reading data-quality score: one 0–100 trust value for consumers that
+//! weight or filter readings.
+//!
+//! The score starts at 100 and each factor that applies subtracts a fixed
+//! penalty, saturating at 0:
+//!
+//! | factor                                            | penalty |
+//! |---------------------------------------------------|---------|
+//! | gas index still in its initial blackout (warm-up) | 50      |
+//! | raw VOC jumped more than `OUTLIER_RAW_JUMP` ticks  | 30      |
+//! | live compensation stale (defaults sent instead)   | 25      |
+//! | a frame failed its CRC since the previous sample  | 20      |
+//! | no compensation configured at all                 | 10      |
+//!
+//! So a clean reading with fresh compensation scores 100, and a warm-up
+//! reading with stale compensation scores 25.
+
+use defmt::Format;
+
+use crate::compensation::CompensationState;
+
+pub const WARM_UP_PENALTY: u8 = 50;
+pub const OUTLIER_PENALTY: u8 = 30;
+pub const STALE_COMPENSATION_PENALTY: u8 = 25;
+pub const CRC_PENALTY: u8 = 20;
+pub const UNCOMPENSATED_PENALTY: u8 = 10;
+
+/// Sample-to-sample VOC raw change treated as an outlier. The raw signal
+/// drifts by tens of ticks per second in normal air; a jump this large is a
+/// glitch or a sensor disturbance rather than a real change.
+pub const OUTLIER_RAW_JUMP: u16 = 5_000;
+
+/// The factors behind one reading's score.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub struct QualityFactors {
+    /// A measurement frame failed its CRC since the previous valid sample.
+    pub recent_crc_error: bool,
+    /// The gas index is still 0 (algorithm blackout after start or reset).
+    pub warming_up: bool,
+    pub compensation: CompensationState,
+    /// See `is_outlier`.
+    pub outlier: bool,
+}
+
+impl QualityFactors {
+    /// 0–100 score from the penalties in the module table.
+    pub fn score(&self) -> u8 {
+        let mut penalty = 0u8;
+        if self.warming_up {
+            penalty = penalty.saturating_add(WARM_UP_PENALTY);
+        }
+        if self.outlier {
+            penalty = penalty.saturating_add(OUTLIER_PENALTY);
+        }
+        penalty = penalty.saturating_add(match self.compensation {
+            CompensationState::Compensated => 0,
+            CompensationState::Stale => STALE_COMPENSATION_PENALTY,
+            CompensationState::Default => UNCOMPENSATED_PENALTY,
+        });
+        if self.recent_crc_error {
+            penalty = penalty.saturating_add(CRC_PENALTY);
+        }
+        100u8.saturating_sub(penalty)
+    }
+}
+
+/// Whether `voc_raw` jumped more than `OUTLIER_RAW_JUMP` from the previous
+/// valid sample (never true for the first one).
+pub fn is_outlier(previous: Option<u16>, voc_raw: u16) -> bool {
+    previous.is_some_and(|prev| prev.abs_diff(voc_raw) > OUTLIER_RAW_JUMP)
+}
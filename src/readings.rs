@@ -0,0 +1,119 @@
+//! Readings published by the measurement task, one per sample its
+//! `reporting::Reporter` lets through (smoothed per `ReportPolicy`), so
+//! BLE/MQTT/display consumers can subscribe instead of being wired into the
+//! sensor loop.
+//!
+//! Consumers take a subscriber at startup and pass it into their task:
+//!
+//! ```ignore
+//! let readings = READINGS.subscriber().expect("too many readings subscribers");
+//! spawner.must_spawn(display_task(readings));
+//! ```
+//!
+//! The channel is `CriticalSectionRawMutex`, so subscribers may run on either
+//! core. The publisher never waits: a subscriber that falls more than
+//! `READINGS_CAPACITY` readings behind gets a `WaitResult::Lagged` and skips
+//! the oldest ones.
+//!
+//! With feature `serde`, `Measurement` derives `Serialize`/`Deserialize`, and
+//! `Measurement::to_json` formats it with `serde-json-core` (no allocation).
+//! In VOC-only mode (`reporting::voc_only_reporting`) the NOx fields are left
+//! out of the serialized form, for MQTT and HTTP alike.
+
+use defmt::Format;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::pubsub::{PubSubChannel, Subscriber};
+
+use crate::baseline::BaselineDelta;
+use crate::compensation::CompensationState;
+use crate::measurement::MeasurementResult;
+#[cfg(feature = "serde")]
+use crate::reporting::voc_only_reporting;
+
+/// Longest `Measurement::to_json` output: all fields at their widest.
+#[cfg(feature = "serde")]
+pub const MEASUREMENT_JSON_MAX: usize = 296;
+
+pub const READINGS_CAPACITY: usize = 4;
+pub const READINGS_SUBSCRIBERS: usize = 7;
+// Only the measurement tasks publish, through `immediate_publisher`, which
+// doesn't take a slot.
+pub const READINGS_PUBLISHERS: usize = 1;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Measurement {
+    /// Which sensor took the sample (`mux::SensorBus::id`); 0 on
+    /// single-sensor builds.
+    pub sensor_id: u8,
+    pub voc_raw: u16,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "nox_hidden"))]
+    pub nox_raw: u16,
+    pub voc_index: i32,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "nox_hidden"))]
+    pub nox_index: i32,
+    /// 0–100 data-quality score (`quality::QualityFactors::score`).
+    pub quality: u8,
+    pub compensation: CompensationState,
+    /// Compensation ticks sent with the measure command; `None` when the
+    /// datasheet defaults went out.
+    pub humidity_comp_ticks: Option<u16>,
+    pub temp_comp_ticks: Option<u16>,
+    /// Uptime (ms) when the sample was taken: one `Instant` per measurement
+    /// cycle, the same one the task logs.
+    pub timestamp_ms: u64,
+    /// Indices against the rolling baseline (`baseline::RollingBaseline`);
+    /// `None` when it is disabled or has no completed bucket yet.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub baseline_delta: Option<BaselineDelta>,
+}
+
+impl Measurement {
+    pub fn from_result(result: &MeasurementResult, sensor_id: u8, timestamp_ms: u64) -> Self {
+        Self {
+            sensor_id,
+            voc_raw: result.voc_raw,
+            nox_raw: result.nox_raw,
+            voc_index: result.voc_index,
+            nox_index: result.nox_index,
+            quality: result.quality,
+            compensation: result.compensation,
+            humidity_comp_ticks: result.humidity_comp_ticks,
+            temp_comp_ticks: result.temp_comp_ticks,
+            timestamp_ms,
+            baseline_delta: None,
+        }
+    }
+
+    /// JSON object with the field names above, written into `buf`. `None`
+    /// if `buf` is too small (`MEASUREMENT_JSON_MAX` always fits).
+    #[cfg(feature = "serde")]
+    pub fn to_json<'b>(&self, buf: &'b mut [u8]) -> Option<&'b [u8]> {
+        let len = serde_json_core::to_slice(self, buf).ok()?;
+        Some(&buf[..len])
+    }
+}
+
+// Serde skip predicate for the NOx fields.
+#[cfg(feature = "serde")]
+pub(crate) fn nox_hidden<T>(_: &T) -> bool {
+    voc_only_reporting()
+}
+
+pub type ReadingsChannel = PubSubChannel<
+    CriticalSectionRawMutex,
+    Measurement,
+    READINGS_CAPACITY,
+    READINGS_SUBSCRIBERS,
+    READINGS_PUBLISHERS,
+>;
+pub type ReadingsSubscriber = Subscriber<
+    'static,
+    CriticalSectionRawMutex,
+    Measurement,
+    READINGS_CAPACITY,
+    READINGS_SUBSCRIBERS,
+    READINGS_PUBLISHERS,
+>;
+
+pub static READINGS: ReadingsChannel = PubSubChannel::new();
@@ -0,0 +1,147 @@
+//! Decides when a reading is published: on a meaningful change, or as a
+//! heartbeat once `heartbeat_interval` has passed without any publish, so
+//! consumers always get a data point at least that often.
+//!
+//! Published indices can optionally be smoothed with a small EMA
+//! (`ReportPolicy::index_smoothing`) to take the point-to-point jitter out of
+//! graphs. This only touches what is published: raw ticks, the gas index
+//! algorithm, the LED and escalation all keep using the unsmoothed values.
+//! Smoothing adds lag: with factor `a` a step change needs about `1/a`
+//! samples to show up mostly (~63 %), e.g. ~3 s at 0.3 and 1 s sampling.
+//!
+//! Also holds the VOC-only presentation switch: when set, every output layer
+//! (LED, logs, wire format, BLE, MQTT, JSON) hides NOx so the SGP41 looks like
+//! an SGP40. NOx is still measured and processed internally.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use defmt::Format;
+use embassy_time::{Duration, Instant};
+
+use crate::measurement::MeasurementResult;
+
+static VOC_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Whether outputs must suppress NOx. Every output layer checks this.
+pub fn voc_only_reporting() -> bool {
+    VOC_ONLY.load(Ordering::Relaxed)
+}
+
+pub fn set_voc_only_reporting(voc_only: bool) {
+    VOC_ONLY.store(voc_only, Ordering::Relaxed);
+}
+
+#[derive(Copy, Clone, Format)]
+pub struct ReportPolicy {
+    /// Publish when either index moved by at least this much since the last
+    /// publish. `None` publishes every reading.
+    pub change_threshold: Option<u16>,
+    /// Maximum time between publishes, even if nothing changed.
+    pub heartbeat_interval: Duration,
+    /// Present the device as VOC-only (SGP40-compatible) on all outputs.
+    pub voc_only_reporting: bool,
+    /// EMA factor (0, 1] for the published indices: the weight of the newest
+    /// sample, so smaller is smoother and slower. `None` (default) publishes
+    /// indices unsmoothed.
+    pub index_smoothing: Option<f32>,
+}
+
+impl Default for ReportPolicy {
+    fn default() -> Self {
+        Self {
+            change_threshold: None,
+            heartbeat_interval: Duration::from_secs(60),
+            voc_only_reporting: false,
+            index_smoothing: None,
+        }
+    }
+}
+
+/// Light EMA over the published VOC/NOx indices.
+pub struct IndexSmoother {
+    factor: Option<f32>,
+    state: Option<(f32, f32)>,
+}
+
+impl IndexSmoother {
+    /// `factor` is clamped to (0, 1]; `None` or 1.0 passes indices through.
+    pub fn new(factor: Option<f32>) -> Self {
+        Self {
+            factor: factor.filter(|f| *f > 0.0).map(|f| f.min(1.0)),
+            state: None,
+        }
+    }
+
+    /// `result` with its indices smoothed; raw ticks and everything else are
+    /// copied unchanged. A 0 VOC index (algorithm blackout) restarts the
+    /// average so warm-up doesn't drag the first real values down.
+    pub fn apply(&mut self, result: &MeasurementResult) -> MeasurementResult {
+        let Some(factor) = self.factor else {
+            return *result;
+        };
+        if result.voc_index == 0 {
+            self.state = None;
+            return *result;
+        }
+        let (voc, nox) = (result.voc_index as f32, result.nox_index as f32);
+        let (voc, nox) = match self.state {
+            None => (voc, nox),
+            Some((prev_voc, prev_nox)) => (
+                prev_voc + factor * (voc - prev_voc),
+                prev_nox + factor * (nox - prev_nox),
+            ),
+        };
+        self.state = Some((voc, nox));
+        MeasurementResult {
+            voc_index: libm::roundf(voc) as i32,
+            nox_index: libm::roundf(nox) as i32,
+            ..*result
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Format)]
+pub enum PublishReason {
+    Changed,
+    Heartbeat,
+}
+
+pub struct Reporter {
+    policy: ReportPolicy,
+    last: Option<(MeasurementResult, Instant)>,
+}
+
+impl Reporter {
+    pub fn new(policy: ReportPolicy) -> Self {
+        Self { policy, last: None }
+    }
+
+    /// Whether to publish `result`. A change and a due heartbeat on the same
+    /// sample yield a single `Changed` publish, and any publish restarts the
+    /// heartbeat timer, so the two never duplicate.
+    pub fn should_publish(&mut self, result: &MeasurementResult, now: Instant) -> Option<PublishReason> {
+        let reason = match self.last {
+            None => Some(PublishReason::Changed),
+            Some((last, at)) => {
+                let changed = match self.policy.change_threshold {
+                    None => true,
+                    Some(threshold) => {
+                        last.voc_index.abs_diff(result.voc_index) >= threshold as u32
+                            || (!voc_only_reporting()
+                                && last.nox_index.abs_diff(result.nox_index) >= threshold as u32)
+                    }
+                };
+                if changed {
+                    Some(PublishReason::Changed)
+                } else if now.saturating_duration_since(at) >= self.policy.heartbeat_interval {
+                    Some(PublishReason::Heartbeat)
+                } else {
+                    None
+                }
+            }
+        };
+        if reason.is_some() {
+            self.last = Some((*result, now));
+        }
+        reason
+    }
+}
@@ -0,0 +1,25 @@
+//! Optional bound on a measurement run, for timed data-collection experiments.
+
+use defmt::Format;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant};
+
+/// Signalled with the measurement count when a bounded run finishes.
+pub static RUN_COMPLETE: Signal<CriticalSectionRawMutex, u32> = Signal::new();
+
+/// Stop after whichever limit is hit first; both `None` runs forever.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Format)]
+pub struct RunLimit {
+    pub max_measurements: Option<u32>,
+    pub max_duration: Option<Duration>,
+}
+
+impl RunLimit {
+    /// Whether a run that started at `started` and produced `count`
+    /// measurements is over.
+    pub fn reached(&self, count: u32, started: Instant) -> bool {
+        self.max_measurements.is_some_and(|max| count >= max)
+            || self.max_duration.is_some_and(|max| started.elapsed() >= max)
+    }
+}
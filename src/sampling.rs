@@ -0,0 +1,45 @@
+//! One measurement cycle split into bus-generic steps, so the ordering rules
+//! (a raw frame reaches the gas index algorithm only after its CRCs check
+//! out) can be exercised against a mock I²C bus with the task's own code:
+//! `measure_or_rest`, then `process_raw` on success.
+
+use defmt::warn;
+use embedded_hal_02::blocking::i2c::{Read, Write};
+
+use crate::algo::IndexProcessor;
+use crate::compensation::CompensationState;
+use crate::driver::{Sgp41, Sgp41Error};
+use crate::measurement::MeasurementResult;
+
+/// Feed validated raw ticks to the VOC and NOx processors.
+pub fn process_raw<P: IndexProcessor + ?Sized>(
+    voc_raw: u16,
+    nox_raw: u16,
+    voc: &mut P,
+    nox: &mut P,
+) -> MeasurementResult {
+    MeasurementResult {
+        voc_raw,
+        nox_raw,
+        voc_index: voc.process(voc_raw as i32),
+        nox_index: nox.process(nox_raw as i32),
+        humidity_comp_ticks: None,
+        temp_comp_ticks: None,
+        quality: 0,
+        compensation: CompensationState::Default,
+    }
+}
+
+/// The measurement task's bus step: measure with `params` and, if that
+/// fails, switch the heater off before the task backs off for one interval.
+/// A failure costs exactly this sample; the next call sends a fresh command.
+pub async fn measure_or_rest<I, E>(sgp41: &mut Sgp41<I>, params: [u8; 6]) -> Result<(u16, u16), Sgp41Error<E>>
+where
+    I: Read<Error = E> + Write<Error = E>,
+{
+    let read = sgp41.measure_raw_signals_with(params).await;
+    if read.is_err() && sgp41.turn_heater_off().await.is_err() {
+        warn!("Failed to turn SGP41 heater off");
+    }
+    read
+}
@@ -0,0 +1,192 @@
+//! Circular CSV log on an SPI SD card (feature `sdcard`).
+//!
+//! Rows use the schema in `csv`, one per reading published on
+//! `readings::READINGS` by the primary sensor, so the log holds exactly what
+//! BLE/MQTT see (every sample with the default `ReportPolicy`).
+//!
+//! Once wall-clock time is known, files are named by UTC date and part,
+//! `YYMMDDnn.CSV`: a new part starts when the current one reaches
+//! `max_file_bytes`, a new date at midnight UTC. Rows from before the clock
+//! is set go to `LOGnnnnn.CSV` with a running number. Only the newest
+//! `max_files` files are kept; undated logs count as older than any dated one,
+//! so they are pruned first.
+
+use defmt::Format;
+
+#[derive(Copy, Clone, Format)]
+pub struct SdLogConfig {
+    pub max_file_bytes: u32,
+    /// Logs kept on the card, the one being written included; 0 is treated
+    /// as 1.
+    pub max_files: u32,
+}
+
+impl Default for SdLogConfig {
+    fn default() -> Self {
+        Self {
+            // ~1 MiB is about a day of 1 Hz rows
+            max_file_bytes: 1024 * 1024,
+            max_files: 90,
+        }
+    }
+}
+
+/// Last part number of a day; further rows keep growing it.
+pub const MAX_PART: u8 = 99;
+
+/// One log file, ordered oldest first.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Format)]
+pub enum LogFile {
+    /// Written before the clock was set.
+    Undated(u32),
+    /// `day` is the UTC day number (`utc_day`), `part` counts size rotations
+    /// within it.
+    Dated { day: u32, part: u8 },
+}
+
+impl LogFile {
+    /// 8.3 file name: `LOG00042.CSV` or `25101601.CSV` (2025-10-16, part 1).
+    pub fn name(&self) -> [u8; 12] {
+        match *self {
+            LogFile::Undated(n) => {
+                let mut name = *b"LOG00000.CSV";
+                write_digits(&mut name[3..8], n);
+                name
+            }
+            LogFile::Dated { day, part } => {
+                let (year, month, date) = civil_from_days(day);
+                let mut name = *b"00000000.CSV";
+                write_digits(&mut name[0..2], year % 100);
+                write_digits(&mut name[2..4], month);
+                write_digits(&mut name[4..6], date);
+                write_digits(&mut name[6..8], part as u32);
+                name
+            }
+        }
+    }
+
+    /// The log an 8.3 name (`LOG00042`, `CSV`) refers to, if it is one of ours.
+    pub fn parse(base: &[u8], ext: &[u8]) -> Option<Self> {
+        if ext != b"CSV" || base.len() != 8 {
+            return None;
+        }
+        if &base[..3] == b"LOG" {
+            return parse_digits(&base[3..]).map(LogFile::Undated);
+        }
+        let year = 2000 + parse_digits(&base[0..2])?;
+        let month = parse_digits(&base[2..4])?;
+        let date = parse_digits(&base[4..6])?;
+        let part = parse_digits(&base[6..8])? as u8;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&date) {
+            return None;
+        }
+        Some(LogFile::Dated {
+            day: days_from_civil(year, month, date),
+            part,
+        })
+    }
+}
+
+/// Which file each row goes to, rotating by size and date.
+pub struct Rotation {
+    current: Option<LogFile>,
+    written: u32,
+    newest_dated: Option<LogFile>,
+    next_undated: u32,
+}
+
+impl Rotation {
+    /// Carry on from the newest undated number and dated log found on the
+    /// card. Undated rows always start a fresh file; dated rows append to the
+    /// day's newest part if there is one.
+    pub fn resume(newest_undated: Option<u32>, newest_dated: Option<LogFile>) -> Self {
+        Self {
+            current: None,
+            written: 0,
+            newest_dated,
+            next_undated: newest_undated.map_or(0, |n| n + 1),
+        }
+    }
+
+    /// File for a row taken on UTC day `day` (`None` before the clock is set),
+    /// and whether it is a different one from the previous row's.
+    pub fn file_for(&mut self, day: Option<u32>, max_file_bytes: u32) -> (LogFile, bool) {
+        let full = self.written >= max_file_bytes;
+        let next = match (self.current, day) {
+            (Some(LogFile::Dated { day: d, part }), Some(day)) if d == day => {
+                if full && part < MAX_PART {
+                    LogFile::Dated { day, part: part + 1 }
+                } else {
+                    LogFile::Dated { day, part }
+                }
+            }
+            (_, Some(day)) => match self.newest_dated {
+                Some(LogFile::Dated { day: d, part }) if d == day => LogFile::Dated { day, part },
+                _ => LogFile::Dated { day, part: 0 },
+            },
+            (Some(LogFile::Undated(n)), None) if !full => LogFile::Undated(n),
+            (_, None) => {
+                self.next_undated += 1;
+                LogFile::Undated(self.next_undated - 1)
+            }
+        };
+        let changed = self.current != Some(next);
+        if changed {
+            self.current = Some(next);
+            // The caller reports the length of a resumed file via `wrote`.
+            self.written = 0;
+            if matches!(next, LogFile::Dated { .. }) {
+                self.newest_dated = Some(next);
+            }
+        }
+        (next, changed)
+    }
+
+    /// Size of the current file after a write.
+    pub fn wrote(&mut self, file_len: u32) {
+        self.written = file_len;
+    }
+}
+
+/// UTC day number of a Unix time, used to name files and rotate on date change.
+pub fn utc_day(unix_ms: u64) -> u32 {
+    (unix_ms / 86_400_000) as u32
+}
+
+fn write_digits(out: &mut [u8], mut n: u32) {
+    for c in out.iter_mut().rev() {
+        *c = b'0' + (n % 10) as u8;
+        n /= 10;
+    }
+}
+
+fn parse_digits(digits: &[u8]) -> Option<u32> {
+    digits.iter().try_fold(0u32, |acc, &c| {
+        c.is_ascii_digit().then(|| acc * 10 + (c - b'0') as u32)
+    })
+}
+
+// Gregorian calendar from days since 1970-01-01 and back, after Howard
+// Hinnant's `civil_from_days`/`days_from_civil` (days ≥ 0 only).
+fn civil_from_days(day: u32) -> (u32, u32, u32) {
+    let z = day + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let date = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as u32;
+    (year, month, date)
+}
+
+fn days_from_civil(year: u32, month: u32, date: u32) -> u32 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + date - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
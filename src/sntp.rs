@@ -0,0 +1,41 @@
+//! SNTP (RFC 4330) client packets: the request and the decoding of the
+//! server's transmit timestamp into Unix milliseconds for
+//! `wall_clock::set_unix_time_ms`. Transport-independent; `tasks::sntp`
+//! sends them over UDP.
+
+/// Length of an NTP packet without extension fields.
+pub const PACKET_LEN: usize = 48;
+pub const NTP_PORT: u16 = 123;
+
+// Seconds from the NTP epoch (1900) to the Unix epoch (1970).
+const NTP_TO_UNIX_S: u64 = 2_208_988_800;
+
+const MODE_CLIENT: u8 = 3;
+const MODE_SERVER: u8 = 4;
+const VERSION: u8 = 4;
+
+/// Client request: version 4, mode 3, everything else zero.
+pub fn request() -> [u8; PACKET_LEN] {
+    let mut packet = [0u8; PACKET_LEN];
+    packet[0] = VERSION << 3 | MODE_CLIENT;
+    packet
+}
+
+/// Unix time (ms) from a server reply's transmit timestamp. `None` for a
+/// short packet, a reply that isn't from a server, a kiss-o'-death (stratum
+/// 0) or an unsynchronized server (leap indicator 3).
+pub fn unix_ms_from_reply(reply: &[u8]) -> Option<u64> {
+    if reply.len() < PACKET_LEN {
+        return None;
+    }
+    let leap = reply[0] >> 6;
+    let mode = reply[0] & 0x07;
+    let stratum = reply[1];
+    if mode != MODE_SERVER || stratum == 0 || leap == 3 {
+        return None;
+    }
+    let seconds = u32::from_be_bytes([reply[40], reply[41], reply[42], reply[43]]) as u64;
+    let fraction = u32::from_be_bytes([reply[44], reply[45], reply[46], reply[47]]) as u64;
+    let unix_s = seconds.checked_sub(NTP_TO_UNIX_S)?;
+    Some(unix_s * 1000 + (fraction * 1000 >> 32))
+}
@@ -0,0 +1,71 @@
+//! Soak test: track raw ticks and indices over a long run, then report
+//! min/max/mean/drift together with the bus error counts seen meanwhile.
+
+use defmt::info;
+use embassy_time::{Duration, Instant};
+
+use crate::health::{self, HealthSnapshot};
+use crate::measurement::MeasurementResult;
+use crate::stats::RunningStats;
+
+pub struct SoakTest {
+    started_at: Instant,
+    duration: Duration,
+    errors_at_start: HealthSnapshot,
+    voc_raw: RunningStats,
+    nox_raw: RunningStats,
+    voc_index: RunningStats,
+    nox_index: RunningStats,
+}
+
+impl SoakTest {
+    pub fn start(duration: Duration) -> Self {
+        info!("Soak test started ({} s)", duration.as_secs());
+        Self {
+            started_at: Instant::now(),
+            duration,
+            errors_at_start: health::snapshot(),
+            voc_raw: RunningStats::new(),
+            nox_raw: RunningStats::new(),
+            voc_index: RunningStats::new(),
+            nox_index: RunningStats::new(),
+        }
+    }
+
+    pub fn update(&mut self, result: &MeasurementResult) {
+        self.voc_raw.push(result.voc_raw as f32);
+        self.nox_raw.push(result.nox_raw as f32);
+        self.voc_index.push(result.voc_index as f32);
+        self.nox_index.push(result.nox_index as f32);
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.started_at.elapsed() >= self.duration
+    }
+
+    /// Log the summary report over RTT.
+    pub fn report(&self) {
+        let errors = health::snapshot();
+        info!("Soak test report ({} s, {} samples):", self.started_at.elapsed().as_secs(), self.voc_raw.count);
+        for (name, stats) in [
+            ("VOC raw", &self.voc_raw),
+            ("NOx raw", &self.nox_raw),
+            ("VOC index", &self.voc_index),
+            ("NOx index", &self.nox_index),
+        ] {
+            info!(
+                "  {=str}: min={} max={} mean={} drift={}",
+                name,
+                stats.min,
+                stats.max,
+                stats.mean,
+                stats.drift()
+            );
+        }
+        info!(
+            "  I2C errors: {}, CRC errors: {}",
+            errors.i2c_errors - self.errors_at_start.i2c_errors,
+            errors.crc_errors - self.errors_at_start.crc_errors
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Device lifecycle state and a central, logged transition point.
+//!
+//! Every change goes through `transition_to`, which logs from/to with a
+//! timestamp and offers the transition on `STATE_TRANSITIONS` for consumers
+//! that want the lifecycle timeline (Booting → SelfTest → Conditioning →
+//! Measuring → Fault → ...).
+
+use core::sync::atomic::{AtomicU8, Ordering};
+use defmt::{info, warn, Format};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use embassy_time::Instant;
+
+use crate::wall_clock::unix_time_ms;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+#[repr(u8)]
+pub enum DeviceState {
+    Booting = 0,
+    SelfTest = 1,
+    Conditioning = 2,
+    Measuring = 3,
+    Fault = 4,
+    /// A bounded run finished; heater off, waiting for a reset.
+    Idle = 5,
+}
+
+impl DeviceState {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::SelfTest,
+            2 => Self::Conditioning,
+            3 => Self::Measuring,
+            4 => Self::Fault,
+            5 => Self::Idle,
+            _ => Self::Booting,
+        }
+    }
+
+    /// Whether `self → to` is an expected lifecycle step. `Booting` is only
+    /// entered by a reset, never by a transition.
+    pub fn can_transition_to(self, to: DeviceState) -> bool {
+        use DeviceState::*;
+        matches!(
+            (self, to),
+            (Booting, SelfTest | Conditioning | Fault)
+                | (SelfTest, Conditioning | Measuring | Fault)
+                | (Conditioning, Measuring | Fault)
+                | (Measuring, Conditioning | Fault | Idle)
+                | (Fault, SelfTest | Conditioning | Measuring)
+        )
+    }
+}
+
+#[derive(Copy, Clone, Format)]
+pub struct StateTransition {
+    pub from: DeviceState,
+    pub to: DeviceState,
+    /// Milliseconds since boot.
+    pub uptime_ms: u64,
+    /// Unix time (ms), if a wall-clock source has been set.
+    pub unix_ms: Option<u64>,
+}
+
+static STATE: AtomicU8 = AtomicU8::new(DeviceState::Booting as u8);
+
+/// Transitions for lifecycle consumers. Publishing never blocks: when nobody
+/// drains the channel the oldest unread transitions are kept and new ones
+/// are dropped (they are still logged).
+pub static STATE_TRANSITIONS: Channel<CriticalSectionRawMutex, StateTransition, 8> =
+    Channel::new();
+
+pub fn current_state() -> DeviceState {
+    DeviceState::from_u8(STATE.load(Ordering::Acquire))
+}
+
+/// Move to `to`, logging and publishing the transition. Re-entering the
+/// current state is a no-op; unexpected transitions are applied but warned.
+pub fn transition_to(to: DeviceState) {
+    let from = DeviceState::from_u8(STATE.swap(to as u8, Ordering::AcqRel));
+    if from == to {
+        return;
+    }
+
+    let transition = StateTransition {
+        from,
+        to,
+        uptime_ms: Instant::now().as_millis(),
+        unix_ms: unix_time_ms(),
+    };
+    if from.can_transition_to(to) {
+        info!("State {} -> {} at {} ms", from, to, transition.uptime_ms);
+    } else {
+        warn!("Unexpected state transition {} -> {} at {} ms", from, to, transition.uptime_ms);
+    }
+    let _ = STATE_TRANSITIONS.try_send(transition);
+}
@@ -0,0 +1,46 @@
+/// Streaming min/max/mean (Welford) over a series of samples, plus the first
+/// and last sample so drift over the run can be reported.
+#[derive(Copy, Clone, defmt::Format)]
+pub struct RunningStats {
+    pub count: u32,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub first: f32,
+    pub last: f32,
+}
+
+impl RunningStats {
+    pub const fn new() -> Self {
+        Self {
+            count: 0,
+            min: f32::MAX,
+            max: f32::MIN,
+            mean: 0.0,
+            first: 0.0,
+            last: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, value: f32) {
+        if self.count == 0 {
+            self.first = value;
+        }
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.mean += (value - self.mean) / self.count as f32;
+        self.last = value;
+    }
+
+    /// Change from the first to the last sample.
+    pub fn drift(&self) -> f32 {
+        self.last - self.first
+    }
+}
+
+impl Default for RunningStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,122 @@
+//! Liveness supervisor hosted by the otherwise idle main task.
+//!
+//! Liveness criteria: once conditioning has finished, a measurement must
+//! complete (`health::record_measurement`) at least every `stale_after`.
+//! Before the first measurement the clock starts when conditioning finishes,
+//! so the conditioning phase itself is never judged here.
+//!
+//! While the measurement task is alive the supervisor feeds the RTC watchdog.
+//! On detected death it logs, moves the device to `Fault` and, with
+//! `SupervisorAction::Reset`, stops feeding so the watchdog resets the chip.
+
+use defmt::{error, info, Format};
+use embassy_time::{Duration, Instant, Timer};
+use esp_hal::rtc_cntl::{Rwdt, RwdtStage};
+
+use crate::health::last_measurement_age;
+use crate::state::{current_state, transition_to, DeviceState};
+
+#[derive(Copy, Clone, PartialEq, Eq, Format)]
+pub enum SupervisorAction {
+    /// Stop feeding the watchdog; the chip resets after `watchdog_timeout`.
+    Reset,
+    /// Only log and enter `Fault`; keep the device running.
+    FaultOnly,
+}
+
+#[derive(Copy, Clone, Format)]
+pub struct SupervisorConfig {
+    /// How often liveness is checked and the watchdog fed.
+    pub check_every: Duration,
+    /// Longest acceptable gap between completed measurements.
+    pub stale_after: Duration,
+    /// RTC watchdog timeout; must exceed `check_every`.
+    pub watchdog_timeout: Duration,
+    pub action: SupervisorAction,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            check_every: Duration::from_secs(5),
+            // Long enough to cover a re-conditioning pass after a freeze or
+            // power cycle, during which no measurements complete.
+            stale_after: Duration::from_secs(60),
+            watchdog_timeout: Duration::from_secs(30),
+            action: SupervisorAction::Reset,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Format)]
+pub enum Liveness {
+    /// Conditioning still running; not judged yet.
+    Starting,
+    /// A bounded run ended on purpose; no measurements expected.
+    Idle,
+    Alive,
+    /// No measurement for this long.
+    Dead(Duration),
+}
+
+pub struct Supervisor {
+    config: SupervisorConfig,
+    measuring_since: Option<Instant>,
+}
+
+impl Supervisor {
+    pub fn new(config: SupervisorConfig) -> Self {
+        Self {
+            config,
+            measuring_since: None,
+        }
+    }
+
+    pub fn check(&mut self, now: Instant) -> Liveness {
+        let state = current_state();
+        if state == DeviceState::Idle {
+            return Liveness::Idle;
+        }
+        // Conditioning has finished once the device first reaches
+        // `Measuring`; later re-conditioning doesn't count as starting.
+        if self.measuring_since.is_none() && state != DeviceState::Measuring {
+            return Liveness::Starting;
+        }
+        let since = *self.measuring_since.get_or_insert(now);
+        let age = last_measurement_age().unwrap_or(now - since);
+        if age > self.config.stale_after {
+            Liveness::Dead(age)
+        } else {
+            Liveness::Alive
+        }
+    }
+
+    /// Check liveness and feed `rwdt` forever.
+    pub async fn run(mut self, mut rwdt: Rwdt) -> ! {
+        let timeout = esp_hal::time::Duration::from_millis(self.config.watchdog_timeout.as_millis());
+        rwdt.set_timeout(RwdtStage::Stage0, timeout);
+        rwdt.enable();
+        info!("Supervisor running: {}", self.config);
+
+        let mut faulted = false;
+        loop {
+            match self.check(Instant::now()) {
+                Liveness::Dead(age) => {
+                    if !faulted {
+                        error!("Measurement task unresponsive for {} s", age.as_secs());
+                        transition_to(DeviceState::Fault);
+                        faulted = true;
+                    }
+                    if self.config.action == SupervisorAction::FaultOnly {
+                        rwdt.feed();
+                    }
+                }
+                Liveness::Starting | Liveness::Idle | Liveness::Alive => {
+                    faulted = false;
+                    rwdt.feed();
+                }
+            }
+            Timer::after(self.config.check_every).await;
+        }
+    }
+}
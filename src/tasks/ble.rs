@@ -0,0 +1,223 @@
+use bt_hci::controller::ExternalController;
+use defmt::{info, warn};
+use embassy_futures::join::join;
+use embassy_futures::select::select3;
+use esp_wifi::ble::controller::BleConnector;
+use trouble_host::prelude::*;
+
+use crate::ble::{
+    CATEGORY_CHARACTERISTIC_UUID, CONFIG_CHARACTERISTIC_UUID, CONTROL_CHARACTERISTIC_UUID,
+    HEALTH_CHARACTERISTIC_UUID, NOX_INDEX_CHARACTERISTIC_UUID, RAW_BLE_LEN, RAW_CHARACTERISTIC_UUID, RAW_TICKS,
+    SGP41_SERVICE_UUID, VOC_INDEX_CHARACTERISTIC_UUID,
+};
+use crate::category::voc_category;
+use crate::config::{get_config, CONFIG_BLE_LEN};
+use crate::control::{ControlCommand, CONTROL, CONTROL_BLE_LEN};
+use crate::health::{self, HEALTH_BLE_LEN};
+use crate::mux::PRIMARY_SENSOR;
+use crate::readings::ReadingsSubscriber;
+use crate::reporting::voc_only_reporting;
+
+pub type BleController = ExternalController<BleConnector<'static>, 20>;
+
+const CONNECTIONS_MAX: usize = 1;
+// Signalling channel plus ATT.
+const L2CAP_CHANNELS_MAX: usize = 2;
+const L2CAP_MTU: usize = 255;
+
+// Static random address; the advertised name tells devices apart.
+const ADDRESS: [u8; 6] = [0xff, 0x8f, 0x1a, 0x05, 0xe4, 0xff];
+
+#[gatt_server]
+struct Server {
+    environmental: EnvironmentalSensingService,
+    device: Sgp41Service,
+}
+
+/// Environmental Sensing service (0x181A). The SIG has no gas index
+/// characteristic, so the indices use the custom UUIDs from `ble`.
+#[gatt_service(uuid = service::ENVIRONMENTAL_SENSING)]
+struct EnvironmentalSensingService {
+    /// VOC index 1–500 (0 during warm-up), `u16`.
+    #[characteristic(uuid = VOC_INDEX_CHARACTERISTIC_UUID, read, notify)]
+    voc_index: u16,
+    /// NOx index 1–500 (0 during warm-up), `u16`. Stays 0 in VOC-only mode.
+    #[characteristic(uuid = NOX_INDEX_CHARACTERISTIC_UUID, read, notify)]
+    nox_index: u16,
+    /// `category::AirQualityCategory` of the VOC index, `u8` (255 during
+    /// warm-up).
+    #[characteristic(uuid = CATEGORY_CHARACTERISTIC_UUID, read, notify)]
+    category: u8,
+}
+
+/// Custom device service (`ble::SGP41_SERVICE_UUID`) for hosts that want
+/// more than the indices.
+#[gatt_service(uuid = SGP41_SERVICE_UUID)]
+struct Sgp41Service {
+    /// Latest raw ticks, `ble::RawTicks::to_ble_bytes`; notified once per
+    /// measurement interval.
+    #[characteristic(uuid = RAW_CHARACTERISTIC_UUID, read, notify)]
+    raw: [u8; RAW_BLE_LEN],
+    /// `health::HealthSnapshot::to_ble_bytes`, refreshed with every reading.
+    #[characteristic(uuid = HEALTH_CHARACTERISTIC_UUID, read)]
+    health: [u8; HEALTH_BLE_LEN],
+    /// Active configuration, `config::ConfigSnapshot::to_ble_bytes`;
+    /// refreshed with every reading.
+    #[characteristic(uuid = CONFIG_CHARACTERISTIC_UUID, read)]
+    config: [u8; CONFIG_BLE_LEN],
+    /// Runtime commands, `control::ControlCommand::from_ble_bytes`; forwarded
+    /// to `control::CONTROL`.
+    #[characteristic(uuid = CONTROL_CHARACTERISTIC_UUID, write)]
+    control: [u8; CONTROL_BLE_LEN],
+}
+
+/// GATT peripheral advertising as `name` and notifying the VOC/NOx indices
+/// and air-quality category whenever a reading arrives on `readings::READINGS`. One connection at a
+/// time; advertising resumes after a disconnect.
+#[embassy_executor::task]
+pub async fn ble_task(controller: BleController, name: &'static str, mut readings: ReadingsSubscriber) {
+    let mut resources: HostResources<CONNECTIONS_MAX, L2CAP_CHANNELS_MAX, L2CAP_MTU> = HostResources::new();
+    let stack = trouble_host::new(controller, &mut resources).set_random_address(Address::random(ADDRESS));
+    let Host {
+        mut peripheral,
+        mut runner,
+        ..
+    } = stack.build();
+
+    let server = match Server::new_with_config(GapConfig::Peripheral(PeripheralConfig {
+        name,
+        appearance: &appearance::sensor::GENERIC_SENSOR,
+    })) {
+        Ok(server) => server,
+        Err(e) => {
+            warn!("BLE GATT server setup failed: {}", defmt::Debug2Format(&e));
+            return;
+        }
+    };
+
+    let _ = join(
+        async {
+            loop {
+                if let Err(e) = runner.run().await {
+                    warn!("BLE host stopped: {}; restarting", defmt::Debug2Format(&e));
+                }
+            }
+        },
+        async {
+            loop {
+                match advertise(name, &mut peripheral, &server).await {
+                    Ok(conn) => {
+                        info!("BLE central connected");
+                        select3(
+                            gatt_events(&server, &conn),
+                            notify_readings(&server, &conn, &mut readings),
+                            notify_raw(&server, &conn),
+                        )
+                        .await;
+                        info!("BLE central disconnected");
+                    }
+                    Err(e) => warn!("BLE advertising failed: {}", defmt::Debug2Format(&e)),
+                }
+            }
+        },
+    )
+    .await;
+}
+
+/// Answer reads and writes until the central disconnects. Control writes are
+/// decoded and queued on `control::CONTROL`.
+async fn gatt_events(server: &Server<'_>, conn: &GattConnection<'_, '_>) {
+    loop {
+        match conn.next().await {
+            GattConnectionEvent::Disconnected { .. } => return,
+            GattConnectionEvent::Gatt { event: Ok(event) } => {
+                if let GattEvent::Write(write) = &event {
+                    if write.handle() == server.device.control.handle {
+                        forward_control(write.data());
+                    }
+                }
+                if let Ok(reply) = event.accept() {
+                    reply.send().await;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn forward_control(data: &[u8]) {
+    let mut bytes = [0u8; CONTROL_BLE_LEN];
+    let len = data.len().min(CONTROL_BLE_LEN);
+    bytes[..len].copy_from_slice(&data[..len]);
+    match ControlCommand::from_ble_bytes(&bytes) {
+        Some(command) => {
+            info!("BLE control: {}", command);
+            if CONTROL.try_send(command).is_err() {
+                warn!("Control queue full; BLE command dropped");
+            }
+        }
+        None => warn!("BLE control: unknown command 0x{:02X}", bytes[0]),
+    }
+}
+
+/// Update and notify the index and category characteristics for every new
+/// reading of the primary sensor; a second sensor's readings are not exposed
+/// over BLE. The health and config characteristics are refreshed on every
+/// reading.
+async fn notify_readings(server: &Server<'_>, conn: &GattConnection<'_, '_>, readings: &mut ReadingsSubscriber) {
+    let index = |i: i32| i.clamp(0, u16::MAX as i32) as u16;
+    loop {
+        let reading = readings.next_message_pure().await;
+        let _ = server.set(&server.device.health, &health::snapshot().to_ble_bytes());
+        let _ = server.set(&server.device.config, &get_config().to_ble_bytes());
+        if reading.sensor_id != PRIMARY_SENSOR {
+            continue;
+        }
+        let nox_index = if voc_only_reporting() { 0 } else { index(reading.nox_index) };
+        let voc = server.environmental.voc_index.notify(conn, &index(reading.voc_index)).await;
+        let nox = server.environmental.nox_index.notify(conn, &nox_index).await;
+        let category = voc_category(reading.voc_index) as u8;
+        let category = server.environmental.category.notify(conn, &category).await;
+        if voc.is_err() || nox.is_err() || category.is_err() {
+            return;
+        }
+    }
+}
+
+/// Update and notify the raw ticks characteristic from `ble::RAW_TICKS`. Only
+/// the newest sample is kept, so a slow link skips samples.
+async fn notify_raw(server: &Server<'_>, conn: &GattConnection<'_, '_>) {
+    loop {
+        let raw = RAW_TICKS.wait().await.to_ble_bytes();
+        if server.device.raw.notify(conn, &raw).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn advertise<'a, 'b>(
+    name: &'a str,
+    peripheral: &mut Peripheral<'a, BleController>,
+    server: &'b Server<'_>,
+) -> Result<GattConnection<'a, 'b>, BleHostError<<BleController as bt_hci::controller::Controller>::Error>> {
+    let mut adv_data = [0; 31];
+    AdStructure::encode_slice(
+        &[
+            AdStructure::Flags(LE_GENERAL_DISCOVERABLE | BR_EDR_NOT_SUPPORTED),
+            // Environmental Sensing, little-endian
+            AdStructure::ServiceUuids16(&[[0x1a, 0x18]]),
+            AdStructure::CompleteLocalName(name.as_bytes()),
+        ],
+        &mut adv_data[..],
+    )?;
+    let advertiser = peripheral
+        .advertise(
+            &Default::default(),
+            Advertisement::ConnectableScannableUndirected {
+                adv_data: &adv_data[..],
+                scan_data: &[],
+            },
+        )
+        .await?;
+    Ok(advertiser.accept().await?.with_attribute_server(server)?)
+}
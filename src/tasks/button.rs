@@ -0,0 +1,65 @@
+use defmt::info;
+use embassy_time::{Duration, Instant, Timer};
+use esp_hal::gpio::Input;
+
+use crate::control::{ControlCommand, CONTROL};
+
+// Button sampling period
+const POLL: Duration = Duration::from_millis(10);
+
+/// "Clean air reset" gesture: hold the button (active low) for `hold` to reset
+/// the gas index algorithms and briefly re-condition.
+#[derive(Copy, Clone)]
+pub struct ButtonConfig {
+    pub hold: Duration,
+    /// The level must be stable this long before a press or release counts.
+    pub debounce: Duration,
+    pub recondition_secs: u8,
+}
+
+impl Default for ButtonConfig {
+    fn default() -> Self {
+        Self {
+            hold: Duration::from_secs(3),
+            debounce: Duration::from_millis(30),
+            recondition_secs: 10,
+        }
+    }
+}
+
+// Wait until the button has been stably at `pressed` for the debounce time
+async fn wait_stable(button: &Input<'static>, pressed: bool, debounce: Duration) {
+    let mut since = Instant::now();
+    loop {
+        if button.is_low() != pressed {
+            since = Instant::now();
+        } else if since.elapsed() >= debounce {
+            return;
+        }
+        Timer::after(POLL).await;
+    }
+}
+
+#[embassy_executor::task]
+pub async fn button_task(button: Input<'static>, config: ButtonConfig) {
+    loop {
+        wait_stable(&button, true, config.debounce).await;
+        let pressed_at = Instant::now();
+
+        // Fire as soon as the hold time is reached, without waiting for release
+        let mut fired = false;
+        while button.is_low() {
+            if !fired && pressed_at.elapsed() >= config.hold {
+                info!("Clean air reset gesture detected");
+                CONTROL
+                    .send(ControlCommand::CleanAirReset {
+                        recondition_secs: config.recondition_secs,
+                    })
+                    .await;
+                fired = true;
+            }
+            Timer::after(POLL).await;
+        }
+        wait_stable(&button, false, config.debounce).await;
+    }
+}
@@ -1,17 +1,24 @@
-use crate::hal::I2cCompat;
-use crate::led::LedCommand;
-use crate::prepare_temp_hum_params;
-use core::sync::atomic::{AtomicBool, Ordering};
-use defmt::{info, warn};
-use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use crate::algo::GasIndex;
+use crate::commission::{measure_raw_once, self_test, VOC_RAW_PLAUSIBLE};
+use crate::compensation::CompensationMode;
+use crate::config::{update_config, ConfigSnapshot, SensorConfig};
+use crate::led::{send_or_drop, ConditioningAnimation, LedCommand};
+use crate::mux::{SensorBus, MAX_SENSORS};
+use crate::state::{transition_to, DeviceState};
+use crate::driver::{Sgp41, Sgp41Error};
+use crate::timing::MAX_CONDITIONING;
+use defmt::{error, info, warn};
+use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, NoopRawMutex};
 use embassy_sync::channel::Sender;
-use embassy_sync::mutex::Mutex;
-use embassy_time::{Duration, Timer};
-use embedded_hal_02::blocking::i2c::{Read, Write};
-use gas_index_algorithm::GasIndexAlgorithm;
-use core::cell::RefCell;
+use embassy_sync::signal::Signal;
+use embassy_time::{with_timeout, Duration, Instant, Timer};
 
-pub static CONDITION_DONE: AtomicBool = AtomicBool::new(false);
+/// Signalled once when conditioning hands the bus over, one slot per sensor
+/// (`SensorBus::id`). Each sensor's measurement task is the only waiter on its
+/// slot (`wait` consumes it); others go by `DeviceState` or the first reading
+/// instead.
+pub static CONDITION_DONE: [Signal<CriticalSectionRawMutex, ()>; MAX_SENSORS] =
+    [const { Signal::new() }; MAX_SENSORS];
 pub const SGP41_ADDR: u8 = 0x59;
 
 
@@ -20,53 +27,237 @@ pub const CMD_EXECUTE_CONDITIONING: [u8; 2] = [0x26, 0x12];
 
 pub const CMD_MEASURE_RAW_SIGNALS: [u8; 2] = [0x26, 0x19];
 
+pub const CMD_EXECUTE_SELF_TEST: [u8; 2] = [0x28, 0x0E];
 
-#[embassy_executor::task]
+pub const CMD_GET_SERIAL_NUMBER: [u8; 2] = [0x36, 0x82];
+
+pub const CMD_TURN_HEATER_OFF: [u8; 2] = [0x36, 0x15];
+
+pub const CMD_GET_FEATURE_SET: [u8; 2] = [0x20, 0x2F];
+
+// I²C general call reset: a single 0x06 byte to address 0x00 resets every
+// device on the bus that supports it (the SGP41 does).
+pub const GENERAL_CALL_ADDR: u8 = 0x00;
+pub const CMD_SOFT_RESET: [u8; 1] = [0x06];
+
+// Fast magenta blink: conditioning never finished, measurements won't start.
+const CONDITIONING_FAULT_LED: LedCommand = LedCommand::Blink(30, 0, 30, Some(250));
+
+#[embassy_executor::task(pool_size = MAX_SENSORS)]
 pub async fn sgp41_conditioning_task(
-    bus: &'static Mutex<NoopRawMutex, I2cCompat<'static>>,
-    duration_secs: u8,
+    bus: SensorBus,
+    // Conditioning length comes from `config.conditioning_secs`.
+    config: SensorConfig,
     led_sender: Sender<'static, NoopRawMutex, LedCommand, 4>,
-    voc_algo: &'static RefCell<GasIndexAlgorithm>,
+    voc_algo: &'static GasIndex,
+    compensation: CompensationMode,
+    animation: ConditioningAnimation,
+    // The algorithm baseline was restored from a previous run; try to skip
+    // conditioning if `confirm_skip` says the sensor is still warm and healthy.
+    baseline_restored: bool,
+    // Abort into `Fault` if the phase hasn't finished by then (see
+    // `timing::CONDITIONING_TIMEOUT`); keep it well above `conditioning_secs`.
+    timeout: Duration,
 ) {
-    info!("Starting SGP41 conditioning phase ({} s)…", duration_secs);
+    update_primary_config(&bus, |c| c.conditioning_timeout_s = timeout.as_secs() as u32);
+    let phase = condition(
+        &bus,
+        config.conditioning_secs,
+        &led_sender,
+        voc_algo,
+        compensation,
+        animation,
+        baseline_restored,
+    );
+    if with_timeout(timeout, phase).await.is_err() {
+        // The phase was dropped mid-command, which released the bus lock.
+        // CONDITION_DONE is never signalled, so this sensor's measurement task
+        // never starts.
+        error!("Conditioning did not finish within {} s; aborting", timeout.as_secs());
+        turn_heater_off(&bus).await;
+        transition_primary(&bus, DeviceState::Fault);
+        send_primary_led(&bus, &led_sender, CONDITIONING_FAULT_LED);
+        return;
+    }
 
-    // led.lock().await.set_color_rgb(30, 0, 0).ok();
-    let _ = led_sender.send(LedCommand::Solid(30, 0, 0)).await;
+    transition_primary(&bus, DeviceState::Measuring);
+    CONDITION_DONE[bus.id as usize].signal(());
+}
 
-    for i in 1..=duration_secs {
-        info!("  Conditioning {}/{}", i, duration_secs);
-        // 25 °C / 50 %RH dummy compensation values
-        let params = prepare_temp_hum_params(25.0, 50.0);
-        let mut cmd = [0u8; 8];
-        cmd[0..2].copy_from_slice(&CMD_EXECUTE_CONDITIONING);
-        cmd[2..8].copy_from_slice(&params);
+// The conditioning phase proper, bounded by the task's timeout.
+async fn condition(
+    bus: &SensorBus,
+    duration_secs: u8,
+    led_sender: &Sender<'static, NoopRawMutex, LedCommand, 4>,
+    voc_algo: &GasIndex,
+    compensation: CompensationMode,
+    animation: ConditioningAnimation,
+    baseline_restored: bool,
+) {
+    if baseline_restored {
+        transition_primary(bus, DeviceState::SelfTest);
+        if confirm_skip(bus, compensation).await {
+            info!("Restored baseline confirmed; skipping conditioning");
+            update_primary_config(bus, |c| c.conditioning_secs = 0);
+            return;
+        }
+        warn!("Skip-conditioning check failed; running full conditioning");
+    }
 
-        if bus.lock().await.write(SGP41_ADDR, &cmd).is_err() {
-            warn!("    Failed to send measure command");
+    transition_primary(bus, DeviceState::Conditioning);
+
+    let duration_secs = cap_conditioning(duration_secs);
+    info!("Starting SGP41 conditioning phase ({} s)…", duration_secs);
+    update_primary_config(bus, |c| c.conditioning_secs = duration_secs);
+
+    // The LED task animates on its own timer until the measurement task
+    // reports the first valid reading.
+    send_primary_led(bus, led_sender, LedCommand::Conditioning(animation));
+
+    run_conditioning(bus, duration_secs, compensation, |voc_raw| {
+        info!("    VOC raw: {}", voc_raw);
+        match voc_algo.try_borrow_mut() {
+            Ok(mut algo) => info!("    VOC index: {}", algo.process(voc_raw as i32)),
+            Err(_) => warn!("    VOC algorithm busy; skipping sample"),
         }
+    })
+    .await;
 
-        // led.lock().await.set_color_rgb(30, 0, 30).ok();
-        let _ = led_sender.send(LedCommand::Solid(30, 0, 30)).await;
+    info!("Conditioning complete!");
+}
 
-        // wait 50 ms before reading
-        Timer::after(Duration::from_millis(50)).await;
+/// Decide whether a restored baseline can be trusted without conditioning.
+///
+/// Criteria, all required:
+/// - the self-test result has a valid CRC and both pixel failure bits clear;
+/// - one raw measurement has valid CRCs;
+/// - its VOC ticks are within `commission::VOC_RAW_PLAUSIBLE`;
+/// - its NOx ticks are non-zero. NOx reads 0 until the heater has been
+///   conditioned, so a sensor that lost power since the state was saved fails
+///   here even if the MCU kept running.
+///
+/// On any failure the caller falls back to the full conditioning phase.
+pub async fn confirm_skip(
+    bus: &SensorBus,
+    compensation: CompensationMode,
+) -> bool {
+    let test = self_test(bus).await;
+    let raw = measure_raw_once(bus, compensation.params()).await;
+    info!("Skip-conditioning check: self-test={} raw={}", test, raw);
+    let test_ok = test.is_some_and(|result| result.passed());
+    let raw_ok = matches!(raw, Some((voc, nox)) if VOC_RAW_PLAUSIBLE.contains(&voc) && nox != 0);
+    test_ok && raw_ok
+}
 
-        // ── read ──────────────────────────────────────────────────────────────
-        let mut buf = [0u8; 3];
-        if bus.lock().await.read(SGP41_ADDR, &mut buf).is_ok() {
-            let voc_raw = u16::from_be_bytes([buf[0], buf[1]]);
-            info!("    VOC raw: {}", voc_raw);
-            let voc_index = voc_algo.borrow_mut().process(voc_raw as i32);
-            info!("    VOC index: {}", voc_index);
+/// Issue one conditioning command and return the VOC raw ticks it produced.
+pub async fn execute_conditioning(
+    bus: &SensorBus,
+    compensation: CompensationMode,
+) -> Option<u16> {
+    let result = Sgp41::new(bus.lock().await)
+        .execute_conditioning(compensation.params())
+        .await;
+    match result {
+        Ok(voc_raw) => Some(voc_raw),
+        Err(Sgp41Error::I2c(e)) => {
+            warn!("    Conditioning command failed on the bus: {}", e);
+            None
         }
+        Err(e) => {
+            warn!("    Conditioning response rejected: {}", e);
+            None
+        }
+    }
+}
 
-        // wait 1 s between conditioning cycles
-        Timer::after(Duration::from_secs(1)).await;
+/// Re-run conditioning for `duration_secs` (e.g. after a sensor power cycle).
+pub async fn recondition(
+    bus: &SensorBus,
+    duration_secs: u8,
+    compensation: CompensationMode,
+) {
+    let duration_secs = cap_conditioning(duration_secs);
+    info!("Re-conditioning SGP41 ({} s)…", duration_secs);
+    transition_primary(bus, DeviceState::Conditioning);
+    run_conditioning(bus, duration_secs, compensation, |_| {}).await;
+    info!("Re-conditioning complete");
+    transition_primary(bus, DeviceState::Measuring);
+}
+
+/// The datasheet allows at most `timing::MAX_CONDITIONING`; longer requests
+/// are cut to it with a warning.
+fn cap_conditioning(duration_secs: u8) -> u8 {
+    let max = MAX_CONDITIONING.as_secs() as u8;
+    if duration_secs > max {
+        warn!("Conditioning for {} s exceeds the datasheet maximum; using {} s", duration_secs, max);
+        return max;
     }
+    duration_secs
+}
 
-    let _ = led_sender.send(LedCommand::Solid(0, 30, 0)).await;
+// Issue the conditioning command at 1 Hz for exactly `duration_secs`: each
+// command starts on a whole second after `start`, so the command's own
+// transfer and wait don't stretch the phase. `on_voc` sees each VOC sample.
+async fn run_conditioning(
+    bus: &SensorBus,
+    duration_secs: u8,
+    compensation: CompensationMode,
+    mut on_voc: impl FnMut(u16),
+) {
+    let start = Instant::now();
+    let end = start + Duration::from_secs(duration_secs as u64);
+    let mut next = start;
+    while next < end {
+        info!("  Conditioning {}/{} s", next.duration_since(start).as_secs() + 1, duration_secs);
+        if let Some(voc_raw) = execute_conditioning(bus, compensation).await {
+            on_voc(voc_raw);
+        }
+        next += Duration::from_secs(1);
+        Timer::at(next).await;
+    }
+}
 
-    // Signal completion.
-    CONDITION_DONE.store(true, Ordering::Release);
-    info!("Conditioning complete!");
+/// Switch the hotplate off and return the sensor to idle. The next measure or
+/// conditioning command turns it back on.
+pub async fn turn_heater_off(bus: &SensorBus) -> bool {
+    let ok = Sgp41::new(bus.lock().await).turn_heater_off().await.is_ok();
+    if !ok {
+        warn!("Failed to turn SGP41 heater off");
+    }
+    ok
+}
+
+/// Soft-reset the sensor via the I²C general call (`Sgp41::soft_reset`). The
+/// heater is off afterwards, so the caller must re-condition before trusting
+/// NOx readings again.
+pub async fn soft_reset(bus: &SensorBus) -> bool {
+    let ok = Sgp41::new(bus.lock().await).soft_reset().await.is_ok();
+    if !ok {
+        warn!("Soft reset (general call) failed");
+    }
+    ok
+}
+
+/// `transition_to` on behalf of `bus`. The device state tracks the primary
+/// sensor; other sensors' progress would overwrite it, so they skip it.
+pub(crate) fn transition_primary(bus: &SensorBus, to: DeviceState) {
+    if bus.is_primary() {
+        transition_to(to);
+    }
+}
+
+/// `update_config` on behalf of `bus`; primary sensor only, like
+/// `transition_primary`.
+pub(crate) fn update_primary_config(bus: &SensorBus, f: impl FnOnce(&mut ConfigSnapshot)) {
+    if bus.is_primary() {
+        update_config(f);
+    }
+}
+
+/// `send_or_drop` on behalf of `bus`; primary sensor only, like
+/// `transition_primary`, since the LED shows the device state.
+fn send_primary_led(bus: &SensorBus, led_sender: &Sender<'static, NoopRawMutex, LedCommand, 4>, command: LedCommand) {
+    if bus.is_primary() {
+        send_or_drop(led_sender, command);
+    }
 }
@@ -0,0 +1,70 @@
+use defmt::{info, warn};
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::mutex::Mutex;
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal_02::blocking::i2c::{Read, Write};
+
+use crate::crosscheck::{latest_voc_index, CrossCheck, DivergenceMonitor, DivergenceRule, CROSSCHECK};
+use crate::decode_words;
+use crate::hal::I2cCompat;
+use crate::readings::READINGS;
+
+pub const SCD4X_ADDR: u8 = 0x62;
+
+// SCD4x commands
+pub const CMD_SCD4X_START_PERIODIC: [u8; 2] = [0x21, 0xB1];
+pub const CMD_SCD4X_READ_MEASUREMENT: [u8; 2] = [0xEC, 0x05];
+
+// The SCD4x produces a new sample every 5 s in periodic mode.
+const SCD4X_PERIOD: Duration = Duration::from_secs(5);
+
+#[embassy_executor::task]
+pub async fn crosscheck_task(
+    bus: &'static Mutex<NoopRawMutex, I2cCompat<'static>>,
+    rule: DivergenceRule,
+) {
+    // Start once the first reading is out: conditioning has handed over the
+    // bus and `latest_voc_index` means something.
+    match READINGS.subscriber() {
+        Ok(mut readings) => {
+            readings.next_message_pure().await;
+        }
+        Err(_) => {
+            warn!("No readings subscriber left; VOC/CO2 cross-check disabled");
+            return;
+        }
+    }
+
+    if bus.lock().await.write(SCD4X_ADDR, &CMD_SCD4X_START_PERIODIC).is_err() {
+        warn!("SCD4x not responding; VOC/CO2 cross-check disabled");
+        return;
+    }
+
+    let mut monitor = DivergenceMonitor::new(rule);
+    loop {
+        Timer::after(SCD4X_PERIOD).await;
+        let Some(co2) = read_co2(bus).await else {
+            warn!("SCD4x read failed");
+            continue;
+        };
+        if let Some(verdict) = monitor.update(latest_voc_index(), co2, Instant::now()) {
+            match verdict {
+                CrossCheck::VocOnly | CrossCheck::Co2Only => {
+                    warn!("VOC/CO2 divergence: {} (CO2 {} ppm)", verdict, co2)
+                }
+                _ => info!("VOC/CO2 cross-check: {} (CO2 {} ppm)", verdict, co2),
+            }
+            CROSSCHECK.signal(verdict);
+        }
+    }
+}
+
+/// CO₂ (ppm) from the SCD4x's latest sample, CRC-checked.
+async fn read_co2(bus: &Mutex<NoopRawMutex, I2cCompat<'static>>) -> Option<u16> {
+    bus.lock().await.write(SCD4X_ADDR, &CMD_SCD4X_READ_MEASUREMENT).ok()?;
+    Timer::after(Duration::from_millis(1)).await;
+    let mut buf = [0u8; 9];
+    bus.lock().await.read(SCD4X_ADDR, &mut buf).ok()?;
+    // Words: CO₂ ppm, temperature, humidity; only CO₂ is used here.
+    decode_words::<3>(&buf).map(|[co2, _, _]| co2)
+}
@@ -0,0 +1,152 @@
+use core::cell::Cell;
+
+use embassy_futures::join::join;
+use embassy_net::Stack;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_time::Duration;
+use picoserve::io::Write;
+use picoserve::response::{Content, Json, StatusCode};
+use picoserve::routing::get;
+use picoserve::{Config, Router, Timeouts};
+use serde::Serialize;
+
+use crate::health::{self, AgeCategory};
+use crate::mux::PRIMARY_SENSOR;
+use crate::prometheus::{format_metrics, CONTENT_TYPE, METRICS_MAX};
+use crate::readings::{Measurement, ReadingsSubscriber};
+
+pub const HTTP_PORT: u16 = 80;
+
+const TCP_BUF: usize = 1024;
+// Request line and headers; the endpoints take no body.
+const HTTP_BUF: usize = 1024;
+
+// Latest reading of the primary sensor; `None` until the first one.
+static LATEST: Mutex<CriticalSectionRawMutex, Cell<Option<Measurement>>> = Mutex::new(Cell::new(None));
+
+/// Latest reading served by `GET /metrics`, or `None` before the first one.
+pub fn latest() -> Option<Measurement> {
+    LATEST.lock(Cell::get)
+}
+
+/// Body of `GET /health`.
+#[derive(Serialize)]
+struct Health {
+    self_test: &'static str,
+    nox_degraded: bool,
+    uptime_s: u32,
+    i2c_errors: u32,
+    crc_errors: u32,
+    measurement_age: &'static str,
+}
+
+fn health() -> Health {
+    let snapshot = health::snapshot();
+    Health {
+        self_test: snapshot.self_test.label(),
+        nox_degraded: snapshot.nox_degraded,
+        uptime_s: snapshot.uptime_s,
+        i2c_errors: snapshot.i2c_errors,
+        crc_errors: snapshot.crc_errors,
+        measurement_age: match snapshot.measurement_age {
+            AgeCategory::Fresh => "fresh",
+            AgeCategory::Recent => "recent",
+            AgeCategory::Stale => "stale",
+            AgeCategory::Dead => "dead",
+        },
+    }
+}
+
+// `GET /prometheus` body, formatted into its own buffer so the handler can
+// return it by value.
+struct PrometheusText {
+    buf: [u8; METRICS_MAX],
+    len: usize,
+}
+
+impl PrometheusText {
+    fn of(serial: Option<[u16; 3]>, reading: &Measurement) -> Option<Self> {
+        let mut buf = [0u8; METRICS_MAX];
+        let len = format_metrics(serial, reading, &mut buf)?.len();
+        Some(Self { buf, len })
+    }
+}
+
+impl Content for PrometheusText {
+    fn content_type(&self) -> &'static str {
+        CONTENT_TYPE
+    }
+
+    fn content_length(&self) -> usize {
+        self.len
+    }
+
+    async fn write_content<W: Write>(self, mut writer: W) -> Result<(), W::Error> {
+        writer.write_all(&self.buf[..self.len]).await
+    }
+}
+
+/// Pull counterpart to the MQTT push, on `HTTP_PORT`:
+///
+/// - `GET /metrics`: the primary sensor's latest `Measurement` as JSON (same
+///   fields as `Measurement::to_json`), or 503 before the first reading;
+/// - `GET /prometheus`: the same reading as Prometheus gauges labelled with
+///   `serial` (see `prometheus`), or 503 before the first reading;
+/// - `GET /health`: startup self-test status and error counters as JSON.
+///
+/// One connection at a time; dashboards poll, so nothing queues for long.
+#[embassy_executor::task]
+pub async fn http_task(
+    stack: Stack<'static>,
+    mut readings: ReadingsSubscriber,
+    // The primary sensor's serial, for the Prometheus `serial` label.
+    serial: Option<[u16; 3]>,
+) {
+    let track = async {
+        loop {
+            let reading = readings.next_message_pure().await;
+            if reading.sensor_id == PRIMARY_SENSOR {
+                LATEST.lock(|latest| latest.set(Some(reading)));
+            }
+        }
+    };
+
+    let app = Router::new()
+        .route(
+            "/metrics",
+            get(|| async {
+                latest().map(Json).ok_or((StatusCode::SERVICE_UNAVAILABLE, "No measurement yet\n"))
+            }),
+        )
+        .route(
+            "/prometheus",
+            get(move || async move {
+                latest()
+                    .and_then(|reading| PrometheusText::of(serial, &reading))
+                    .ok_or((StatusCode::SERVICE_UNAVAILABLE, "No measurement yet\n"))
+            }),
+        )
+        .route("/health", get(|| async { Json(health()) }));
+    let config = Config::new(Timeouts {
+        start_read_request: Some(Duration::from_secs(5)),
+        persistent_start_read_request: Some(Duration::from_secs(1)),
+        read_request: Some(Duration::from_secs(1)),
+        write: Some(Duration::from_secs(1)),
+    });
+    let mut tcp_rx = [0u8; TCP_BUF];
+    let mut tcp_tx = [0u8; TCP_BUF];
+    let mut http_buf = [0u8; HTTP_BUF];
+    let serve = picoserve::listen_and_serve(
+        0,
+        &app,
+        &config,
+        stack,
+        HTTP_PORT,
+        &mut tcp_rx,
+        &mut tcp_tx,
+        &mut http_buf,
+    );
+
+    join(track, serve).await;
+}
@@ -0,0 +1,52 @@
+use defmt::warn;
+use embassy_net::udp::{PacketMetadata, UdpSocket};
+use embassy_net::{IpEndpoint, Ipv4Address, Stack};
+
+use crate::influx::{InfluxConfig, LINE_MAX};
+use crate::mux::PRIMARY_SENSOR;
+use crate::readings::{ReadingsSubscriber, READINGS_CAPACITY};
+use crate::wall_clock::unix_time_ms;
+
+/// Send every primary-sensor reading from `readings` as one line-protocol
+/// datagram to `config.host`. UDP is fire-and-forget: a line sent while the
+/// network is down is lost, and nothing is retried. Lines carry a timestamp
+/// once a time source has set the wall clock.
+#[embassy_executor::task]
+pub async fn influx_task(
+    stack: Stack<'static>,
+    config: InfluxConfig,
+    serial: Option<[u16; 3]>,
+    mut readings: ReadingsSubscriber,
+) {
+    let Some(([a, b, c, d], port)) = config.endpoint() else {
+        warn!("INFLUX_HOST {} is not ip:port; Influx output disabled", config.host);
+        return;
+    };
+    let server = IpEndpoint::new(Ipv4Address::new(a, b, c, d).into(), port);
+
+    // Nothing is received; the tx side holds a burst of queued readings.
+    let mut rx_meta = [PacketMetadata::EMPTY; 1];
+    let mut rx = [0u8; 16];
+    let mut tx_meta = [PacketMetadata::EMPTY; READINGS_CAPACITY];
+    let mut tx = [0u8; LINE_MAX * READINGS_CAPACITY];
+    let mut socket = UdpSocket::new(stack, &mut rx_meta, &mut rx, &mut tx_meta, &mut tx);
+    if socket.bind(0).is_err() {
+        warn!("Influx UDP socket bind failed; Influx output disabled");
+        return;
+    }
+
+    let mut line = [0u8; LINE_MAX];
+    loop {
+        let reading = readings.next_message_pure().await;
+        // The line's `serial` tag names the primary sensor.
+        if reading.sensor_id != PRIMARY_SENSOR {
+            continue;
+        }
+        let Some(payload) = config.format_line(serial, &reading, unix_time_ms(), &mut line) else {
+            continue;
+        };
+        if let Err(e) = socket.send_to(payload, server).await {
+            warn!("Influx send failed: {}", e);
+        }
+    }
+}
@@ -1,39 +1,169 @@
-use defmt::info;
+use defmt::{info, warn};
 use embassy_sync::blocking_mutex::raw::NoopRawMutex;
 use embassy_sync::channel::Receiver;
 use embassy_sync::mutex::Mutex;
 use embassy_time::Duration;
+use embassy_time::Instant;
 use embassy_time::Timer;
-use esp_hal::rmt::Channel as RmtChannel;
-use esp_hal::Blocking;
+use embassy_time::with_timeout;
 
-use crate::led::Led;
+use crate::led::ConnectionStatus;
+use crate::led::LedDriver;
 use crate::led::LedCommand;
+use crate::led::StatusLedConfig;
+use crate::led::desaturate;
+use crate::compensation::compensation_degraded;
+use crate::health::{record_led_failure, record_led_success};
+
+// Pause before retrying a failed LED write; long enough for a transient RMT
+// hiccup to clear, short enough to be invisible.
+const LED_RETRY_DELAY: Duration = Duration::from_millis(2);
 
 #[embassy_executor::task]
 pub async fn led_task(
     led_receiver: Receiver<'static, NoopRawMutex, LedCommand, 4>,
-    led: &'static Mutex<NoopRawMutex, Led<RmtChannel<Blocking, 0>>>,
+    led: &'static Mutex<NoopRawMutex, LedDriver>,
+    status_config: StatusLedConfig,
 ) {
+    // Last air-quality color, restored after a connection status blip. Main
+    // showed the initializing color before this task started.
+    let mut current: (u8, u8, u8) = status_config.initializing;
+
+    // A command that preempted a running animation, handled before waiting again.
+    let mut pending: Option<LedCommand> = None;
+
+    // Set by a `Disconnected` report, cleared by `Connected`. While set, the
+    // disconnected blip repeats at `next_link_blip` so a dead network stays
+    // visible between samples.
+    let mut link_down = false;
+    let mut next_link_blip = Instant::now();
+
     loop {
         // Wait for a command from the channel
-        let command = led_receiver.receive().await;
-        match command {
+        let command = match pending.take() {
+            Some(command) => command,
+            None if link_down => {
+                let wait = next_link_blip.saturating_duration_since(Instant::now());
+                with_timeout(wait, led_receiver.receive())
+                    .await
+                    .unwrap_or(LedCommand::Connection(ConnectionStatus::Disconnected))
+            }
+            None => led_receiver.receive().await,
+        };
+        let command = match command {
             LedCommand::Solid(r, g, b) => {
                 info!("Setting LED to solid color: R={}, G={}, B={}", r, g, b);
-                led.lock().await.set_color_rgb(r, g, b);
+                let (r, g, b) = degraded_hint(&status_config, (r, g, b));
+                current = (r, g, b);
+                LedCommand::Solid(r, g, b)
             }
-            LedCommand::Blink(r, g, b, period_ms_opt) => {
-                let period_ms = period_ms_opt.unwrap_or(300);
+            LedCommand::Blink(r, g, b, period_ms) => {
+                let (r, g, b) = degraded_hint(&status_config, (r, g, b));
                 info!(
                     "Blink LED: R={}, G={}, B={}, Period={}",
-                    r, g, b, period_ms
+                    r, g, b, period_ms.unwrap_or(300)
                 );
+                current = (r, g, b);
+                LedCommand::Blink(r, g, b, period_ms)
+            }
+            LedCommand::Connection(status) => {
+                info!("Connection status: {}", status);
+                match status {
+                    ConnectionStatus::Disconnected => {
+                        link_down = true;
+                        let every = Duration::from_millis(status_config.link_down_every_ms as u64);
+                        next_link_blip = Instant::now() + every;
+                    }
+                    ConnectionStatus::Connected => link_down = false,
+                    ConnectionStatus::Connecting => {}
+                }
+                command
+            }
+            LedCommand::Conditioning(animation) => {
+                info!("Conditioning animation: {}", animation);
+                command
+            }
+            LedCommand::Ready => {
+                info!("Warm-up complete: {}", status_config.ready_flash);
+                command
+            }
+            LedCommand::Pulse { r, g, b, period_ms } => {
+                let (r, g, b) = degraded_hint(&status_config, (r, g, b));
+                info!("Pulse LED: R={}, G={}, B={}, Period={}", r, g, b, period_ms);
+                current = (r, g, b);
+                LedCommand::Pulse { r, g, b, period_ms }
+            }
+            LedCommand::Alternate { first, second, period_ms } => {
+                let first = degraded_hint(&status_config, first);
+                let second = degraded_hint(&status_config, second);
+                info!("Alternate LED: {} / {}, Period={}", first, second, period_ms);
+                // A connection blip returns to the first color; the next
+                // sample restarts the alternation.
+                current = first;
+                LedCommand::Alternate { first, second, period_ms }
+            }
+            // An alert over the current color: `current` stays, so the burst
+            // ends on it.
+            LedCommand::BlinkN { r, g, b, period_ms, count } => {
+                let (r, g, b) = degraded_hint(&status_config, (r, g, b));
+                info!("Blink LED {}x: R={}, G={}, B={}, Period={}", count, r, g, b, period_ms);
+                LedCommand::BlinkN { r, g, b, period_ms, count }
+            }
+            LedCommand::Off => {
+                info!("LED off");
+                current = (0, 0, 0);
+                write_color(led, 0, 0, 0).await;
+                continue;
+            }
+        };
 
-                led.lock().await.set_color_rgb(0, 0, 0);
-                Timer::after(Duration::from_millis(period_ms as u64)).await;
-                led.lock().await.set_color_rgb(r, g, b);
+        // Animations and blink bursts run until any newer command arrives; the
+        // short blink/blip sequences always play to the end.
+        let preemptible = matches!(
+            command,
+            LedCommand::Conditioning(_)
+                | LedCommand::Pulse { .. }
+                | LedCommand::BlinkN { .. }
+                | LedCommand::Alternate { .. }
+        );
+        for frame in command.frames(&status_config, current) {
+            let (r, g, b) = frame.color;
+            write_color(led, r, g, b).await;
+            let Some(hold_ms) = frame.hold_ms else {
+                break;
+            };
+            let hold = Duration::from_millis(hold_ms as u64);
+            if !preemptible {
+                Timer::after(hold).await;
+            } else if let Ok(next) = with_timeout(hold, led_receiver.receive()).await {
+                pending = Some(next);
+                break;
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Write a color, retrying once after `LED_RETRY_DELAY` so a transient RMT
+/// failure doesn't drop a status update. Failures that survive the retry count
+/// toward the LED-unhealthy flag in `health`.
+async fn write_color(led: &Mutex<NoopRawMutex, LedDriver>, r: u8, g: u8, b: u8) {
+    if led.lock().await.set_color_rgb(r, g, b).is_ok() {
+        record_led_success();
+        return;
+    }
+    Timer::after(LED_RETRY_DELAY).await;
+    if led.lock().await.set_color_rgb(r, g, b).is_ok() {
+        record_led_success();
+    } else {
+        warn!("LED write failed after retry");
+        record_led_failure();
+    }
+}
+
+fn degraded_hint(config: &StatusLedConfig, color: (u8, u8, u8)) -> (u8, u8, u8) {
+    if config.desaturate_when_uncompensated && compensation_degraded() {
+        desaturate(color)
+    } else {
+        color
+    }
+}
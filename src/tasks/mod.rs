@@ -1,3 +1,22 @@
+pub mod ble;
+pub mod button;
 pub mod conditioning;
+#[cfg(feature = "co2-crosscheck")]
+pub mod crosscheck;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "influx")]
+pub mod influx;
 pub mod sgp41_measurement;
-pub mod led;
\ No newline at end of file
+pub mod led;
+#[cfg(feature = "wifi-mqtt")]
+pub mod mqtt;
+#[cfg(feature = "persistence")]
+pub mod persistence;
+pub mod relay;
+#[cfg(feature = "sdcard")]
+pub mod sdlog;
+#[cfg(feature = "sht4x")]
+pub mod sht4x;
+#[cfg(feature = "sntp")]
+pub mod sntp;
@@ -0,0 +1,227 @@
+// `mqtt_task` runs next to the radio on core 0 and sends on the LED queue,
+// whose `NoopRawMutex` receiver moves to the app core under `dual-core`.
+#[cfg(feature = "dual-core")]
+compile_error!("`wifi-mqtt` needs a single-core build: the MQTT task shares the core-local LED queue, which `dual-core` moves to the app core");
+
+use core::convert::Infallible;
+
+use defmt::{info, warn};
+use embassy_net::tcp::TcpSocket;
+use embassy_net::{IpEndpoint, Ipv4Address, Runner, Stack};
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::channel::Sender;
+use embassy_time::{Duration, Instant, Timer};
+use esp_wifi::wifi::{ClientConfiguration, Configuration, WifiController, WifiDevice, WifiEvent, WifiState};
+use rust_mqtt::client::client::MqttClient;
+use rust_mqtt::client::client_config::{ClientConfig, MqttVersion};
+use rust_mqtt::packet::v5::publish_packet::QualityOfService;
+use rust_mqtt::utils::rng_generator::CountingRng;
+
+use crate::ble::DeviceName;
+use crate::health;
+use crate::led::{ConnectionStatus, LedCommand};
+use crate::mqtt::{
+    health_payload, reading_payload, DiscoveryEntity, MqttConfig, QoS, Topic, WifiConfig, DISCOVERY_MAX, ONLINE,
+    PAYLOAD_MAX,
+};
+use crate::mux::PRIMARY_SENSOR;
+use crate::readings::{ReadingsSubscriber, MEASUREMENT_JSON_MAX};
+
+// Reconnect backoff: doubles after every failed attempt, reset on success.
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+
+const SOCKET_BUF: usize = 1024;
+// Largest MQTT packet either way: a discovery config (`DISCOVERY_MAX`) plus
+// its topic and the publish header.
+const MQTT_BUF: usize = 640;
+const KEEP_ALIVE_S: u16 = 60;
+// Health is diagnostics; once a minute is plenty.
+const HEALTH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Keep the station associated with `config.ssid`, reconnecting with backoff.
+#[embassy_executor::task]
+pub async fn wifi_task(mut controller: WifiController<'static>, config: WifiConfig) {
+    let mut backoff = RECONNECT_MIN;
+    loop {
+        if esp_wifi::wifi::wifi_state() == WifiState::StaConnected {
+            controller.wait_for_event(WifiEvent::StaDisconnected).await;
+            warn!("Wi-Fi disconnected");
+        }
+        if !matches!(controller.is_started(), Ok(true)) {
+            let client = Configuration::Client(ClientConfiguration {
+                ssid: config.ssid.into(),
+                password: config.password.into(),
+                ..Default::default()
+            });
+            if controller.set_configuration(&client).is_err() || controller.start_async().await.is_err() {
+                warn!("Wi-Fi start failed");
+            }
+        }
+        match controller.connect_async().await {
+            Ok(()) => {
+                info!("Wi-Fi connected to {}", config.ssid);
+                backoff = RECONNECT_MIN;
+            }
+            Err(e) => {
+                warn!("Wi-Fi connect failed: {}; retrying in {} s", e, backoff.as_secs());
+                Timer::after(backoff).await;
+                backoff = (backoff * 2).min(RECONNECT_MAX);
+            }
+        }
+    }
+}
+
+/// Drive the embassy-net stack.
+#[embassy_executor::task]
+pub async fn net_task(mut runner: Runner<'static, WifiDevice<'static>>) -> ! {
+    runner.run().await
+}
+
+/// Publish every reading from `readings` as JSON (`Measurement::to_json`) to
+/// `<base>/<device>/measurement`, i.e. at the measurement cadence, and the
+/// primary sensor's readings to the per-metric topics (`voc`, `nox`,
+/// `category`, `raw`). `health` goes out every `HEALTH_INTERVAL`. Registers
+/// the availability will, marks the device online and sends the Home
+/// Assistant discovery configs on every connect.
+///
+/// Any network or broker error drops the session: the LED keeps blipping the
+/// disconnected color until the next successful connect, and the task
+/// reconnects with exponential backoff. Readings beyond `READINGS_CAPACITY` that arrive while
+/// disconnected are lost.
+#[embassy_executor::task]
+pub async fn mqtt_task(
+    stack: Stack<'static>,
+    wifi: WifiConfig,
+    mqtt: MqttConfig,
+    device: &'static DeviceName,
+    serial: Option<[u16; 3]>,
+    mut readings: ReadingsSubscriber,
+    led_sender: Sender<'static, NoopRawMutex, LedCommand, 4>,
+) {
+    let mut backoff = RECONNECT_MIN;
+    loop {
+        stack.wait_config_up().await;
+        let _ = led_sender.try_send(LedCommand::Connection(ConnectionStatus::Connecting));
+        // A session only ends in an error; reaching the broker at all resets
+        // the backoff.
+        match publish_session(stack, &wifi, &mqtt, device, serial, &mut readings, &led_sender).await {
+            Ok(never) => match never {},
+            Err(Session::Dropped) => {
+                warn!("MQTT session lost; reconnecting");
+                let _ = led_sender.try_send(LedCommand::Connection(ConnectionStatus::Disconnected));
+                backoff = RECONNECT_MIN;
+            }
+            Err(Session::ConnectFailed) => {
+                warn!("MQTT broker unreachable; retrying in {} s", backoff.as_secs());
+                let _ = led_sender.try_send(LedCommand::Connection(ConnectionStatus::Disconnected));
+                Timer::after(backoff).await;
+                backoff = (backoff * 2).min(RECONNECT_MAX);
+            }
+        }
+    }
+}
+
+enum Session {
+    ConnectFailed,
+    Dropped,
+}
+
+async fn publish_session(
+    stack: Stack<'static>,
+    wifi: &WifiConfig,
+    mqtt: &MqttConfig,
+    device: &DeviceName,
+    serial: Option<[u16; 3]>,
+    readings: &mut ReadingsSubscriber,
+    led_sender: &Sender<'static, NoopRawMutex, LedCommand, 4>,
+) -> Result<Infallible, Session> {
+    let mut rx = [0u8; SOCKET_BUF];
+    let mut tx = [0u8; SOCKET_BUF];
+    let mut socket = TcpSocket::new(stack, &mut rx, &mut tx);
+    socket.set_timeout(Some(Duration::from_secs(KEEP_ALIVE_S as u64 * 2)));
+    let [a, b, c, d] = wifi.broker_addr;
+    let broker = IpEndpoint::new(Ipv4Address::new(a, b, c, d).into(), wifi.broker_port);
+    socket.connect(broker).await.map_err(|_| Session::ConnectFailed)?;
+
+    let will = mqtt.last_will(device);
+    let mut client_config = ClientConfig::<'_, 5, CountingRng>::new(MqttVersion::MQTTv5, CountingRng(20000));
+    client_config.add_client_id(device.as_str());
+    client_config.keep_alive = KEEP_ALIVE_S;
+    client_config.max_packet_size = MQTT_BUF as u32;
+    client_config.add_will(will.topic.as_str(), will.payload, will.retain);
+    let mut write_buf = [0u8; MQTT_BUF];
+    let mut recv_buf = [0u8; MQTT_BUF];
+    let mut client =
+        MqttClient::<_, 5, _>::new(socket, &mut write_buf, MQTT_BUF, &mut recv_buf, MQTT_BUF, client_config);
+    client.connect_to_broker().await.map_err(|_| Session::ConnectFailed)?;
+    info!("MQTT connected");
+    let _ = led_sender.try_send(LedCommand::Connection(ConnectionStatus::Connected));
+
+    let availability = mqtt.topic(device, Topic::Availability);
+    client
+        .send_message(availability.as_str(), ONLINE, qos(mqtt.availability_qos), true)
+        .await
+        .map_err(|_| Session::Dropped)?;
+
+    let mut discovery = [0u8; DISCOVERY_MAX];
+    for &entity in DiscoveryEntity::all() {
+        let Some(topic) = mqtt.discovery_topic(device, entity) else {
+            break;
+        };
+        let Some(payload) = mqtt.discovery_payload(device, serial, entity, &mut discovery) else {
+            continue;
+        };
+        client
+            .send_message(topic.as_str(), payload, qos(mqtt.availability_qos), true)
+            .await
+            .map_err(|_| Session::Dropped)?;
+    }
+
+    let measurement = mqtt.topic(device, Topic::Measurement);
+    let mut json = [0u8; MEASUREMENT_JSON_MAX];
+    let mut buf = [0u8; PAYLOAD_MAX];
+    let mut next_health = Instant::now();
+    loop {
+        let reading = readings.next_message_pure().await;
+        if let Some(payload) = reading.to_json(&mut json) {
+            client
+                .send_message(measurement.as_str(), payload, qos(mqtt.qos(Topic::Measurement)), false)
+                .await
+                .map_err(|_| Session::Dropped)?;
+        }
+        // The per-metric topics describe the device, i.e. its primary sensor.
+        if reading.sensor_id == PRIMARY_SENSOR {
+            for topic in [Topic::Voc, Topic::Nox, Topic::Category, Topic::Raw] {
+                let Some(payload) = reading_payload(topic, &reading, &mut buf) else {
+                    continue;
+                };
+                client
+                    .send_message(mqtt.topic(device, topic).as_str(), payload, qos(mqtt.qos(topic)), false)
+                    .await
+                    .map_err(|_| Session::Dropped)?;
+            }
+        }
+        if Instant::now() >= next_health {
+            next_health = Instant::now() + HEALTH_INTERVAL;
+            let payload = health_payload(&health::snapshot(), &mut buf);
+            client
+                .send_message(
+                    mqtt.topic(device, Topic::Health).as_str(),
+                    payload,
+                    qos(mqtt.qos(Topic::Health)),
+                    false,
+                )
+                .await
+                .map_err(|_| Session::Dropped)?;
+        }
+    }
+}
+
+// rust-mqtt has no exactly-once publishing; downgrade to at-least-once.
+fn qos(qos: QoS) -> QualityOfService {
+    match qos {
+        QoS::AtMostOnce => QualityOfService::QoS0,
+        QoS::AtLeastOnce | QoS::ExactlyOnce => QualityOfService::QoS1,
+    }
+}
@@ -0,0 +1,125 @@
+use defmt::{info, warn};
+use embassy_futures::select::{select3, Either3};
+use embassy_time::Timer;
+use embedded_storage::{ReadStorage, Storage};
+use esp_storage::FlashStorage;
+
+use crate::algo::{export_state, import_state, GasIndex};
+use crate::calibration::{IndexOffset, CALIBRATION_UPDATE};
+use crate::persistence::{
+    AlgorithmSnapshot, CalibrationRecord, PersistenceConfig, CALIBRATION_RECORD_LEN, SNAPSHOT_RECORD_LEN,
+};
+use crate::run_limit::RUN_COMPLETE;
+
+/// Load the saved state into freshly built algorithms. Returns whether a
+/// valid record was found; otherwise the algorithms are left untouched.
+/// Call before the conditioning and measurement tasks start.
+pub fn restore(
+    flash: &mut FlashStorage,
+    config: &PersistenceConfig,
+    voc_algo: &GasIndex,
+    nox_algo: &GasIndex,
+) -> bool {
+    let mut record = [0u8; SNAPSHOT_RECORD_LEN];
+    if flash.read(config.flash_offset, &mut record).is_err() {
+        warn!("Flash read failed; starting with fresh algorithms");
+        return false;
+    }
+    let Some(snapshot) = AlgorithmSnapshot::from_record(&record) else {
+        info!("No saved algorithm state; starting fresh");
+        return false;
+    };
+    match (voc_algo.try_borrow_mut(), nox_algo.try_borrow_mut()) {
+        (Ok(mut voc), Ok(mut nox)) => {
+            import_state(&mut voc, &snapshot.voc);
+            import_state(&mut nox, &snapshot.nox);
+        }
+        _ => {
+            warn!("Gas index algorithm busy; saved state not restored");
+            return false;
+        }
+    }
+    info!("Restored algorithm state from flash");
+    true
+}
+
+/// The saved index offset for the sensor with `serial`, or
+/// `IndexOffset::NONE` if there is none (or it was saved for another sensor).
+pub fn load_calibration(flash: &mut FlashStorage, config: &PersistenceConfig, serial: Option<[u16; 3]>) -> IndexOffset {
+    let mut record = [0u8; CALIBRATION_RECORD_LEN];
+    if flash.read(config.calibration_offset, &mut record).is_err() {
+        warn!("Flash read failed; no index offset applied");
+        return IndexOffset::NONE;
+    }
+    match CalibrationRecord::from_record(&record).and_then(|r| r.offset_for(serial)) {
+        Some(offset) => {
+            info!("Index offset from flash: {}", offset);
+            offset
+        }
+        None => IndexOffset::NONE,
+    }
+}
+
+/// Save the algorithm state every `save_interval`, and once more when a
+/// bounded run completes (`run_limit::RUN_COMPLETE`) so the final state isn't
+/// lost. Warm-up samples aren't worth keeping, so nothing is saved until the
+/// first interval has passed. A new index offset
+/// (`calibration::CALIBRATION_UPDATE`) is saved right away for `serial`.
+#[embassy_executor::task]
+pub async fn persistence_task(
+    mut flash: FlashStorage,
+    config: PersistenceConfig,
+    serial: Option<[u16; 3]>,
+    voc_algo: &'static GasIndex,
+    nox_algo: &'static GasIndex,
+) {
+    loop {
+        match select3(Timer::after(config.save_interval), RUN_COMPLETE.wait(), CALIBRATION_UPDATE.wait()).await {
+            Either3::First(_) => save(&mut flash, &config, voc_algo, nox_algo),
+            Either3::Second(_) => {
+                save(&mut flash, &config, voc_algo, nox_algo);
+                return;
+            }
+            Either3::Third(offset) => save_calibration(&mut flash, &config, serial, offset),
+        }
+    }
+}
+
+fn save_calibration(
+    flash: &mut FlashStorage,
+    config: &PersistenceConfig,
+    serial: Option<[u16; 3]>,
+    offset: IndexOffset,
+) {
+    // Without a serial the offset couldn't be matched to this sensor at boot.
+    let Some(serial) = serial else {
+        warn!("Sensor serial unknown; index offset not saved");
+        return;
+    };
+    match flash.write(config.calibration_offset, &CalibrationRecord { serial, offset }.to_record()) {
+        Ok(()) => info!("Saved index offset to flash"),
+        Err(_) => warn!("Flash write failed; index offset not saved"),
+    }
+}
+
+fn save(
+    flash: &mut FlashStorage,
+    config: &PersistenceConfig,
+    voc_algo: &GasIndex,
+    nox_algo: &GasIndex,
+) {
+    let snapshot = match (voc_algo.try_borrow(), nox_algo.try_borrow()) {
+        (Ok(voc), Ok(nox)) => AlgorithmSnapshot {
+            voc: export_state(&voc),
+            nox: export_state(&nox),
+        },
+        _ => {
+            warn!("Gas index algorithm busy; state save skipped");
+            return;
+        }
+    };
+    match flash.write(config.flash_offset, &snapshot.to_record()) {
+        Ok(()) => info!("Saved algorithm state to flash"),
+        Err(_) => warn!("Flash write failed; algorithm state not saved"),
+    }
+}
@@ -0,0 +1,17 @@
+use defmt::info;
+use esp_hal::gpio::Output;
+
+use crate::escalation::FAN_RELAY;
+
+#[embassy_executor::task]
+pub async fn relay_task(mut relay: Output<'static>) {
+    loop {
+        let on = FAN_RELAY.wait().await;
+        info!("Fan relay {}", if on { "ON" } else { "OFF" });
+        if on {
+            relay.set_high();
+        } else {
+            relay.set_low();
+        }
+    }
+}
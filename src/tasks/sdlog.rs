@@ -0,0 +1,149 @@
+use defmt::{info, warn};
+use embassy_time::{Duration, Timer};
+use embedded_hal_bus::spi::ExclusiveDevice;
+use embedded_sdmmc::{BlockDevice, Directory, Mode, SdCard, TimeSource, Timestamp, VolumeIdx, VolumeManager};
+use esp_hal::delay::Delay;
+use esp_hal::gpio::Output;
+use esp_hal::spi::master::Spi;
+use esp_hal::Blocking;
+
+use crate::csv::{CsvRow, CSV_HEADER, CSV_ROW_MAX};
+use crate::mux::PRIMARY_SENSOR;
+use crate::readings::ReadingsSubscriber;
+use crate::sdlog::{utc_day, LogFile, Rotation, SdLogConfig};
+
+// Wait before retrying a missing or failing card.
+const CARD_RETRY: Duration = Duration::from_secs(30);
+
+/// FAT timestamps aren't used for rotation; file names carry the order.
+struct FixedTime;
+
+impl TimeSource for FixedTime {
+    fn get_timestamp(&self) -> Timestamp {
+        Timestamp {
+            year_since_1970: 0,
+            zero_indexed_month: 0,
+            zero_indexed_day: 0,
+            hours: 0,
+            minutes: 0,
+            seconds: 0,
+        }
+    }
+}
+
+type Root<'a, D> = Directory<'a, D, FixedTime, 4, 4, 1>;
+
+type Card = SdCard<ExclusiveDevice<Spi<'static, Blocking>, Output<'static>, Delay>, Delay>;
+
+/// Log the primary sensor's readings from `readings::READINGS`. A second
+/// sensor's readings are not logged; the CSV schema has no sensor column.
+#[embassy_executor::task]
+pub async fn sdlog_task(
+    spi: Spi<'static, Blocking>,
+    cs: Output<'static>,
+    config: SdLogConfig,
+    mut readings: ReadingsSubscriber,
+) {
+    let Ok(device) = ExclusiveDevice::new(spi, cs, Delay::new()) else {
+        warn!("SD card chip select unavailable; SD logging disabled");
+        return;
+    };
+    let card: Card = SdCard::new(device, Delay::new());
+    let mut volumes = VolumeManager::new(card, FixedTime);
+
+    loop {
+        if log_until_error(&mut volumes, &config, &mut readings).await.is_err() {
+            // Card absent, full or removed: readings published meanwhile are
+            // skipped (the subscriber lags) and the card is retried later.
+            warn!("SD card unavailable; retrying in {} s", CARD_RETRY.as_secs());
+            Timer::after(CARD_RETRY).await;
+        }
+    }
+}
+
+/// Append rows until the card fails. Each row is opened, appended and closed,
+/// so a pulled card loses at most the row being written.
+async fn log_until_error<D: BlockDevice>(
+    volumes: &mut VolumeManager<D, FixedTime>,
+    config: &SdLogConfig,
+    readings: &mut ReadingsSubscriber,
+) -> Result<(), ()> {
+    let volume = volumes.open_volume(VolumeIdx(0)).map_err(|_| ())?;
+    let root = volume.open_root_dir().map_err(|_| ())?;
+
+    let logs = scan(&root)?;
+    let mut rotation = Rotation::resume(logs.newest_undated, logs.newest_dated);
+    // The file being written always stays.
+    let max_files = config.max_files.max(1);
+
+    loop {
+        let reading = readings.next_message_pure().await;
+        if reading.sensor_id != PRIMARY_SENSOR {
+            continue;
+        }
+        let row = CsvRow::new(reading);
+
+        let (file, changed) = rotation.file_for(row.unix_ms.map(utc_day), config.max_file_bytes);
+        let name = file.name();
+        let name = core::str::from_utf8(&name).unwrap_or_default();
+        if changed {
+            info!("SD logging to {=str}", name);
+            // Keep at most `max_files` logs, counting the one about to be
+            // created.
+            loop {
+                let logs = scan(&root)?;
+                match logs.oldest {
+                    Some(oldest) if logs.count >= max_files && oldest != file => {
+                        let old = oldest.name();
+                        root
+                            .delete_file_in_dir(core::str::from_utf8(&old).unwrap_or_default())
+                            .map_err(|_| ())?;
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        let log = root
+            .open_file_in_dir(name, Mode::ReadWriteCreateOrAppend)
+            .map_err(|_| ())?;
+        if log.length() == 0 {
+            log.write(CSV_HEADER.as_bytes()).map_err(|_| ())?;
+        }
+        let mut buf = [0u8; CSV_ROW_MAX];
+        log.write(row.format(&mut buf)).map_err(|_| ())?;
+        rotation.wrote(log.length());
+        log.close().map_err(|_| ())?;
+    }
+}
+
+/// Our logs on the card: how many, the oldest (to prune) and the newest
+/// undated and dated ones (to resume after).
+struct Logs {
+    count: u32,
+    oldest: Option<LogFile>,
+    newest_undated: Option<u32>,
+    newest_dated: Option<LogFile>,
+}
+
+fn scan<D: BlockDevice>(root: &Root<'_, D>) -> Result<Logs, ()> {
+    let mut logs = Logs {
+        count: 0,
+        oldest: None,
+        newest_undated: None,
+        newest_dated: None,
+    };
+    root.iterate_dir(|entry| {
+        let Some(file) = LogFile::parse(entry.name.base_name(), entry.name.extension()) else {
+            return;
+        };
+        logs.count += 1;
+        logs.oldest = Some(logs.oldest.map_or(file, |o| o.min(file)));
+        match file {
+            LogFile::Undated(n) => logs.newest_undated = Some(logs.newest_undated.map_or(n, |m| m.max(n))),
+            LogFile::Dated { .. } => logs.newest_dated = Some(logs.newest_dated.map_or(file, |m| m.max(file))),
+        }
+    })
+    .map_err(|_| ())?;
+    Ok(logs)
+}
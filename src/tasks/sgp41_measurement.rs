@@ -1,89 +1,473 @@
-use crate::led::LedCommand;
-use core::sync::atomic::Ordering;
-use defmt::{error, info};
+use crate::algo::{export_state, import_state, state_to_hex, GasIndex};
+use crate::escalation::{Gas, EscalationAction, EscalationEvent, SustainedMonitor, FAN_RELAY};
+use crate::led::{air_quality_command, send_or_drop, ConditioningAnimation, LedCommand, LED_INDEX_WINDOW};
+use crate::measurement::MeasurementResult;
+use crate::reporting::{set_voc_only_reporting, voc_only_reporting, IndexSmoother, Reporter};
+use crate::filter::MovingAverage;
+use crate::freeze::{FailureStreak, FreezeDetector};
+use crate::health::{self, nox_degraded, record_crc_error, record_i2c_error, record_measurement};
+use crate::run_limit::RUN_COMPLETE;
+use crate::soak::SoakTest;
+use crate::state::DeviceState;
+use crate::sampling::{measure_or_rest, process_raw};
+use crate::power_cycle::{PowerCycleDetector, PowerCycleResponse};
+use defmt::{debug, error, info, warn};
 use embassy_sync::blocking_mutex::raw::NoopRawMutex;
 use embassy_sync::channel::Sender;
-use embassy_sync::mutex::Mutex;
-use embassy_time::{Duration, Timer};
-use embedded_hal_02::blocking::i2c::{Read, Write};
-use gas_index_algorithm::GasIndexAlgorithm;
-use core::cell::RefCell;
+use embassy_time::{Duration, Instant, Timer};
 
-use crate::hal::I2cCompat;
-use crate::prepare_temp_hum_params;
-use crate::tasks::conditioning::{CMD_MEASURE_RAW_SIGNALS, CONDITION_DONE, SGP41_ADDR};
+use crate::ble::{RawTicks, RAW_TICKS};
+use crate::baseline::RollingBaseline;
+use crate::calibration::CALIBRATION_UPDATE;
+use crate::category::voc_category;
+use crate::compensation::{params_for, CompensationState};
+use crate::quality::{is_outlier, QualityFactors};
+use crate::readings::{Measurement, READINGS};
+use crate::config::{get_config, update_config, MeasurementConfig};
+use crate::control::{ControlCommand, CONTROL};
+use crate::driver::{Sgp41, Sgp41Error};
+use crate::humidity::absolute_humidity;
+use crate::mux::{SensorBus, MAX_SENSORS};
+use crate::wall_clock::{delay_to_boundary, unix_time_ms};
+use crate::ticks_to_temp_hum;
+use crate::tasks::conditioning::{
+    recondition, soft_reset, transition_primary, turn_heater_off, update_primary_config,
+    CONDITION_DONE,
+};
 
-#[embassy_executor::task]
+// Fast red blink while a stuck sensor is soft-reset and re-conditioned.
+const STUCK_RECOVERY_LED: LedCommand = LedCommand::Blink(30, 0, 0, Some(100));
+
+// Run one per sensor. Only the primary sensor (`SensorBus::is_primary`) takes
+// control commands, feeds the BLE raw ticks and CO2 cross-check, and
+// writes the device state, config snapshot and VOC-only flag; every sensor
+// publishes to `READINGS`, tagged with its id.
+#[embassy_executor::task(pool_size = MAX_SENSORS)]
 pub async fn sgp41_measurement_task(
-    bus: &'static Mutex<NoopRawMutex, I2cCompat<'static>>,
-    _led_sender: Sender<'static, NoopRawMutex, LedCommand, 4>,
-    voc_algo: &'static RefCell<GasIndexAlgorithm>,
-    nox_algo: &'static RefCell<GasIndexAlgorithm>,
+    bus: SensorBus,
+    // `None` leaves the LED to another sensor.
+    led_sender: Option<Sender<'static, NoopRawMutex, LedCommand, 4>>,
+    voc_algo: &'static GasIndex,
+    nox_algo: &'static GasIndex,
+    config: MeasurementConfig,
 ) {
     // Wait until conditioning has handed over the bus.
-    while !CONDITION_DONE.load(Ordering::Acquire) {
-        Timer::after(Duration::from_millis(100)).await;
+    CONDITION_DONE[bus.id as usize].wait().await;
+
+    info!("Sensor {}: starting normal measurements…", bus.id);
+    let MeasurementConfig {
+        sensor,
+        compensation,
+        power_cycle,
+        align_to_wall_clock,
+        escalation,
+        reporting,
+        freeze_threshold,
+        failure_threshold,
+        soak_duration,
+        mut index_offset,
+        run_limit,
+        combined_alarm,
+        maintenance,
+        rolling_baseline,
+    } = config;
+    let led = |command| {
+        if let Some(sender) = &led_sender {
+            send_or_drop(sender, command);
+        }
+    };
+    let interval = sensor.measurement_interval;
+
+    let mut power_cycle_detector = PowerCycleDetector::new(&power_cycle);
+    update_primary_config(&bus, |c| {
+        c.measurement_interval_ms = interval.as_millis() as u32;
+        c.align_to_wall_clock = align_to_wall_clock;
+        c.escalation = escalation;
+        c.compensation = compensation;
+        c.reporting = Some(reporting);
+        c.index_offset = index_offset;
+        c.max_measurements = run_limit.max_measurements;
+        c.baseline_window_s = rolling_baseline.map(|w| w.as_secs() as u32);
+    });
+    // A failed NOx pixel keeps NOx hidden whatever the policy asks for. The
+    // primary's policy decides for the whole device.
+    if bus.is_primary() {
+        set_voc_only_reporting(reporting.voc_only_reporting || nox_degraded());
     }
+    let mut reporter = Reporter::new(reporting);
+    let mut smoother = IndexSmoother::new(reporting.index_smoothing);
+    let mut led_voc = MovingAverage::<LED_INDEX_WINDOW>::new();
+    let mut led_nox = MovingAverage::<LED_INDEX_WINDOW>::new();
+    let mut escalation = escalation.map(SustainedMonitor::new);
+    let mut freeze_detector = freeze_threshold.map(FreezeDetector::new);
+    let mut failure_streak = failure_threshold.map(FailureStreak::new);
+    let mut soak = soak_duration.map(SoakTest::start);
+    let mut rolling_baseline = rolling_baseline.map(|window| RollingBaseline::new(window, Instant::now()));
+    update_primary_config(&bus, |c| c.soak_duration_s = soak_duration.map(|d| d.as_secs() as u32));
+
+    let run_started = Instant::now();
+    let mut last_maintenance = Instant::now();
+    let mut measurements: u32 = 0;
+    // The ready flash marks the end of warm-up, so it plays only before the
+    // first air-quality color, never after maintenance reconditioning.
+    let mut warm_up_announced = false;
+    // Inputs to the quality score carried between samples.
+    let mut crc_since_last_sample = false;
+    let mut previous_voc_raw: Option<u16> = None;
+    // When the previous reading came in, for the loop jitter log.
+    let mut previous_sample: Option<Instant> = None;
 
-    info!("Starting normal measurements…");
+    // Delay the first sample to a wall-clock boundary when time is known.
+    if align_to_wall_clock && unix_time_ms().is_some() {
+        Timer::after(delay_to_boundary(interval)).await;
+    }
 
     loop {
-        // Prepare measurement command with temperature (25 °C) and humidity (50 % RH).
-        let params = prepare_temp_hum_params(25.0, 50.0);
-        let mut cmd_with_params = [0u8; 8];
-        cmd_with_params[0] = CMD_MEASURE_RAW_SIGNALS[0];
-        cmd_with_params[1] = CMD_MEASURE_RAW_SIGNALS[1];
-        cmd_with_params[2..8].copy_from_slice(&params);
-
-        // ── write ─────────────────────────────────────────────────────────────
-        if bus.lock().await.write(SGP41_ADDR, &cmd_with_params).is_err() {
-            error!("Failed to send measurement command");
-            Timer::after(Duration::from_secs(1)).await;
-            continue;
+        // Handle pending control commands between samples
+        while let Some(command) = next_control(&bus) {
+            match command {
+                ControlCommand::DumpConfig => {
+                    info!("Active config: {}", get_config());
+                    info!(
+                        "Tuning VOC={} NOx={}",
+                        voc_algo.get_tuning_parameters(),
+                        nox_algo.get_tuning_parameters()
+                    );
+                }
+                ControlCommand::ExportAlgorithmState => {
+                    match (voc_algo.try_borrow(), nox_algo.try_borrow()) {
+                        (Ok(voc), Ok(nox)) => {
+                            let voc_hex = state_to_hex(&export_state(&voc));
+                            let nox_hex = state_to_hex(&export_state(&nox));
+                            info!(
+                                "Algorithm state VOC={=[u8]:a} NOx={=[u8]:a}",
+                                voc_hex[..],
+                                nox_hex[..]
+                            );
+                        }
+                        _ => warn!("Gas index algorithm busy; state export skipped"),
+                    }
+                }
+                ControlCommand::ImportAlgorithmState { gas, state } => {
+                    let algo = match gas {
+                        Gas::Voc => voc_algo,
+                        Gas::Nox => nox_algo,
+                    };
+                    match algo.try_borrow_mut() {
+                        Ok(mut algo) => {
+                            import_state(&mut algo, &state);
+                            info!("Imported {} algorithm state", gas);
+                        }
+                        Err(_) => warn!("Gas index algorithm busy; state import skipped"),
+                    }
+                }
+                ControlCommand::CleanAirReset { recondition_secs } => {
+                    info!("Clean air reset: re-baselining");
+                    if voc_algo.reset().and(nox_algo.reset()).is_err() {
+                        warn!("Gas index algorithm busy; skipping reset");
+                    }
+                    // White flash confirms the gesture, then the conditioning animation
+                    led(LedCommand::Blink(30, 30, 30, Some(100)));
+                    led(LedCommand::Conditioning(ConditioningAnimation::default()));
+                    recondition(&bus, recondition_secs, compensation).await;
+                    power_cycle_detector.reset();
+                }
+                ControlCommand::StartSoak { duration_s } => {
+                    soak = Some(SoakTest::start(Duration::from_secs(duration_s as u64)));
+                    update_config(|c| c.soak_duration_s = Some(duration_s));
+                }
+                ControlCommand::ReadSerial => {
+                    // Between samples, so it never interleaves with a measurement.
+                    match Sgp41::new(bus.lock().await).read_serial_number().await {
+                        Ok(words) => info!(
+                            "SGP41 serial: {:04X}{:04X}{:04X} (CRC ok)",
+                            words[0], words[1], words[2]
+                        ),
+                        Err(Sgp41Error::CrcMismatch { expected, got }) => warn!(
+                            "SGP41 serial read failed CRC: expected 0x{:02X}, got 0x{:02X}",
+                            expected, got
+                        ),
+                        Err(e) => error!("SGP41 serial read failed: {}", e),
+                    }
+                }
+                ControlCommand::SetCalibration { offset } => {
+                    info!("Index offset set to {}", offset);
+                    index_offset = offset;
+                    update_config(|c| c.index_offset = offset);
+                    CALIBRATION_UPDATE.signal(offset);
+                }
+            }
         }
 
-        // wait 50 ms before reading
-        Timer::after(Duration::from_millis(50)).await;
+        if let Some(m) = maintenance {
+            if last_maintenance.elapsed() >= m.every {
+                info!("Maintenance re-conditioning");
+                recondition(&bus, m.capped_secs(), compensation).await;
+                // Algorithm state is kept; only the detectors restart.
+                power_cycle_detector.reset();
+                if let Some(detector) = freeze_detector.as_mut() {
+                    detector.reset();
+                }
+                last_maintenance = Instant::now();
+            }
+        }
 
-        // ── read ──────────────────────────────────────────────────────────────
-        let mut buffer = [0u8; 6];
-        if bus.lock().await.read(SGP41_ADDR, &mut buffer).is_err() {
-            error!("Failed to read SGP41 measurement data");
-            Timer::after(Duration::from_secs(1)).await;
-            continue;
+        // Prepare measurement command with the configured compensation,
+        // keeping the ticks so the result reports exactly what was sent.
+        let comp_ticks = compensation.ticks();
+        let params = params_for(comp_ticks);
+
+        // ── measure ───────────────────────────────────────────────────────────
+        // On failure the heater is already off (the next measure command
+        // turns it back on); skip this sample and retry after one interval.
+        let read = measure_or_rest(&mut Sgp41::new(bus.lock().await), params).await;
+        let (voc_raw, nox_raw) = match read {
+            Ok(raw) => raw,
+            Err(e) => {
+                match e {
+                    Sgp41Error::I2c(e) => {
+                        error!("SGP41 measurement failed on the bus: {}", e);
+                        record_i2c_error();
+                    }
+                    e => {
+                        error!("SGP41 measurement rejected: {}", e);
+                        record_crc_error();
+                        crc_since_last_sample = true;
+                    }
+                }
+                if let Some(count) = failure_streak.as_mut().and_then(|s| s.record_failure()) {
+                    warn!("SGP41 stuck: {} failed measurements in a row; soft-resetting", count);
+                    transition_primary(&bus, DeviceState::Fault);
+                    led(STUCK_RECOVERY_LED);
+                    soft_reset(&bus).await;
+                    recondition(&bus, power_cycle.recondition_secs, compensation).await;
+                    power_cycle_detector.reset();
+                    if let Some(detector) = freeze_detector.as_mut() {
+                        detector.reset();
+                    }
+                    continue;
+                }
+                // Don't leave a stale air-quality color up while backing off.
+                led(LedCommand::Off);
+                Timer::after(interval).await;
+                continue;
+            }
+        };
+        if let Some(streak) = failure_streak.as_mut() {
+            streak.record_success();
         }
+        // One timestamp per cycle: logs, the published reading and the SD log
+        // row all carry the same time.
+        let sampled_at = Instant::now();
 
-        let voc_raw = u16::from_be_bytes([buffer[0], buffer[1]]);
-        let nox_raw = u16::from_be_bytes([buffer[3], buffer[4]]);
+        if bus.is_primary() {
+            RAW_TICKS.signal(RawTicks::new(voc_raw, nox_raw, params));
+        }
+        let outlier = is_outlier(previous_voc_raw, voc_raw);
+        previous_voc_raw = Some(voc_raw);
 
-        info!("SGP41 Raw Measurements:");
+        info!("SGP41 Raw Measurements (t={} ms):", sampled_at.as_millis());
+        if let Some(previous) = previous_sample.replace(sampled_at) {
+            info!("  Since last reading: {} ms", (sampled_at - previous).as_millis());
+        }
         info!("  VOC Raw: {} ticks", voc_raw);
-        info!("  NOx Raw: {} ticks", nox_raw);
+        if !voc_only_reporting() {
+            info!("  NOx Raw: {} ticks", nox_raw);
+        }
+
+        if let Some(count) = freeze_detector.as_mut().and_then(|d| d.update(voc_raw, nox_raw)) {
+            warn!(
+                "Sensor output frozen: VOC={} NOx={} repeated {} times; soft-resetting",
+                voc_raw, nox_raw, count
+            );
+            transition_primary(&bus, DeviceState::Fault);
+            soft_reset(&bus).await;
+            recondition(&bus, power_cycle.recondition_secs, compensation).await;
+            if let Some(detector) = freeze_detector.as_mut() {
+                detector.reset();
+            }
+            power_cycle_detector.reset();
+            continue;
+        }
+
+        if let Some(cause) = power_cycle_detector.update(voc_raw, nox_raw) {
+            warn!("Sensor power cycle suspected: {}", cause);
+            match power_cycle.response {
+                PowerCycleResponse::LogOnly => {}
+                PowerCycleResponse::Recondition => {
+                    recondition(&bus, power_cycle.recondition_secs, compensation).await;
+                    continue;
+                }
+                PowerCycleResponse::ReconditionAndReset => {
+                    if voc_algo.reset().and(nox_algo.reset()).is_err() {
+                        warn!("Gas index algorithm busy; skipping reset");
+                    }
+                    recondition(&bus, power_cycle.recondition_secs, compensation).await;
+                    continue;
+                }
+            }
+        }
 
-        let voc_index = voc_algo.borrow_mut().process(voc_raw as i32);
-        let nox_index = nox_algo.borrow_mut().process(nox_raw as i32);
+        // Borrow both algorithms up front so a conflict skips the whole sample
+        // and VOC/NOx stay in step.
+        let result = match (voc_algo.try_borrow_mut(), nox_algo.try_borrow_mut()) {
+            (Ok(mut voc), Ok(mut nox)) => Some(process_raw(voc_raw, nox_raw, &mut *voc, &mut *nox)),
+            _ => None,
+        };
+        let Some(mut result) = result else {
+            warn!("Gas index algorithm busy; skipping sample");
+            Timer::after(interval).await;
+            continue;
+        };
+        result.voc_index = index_offset.apply_voc(result.voc_index);
+        result.nox_index = index_offset.apply_nox(result.nox_index);
+        result.humidity_comp_ticks = comp_ticks.map(|(humidity_ticks, _)| humidity_ticks);
+        result.temp_comp_ticks = comp_ticks.map(|(_, temp_ticks)| temp_ticks);
+        result.compensation = CompensationState::of(&compensation, comp_ticks);
+        result.quality = QualityFactors {
+            recent_crc_error: crc_since_last_sample,
+            warming_up: result.voc_index == 0,
+            compensation: result.compensation,
+            outlier,
+        }
+        .score();
+        crc_since_last_sample = false;
 
+        let MeasurementResult { voc_index, nox_index, .. } = result;
         info!("  VOC Index: {}", voc_index);
-        info!("  NOx Index: {}", nox_index);
-
-        let mut color = if voc_index > 155 {
-            [30, 0, 0]          // red
-        } else if voc_index > 114 {
-            [30, 10, 20]        // pink
-        } else if voc_index > 92 {
-            [30, 30, 0]         // yellow
+        if !voc_only_reporting() {
+            info!("  NOx Index: {}", nox_index);
+        }
+        info!("  Air quality: {}", voc_category(voc_index).label());
+        info!("  Data quality: {}/100", result.quality);
+        let baseline_delta = rolling_baseline
+            .as_mut()
+            .and_then(|b| b.update(voc_index, nox_index, Instant::now()));
+        if let Some(delta) = baseline_delta {
+            if voc_only_reporting() {
+                info!("  vs. rolling baseline: VOC {}", delta.voc);
+            } else {
+                info!("  vs. rolling baseline: VOC {} NOx {}", delta.voc, delta.nox);
+            }
+        }
+        if let Some((temp_c, humidity_pct)) = compensation.temp_humidity() {
+            info!("  Abs humidity: {} g/m³", absolute_humidity(temp_c, humidity_pct));
+        }
+        if let Some((humidity_ticks, temp_ticks)) = comp_ticks {
+            let (temp_c, humidity_pct) = ticks_to_temp_hum(humidity_ticks, temp_ticks);
+            debug!("  Compensation sent: {} °C, {} %RH", temp_c, humidity_pct);
+        }
+
+        record_measurement();
+        measurements += 1;
+        #[cfg(feature = "co2-crosscheck")]
+        if bus.is_primary() {
+            crate::crosscheck::record_voc_index(voc_index);
+        }
+        debug!("  Record checksum: 0x{:02X}", result.checksum());
+        if let Some(test) = soak.as_mut() {
+            test.update(&result);
+            if test.is_done() {
+                test.report();
+                soak = None;
+                update_primary_config(&bus, |c| c.soak_duration_s = None);
+            }
+        }
+
+        let published = smoother.apply(&result);
+        if let Some(reason) = reporter.should_publish(&published, Instant::now()) {
+            if voc_only_reporting() {
+                info!("Publish ({}): VOC raw={} index={}", reason, published.voc_raw, published.voc_index);
+            } else {
+                info!("Publish ({}): {}", reason, published);
+            }
+            let reading = Measurement {
+                baseline_delta,
+                ..Measurement::from_result(&published, bus.id, sampled_at.as_millis())
+            };
+            READINGS.immediate_publisher().publish_immediate(reading);
+        }
+
+        // Color from the averaged indices. Warm-up zeros would drag the
+        // average down, so they bypass it and restart it instead.
+        let (led_voc_index, led_nox_index) = if voc_index == 0 {
+            led_voc.reset();
+            led_nox.reset();
+            (voc_index, nox_index)
         } else {
-            // [0, 30, 0]          // green
-            [21, 27, 28]        // royal concerto , kinda green
+            (led_voc.push(voc_index), led_nox.push(nox_index))
         };
+        let command =
+            air_quality_command(led_voc_index, led_nox_index, !voc_only_reporting(), combined_alarm);
+
+        // Escalate when the index stays in the poor band for too long
+        let mut led_alarm = false;
+        if let Some(monitor) = escalation.as_mut() {
+            let action = monitor.rule().action;
+            match monitor.update(voc_index, nox_index, Instant::now()) {
+                Some(EscalationEvent::Fired) => {
+                    warn!("Sustained poor air quality: {}", monitor.rule());
+                    if action == EscalationAction::FanRelay {
+                        FAN_RELAY.signal(true);
+                    }
+                }
+                Some(EscalationEvent::Cleared) => {
+                    info!("Air quality recovered; escalation cleared");
+                    if action == EscalationAction::FanRelay {
+                        FAN_RELAY.signal(false);
+                    }
+                }
+                None => {}
+            }
+            led_alarm = monitor.is_active() && action == EscalationAction::LedAlarm;
+        }
 
-        // Override for NOx
-        if nox_index > 30 {
-            color = [30, 0, 30]; // magenta
+        if !warm_up_announced {
+            warm_up_announced = true;
+            led(LedCommand::Ready);
         }
 
         // Send blink command
-        _led_sender.send(LedCommand::Blink(color[0], color[1], color[2], None)).await;
-        Timer::after(Duration::from_secs(1)).await;
+        if led_alarm {
+            // One full ramp per sample; the next sample's command restarts it.
+            led(LedCommand::Pulse {
+                r: 30,
+                g: 0,
+                b: 0,
+                period_ms: interval.as_millis().min(u16::MAX as u64) as u16,
+            });
+        } else {
+            led(command);
+        }
+        if run_limit.reached(measurements, run_started) {
+            info!(
+                "Run complete: {} measurements in {} s, last {}, health {}",
+                measurements,
+                run_started.elapsed().as_secs(),
+                result,
+                health::snapshot()
+            );
+            turn_heater_off(&bus).await;
+            transition_primary(&bus, DeviceState::Idle);
+            RUN_COMPLETE.signal(measurements);
+            led(LedCommand::Solid(0, 0, 30));
+            return;
+        }
+
+        let delay = if align_to_wall_clock {
+            delay_to_boundary(interval)
+        } else {
+            interval
+        };
+        Timer::after(delay).await;
+    }
+}
+// Control commands go to the primary sensor only, so a command is never
+// consumed by a task it wasn't meant for.
+fn next_control(bus: &SensorBus) -> Option<ControlCommand> {
+    if bus.is_primary() {
+        CONTROL.try_receive().ok()
+    } else {
+        None
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,59 @@
+use defmt::{debug, warn};
+use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+use embassy_sync::mutex::Mutex;
+use embassy_time::{Duration, Timer};
+use embedded_hal_02::blocking::i2c::{Read, Write};
+
+use crate::compensation::update_live;
+use crate::decode_words;
+use crate::hal::I2cCompat;
+
+pub const SHT4X_ADDR: u8 = 0x44;
+
+// SHT4x commands (single byte, no parameters)
+pub const CMD_SHT4X_MEASURE_HIGH_PRECISION: [u8; 1] = [0xFD];
+
+// High-precision measurement takes up to 8.3 ms.
+const SHT4X_MEASURE_TIME: Duration = Duration::from_millis(10);
+
+/// Temperature (°C) from an SHT4x temperature word.
+pub fn ticks_to_celsius(ticks: u16) -> f32 {
+    -45.0 + 175.0 * (ticks as f32 / 65535.0)
+}
+
+/// Relative humidity (%) from an SHT4x humidity word. The sensor's transfer
+/// function spans -6..=119 %, so it is clamped to the physical range.
+pub fn ticks_to_humidity(ticks: u16) -> f32 {
+    (-6.0 + 125.0 * (ticks as f32 / 65535.0)).clamp(0.0, 100.0)
+}
+
+/// Feed live SGP41 compensation from an SHT4x on the shared bus.
+///
+/// Each reading goes to `compensation::update_live`, which both the
+/// conditioning and measurement tasks read through `CompensationMode::Live`.
+/// Until the first reading (or whenever readings stop for longer than
+/// `stale_after`) they send the datasheet defaults, i.e. 25 °C / 50 %RH.
+#[embassy_executor::task]
+pub async fn sht4x_task(bus: &'static Mutex<NoopRawMutex, I2cCompat<'static>>, period: Duration) {
+    loop {
+        match read_temp_humidity(bus).await {
+            Some((temp_c, humidity_pct)) => {
+                debug!("SHT4x: {} °C, {} %RH", temp_c, humidity_pct);
+                if update_live(temp_c, humidity_pct).is_err() {
+                    warn!("SHT4x reading out of range: {} °C, {} %RH", temp_c, humidity_pct);
+                }
+            }
+            None => warn!("SHT4x read failed"),
+        }
+        Timer::after(period).await;
+    }
+}
+
+/// One high-precision measurement as (°C, %RH), CRC-checked.
+async fn read_temp_humidity(bus: &Mutex<NoopRawMutex, I2cCompat<'static>>) -> Option<(f32, f32)> {
+    bus.lock().await.write(SHT4X_ADDR, &CMD_SHT4X_MEASURE_HIGH_PRECISION).ok()?;
+    Timer::after(SHT4X_MEASURE_TIME).await;
+    let mut buf = [0u8; 6];
+    bus.lock().await.read(SHT4X_ADDR, &mut buf).ok()?;
+    decode_words::<2>(&buf).map(|[temp, humidity]| (ticks_to_celsius(temp), ticks_to_humidity(humidity)))
+}
@@ -0,0 +1,53 @@
+use defmt::{info, warn};
+use embassy_net::dns::DnsQueryType;
+use embassy_net::udp::{PacketMetadata, UdpSocket};
+use embassy_net::{IpEndpoint, Stack};
+use embassy_time::{with_timeout, Duration, Timer};
+
+use crate::sntp::{request, unix_ms_from_reply, NTP_PORT, PACKET_LEN};
+use crate::wall_clock::set_unix_time_ms;
+
+// Re-sync often enough that the crystal's drift stays well under a second.
+const SYNC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Set the wall clock (`wall_clock::set_unix_time_ms`) from `server` once the
+/// network is up, then every `SYNC_INTERVAL`. Failures retry after
+/// `RETRY_INTERVAL`; until the first success everything that needs the wall
+/// clock keeps its boot-relative fallback.
+#[embassy_executor::task]
+pub async fn sntp_task(stack: Stack<'static>, server: &'static str) {
+    let mut rx_meta = [PacketMetadata::EMPTY; 1];
+    let mut rx = [0u8; PACKET_LEN];
+    let mut tx_meta = [PacketMetadata::EMPTY; 1];
+    let mut tx = [0u8; PACKET_LEN];
+    let mut socket = UdpSocket::new(stack, &mut rx_meta, &mut rx, &mut tx_meta, &mut tx);
+    if socket.bind(0).is_err() {
+        warn!("SNTP socket bind failed; wall clock stays unset");
+        return;
+    }
+
+    loop {
+        stack.wait_config_up().await;
+        match sync(stack, &socket, server).await {
+            Some(unix_ms) => {
+                set_unix_time_ms(unix_ms);
+                info!("Wall clock set from {}: {} ms", server, unix_ms);
+                Timer::after(SYNC_INTERVAL).await;
+            }
+            None => {
+                warn!("SNTP sync with {} failed; retrying in {} s", server, RETRY_INTERVAL.as_secs());
+                Timer::after(RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
+
+async fn sync(stack: Stack<'static>, socket: &UdpSocket<'_>, server: &str) -> Option<u64> {
+    let address = *stack.dns_query(server, DnsQueryType::A).await.ok()?.first()?;
+    socket.send_to(&request(), IpEndpoint::new(address, NTP_PORT)).await.ok()?;
+    let mut reply = [0u8; PACKET_LEN];
+    let (len, _) = with_timeout(REPLY_TIMEOUT, socket.recv_from(&mut reply)).await.ok()?.ok()?;
+    unix_ms_from_reply(&reply[..len])
+}
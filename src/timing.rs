@@ -0,0 +1,37 @@
+//! SGP41 command timing from the Sensirion SGP41 datasheet: the "max.
+//! duration" column of the I²C command table, plus the general call reset
+//! and conditioning notes. Each constant is the time the host must wait after
+//! sending a command before reading its response or sending the next one.
+//! Use these rather than literal delays.
+
+use embassy_time::Duration;
+
+/// `sgp41_execute_conditioning` (0x2612): 50 ms max.
+pub const CONDITIONING_TIME: Duration = Duration::from_millis(50);
+
+/// `sgp41_measure_raw_signals` (0x2619): 50 ms max.
+pub const MEASURE_RAW_TIME: Duration = Duration::from_millis(50);
+
+/// `sgp41_execute_self_test` (0x280E): 320 ms max.
+pub const SELF_TEST_TIME: Duration = Duration::from_millis(320);
+
+/// `sgp41_turn_heater_off` (0x3615): 1 ms max.
+pub const HEATER_OFF_TIME: Duration = Duration::from_millis(1);
+
+/// `sgp4x_get_serial_number` (0x3682): 1 ms max.
+pub const SERIAL_NUMBER_TIME: Duration = Duration::from_millis(1);
+
+/// `sgp4x_get_feature_set` (0x202F): 1 ms max.
+pub const FEATURE_SET_TIME: Duration = Duration::from_millis(1);
+
+/// General call reset (0x0006): the sensor is ready again within 1 ms.
+pub const SOFT_RESET_TIME: Duration = Duration::from_millis(1);
+
+/// Longest conditioning phase the datasheet allows after power-up (10 s);
+/// conditioning longer than this can damage the sensing layer.
+pub const MAX_CONDITIONING: Duration = Duration::from_secs(10);
+
+/// Default limit on the whole conditioning phase (skip check included)
+/// before it is aborted into `DeviceState::Fault`: three times the longest
+/// allowed conditioning, so only a hung sensor or bus ever reaches it.
+pub const CONDITIONING_TIMEOUT: Duration = Duration::from_secs(30);
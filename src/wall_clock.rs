@@ -0,0 +1,47 @@
+//! Optional wall-clock time, set by a time source (`tasks::sntp` on `sntp`
+//! builds).
+//!
+//! Stored as the Unix time (ms) at which the embassy clock read zero, so the
+//! current wall time is derived from the monotonic `Instant` without drift
+//! between updates. Until a time source calls `set_unix_time_ms`, everything
+//! here falls back to boot-relative timing.
+
+use core::cell::Cell;
+use critical_section::Mutex;
+use embassy_time::{Duration, Instant};
+
+static BOOT_UNIX_MS: Mutex<Cell<Option<u64>>> = Mutex::new(Cell::new(None));
+
+/// Record the current Unix time (ms) from a time source.
+pub fn set_unix_time_ms(unix_ms: u64) {
+    let boot = unix_ms.saturating_sub(Instant::now().as_millis());
+    critical_section::with(|cs| BOOT_UNIX_MS.borrow(cs).set(Some(boot)));
+}
+
+/// Current Unix time (ms), if a time source has been applied.
+pub fn unix_time_ms() -> Option<u64> {
+    let boot = critical_section::with(|cs| BOOT_UNIX_MS.borrow(cs).get())?;
+    Some(boot + Instant::now().as_millis())
+}
+
+/// Unix time (ms) of an earlier uptime reading (ms since boot, e.g.
+/// `readings::Measurement::timestamp_ms`), if a time source has been applied.
+pub fn unix_ms_at(uptime_ms: u64) -> Option<u64> {
+    let boot = critical_section::with(|cs| BOOT_UNIX_MS.borrow(cs).get())?;
+    Some(boot + uptime_ms)
+}
+
+/// Delay until the next wall-clock multiple of `interval` (e.g. the next whole
+/// second for a 1 s interval), so devices sharing a time source sample at
+/// roughly the same moment. Returns `interval` unchanged without a time source.
+///
+/// Alignment is only as good as the time source: with SNTP over Wi-Fi expect
+/// tens of milliseconds between devices, plus a few ms of executor and I²C
+/// jitter on each sample.
+pub fn delay_to_boundary(interval: Duration) -> Duration {
+    let interval_ms = interval.as_millis();
+    match unix_time_ms() {
+        Some(now) if interval_ms > 0 => Duration::from_millis(interval_ms - now % interval_ms),
+        _ => interval,
+    }
+}
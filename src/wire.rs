@@ -0,0 +1,102 @@
+//! Compact, versioned binary encoding of a reading for ESP-NOW, flash and
+//! USB-binary outputs. Every binary transport uses this one layout.
+//!
+//! Layout (16 bytes, multi-byte fields big-endian):
+//!
+//! | offset | size | field                                           |
+//! |--------|------|-------------------------------------------------|
+//! | 0      | 1    | version (high nibble) \| validity flags (low)   |
+//! | 1      | 2    | serial low word                                 |
+//! | 3      | 4    | timestamp (s)                                   |
+//! | 7      | 2    | VOC raw ticks                                   |
+//! | 9      | 2    | NOx raw ticks                                   |
+//! | 11     | 2    | VOC index                                       |
+//! | 13     | 2    | NOx index                                       |
+//! | 15     | 1    | CRC-8 over bytes 0..15 (SGP41 polynomial)       |
+
+use defmt::Format;
+
+use crate::calculate_crc;
+use crate::measurement::MeasurementResult;
+use crate::reporting::voc_only_reporting;
+
+pub const WIRE_VERSION: u8 = 1;
+pub const WIRE_LEN: usize = 16;
+
+// Validity flags (low nibble of byte 0)
+pub const FLAG_RAW_VALID: u8 = 0b0001;
+pub const FLAG_VOC_INDEX_VALID: u8 = 0b0010;
+pub const FLAG_NOX_INDEX_VALID: u8 = 0b0100;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub struct WireReading {
+    pub flags: u8,
+    pub serial_low: u16,
+    pub timestamp_s: u32,
+    pub voc_raw: u16,
+    pub nox_raw: u16,
+    pub voc_index: u16,
+    pub nox_index: u16,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Format)]
+pub enum WireError {
+    BadCrc,
+    UnsupportedVersion(u8),
+}
+
+impl WireReading {
+    /// Wrap a measurement with all validity flags set. In VOC-only reporting
+    /// mode the NOx fields are zeroed and `FLAG_NOX_INDEX_VALID` is cleared.
+    pub fn from_measurement(m: &MeasurementResult, serial_low: u16, timestamp_s: u32) -> Self {
+        let index = |i: i32| i.clamp(0, u16::MAX as i32) as u16;
+        let mut reading = Self {
+            flags: FLAG_RAW_VALID | FLAG_VOC_INDEX_VALID | FLAG_NOX_INDEX_VALID,
+            serial_low,
+            timestamp_s,
+            voc_raw: m.voc_raw,
+            nox_raw: m.nox_raw,
+            voc_index: index(m.voc_index),
+            nox_index: index(m.nox_index),
+        };
+        if voc_only_reporting() {
+            reading.flags &= !FLAG_NOX_INDEX_VALID;
+            reading.nox_raw = 0;
+            reading.nox_index = 0;
+        }
+        reading
+    }
+
+    pub fn encode(&self) -> [u8; WIRE_LEN] {
+        let mut out = [0u8; WIRE_LEN];
+        out[0] = (WIRE_VERSION << 4) | (self.flags & 0x0F);
+        out[1..3].copy_from_slice(&self.serial_low.to_be_bytes());
+        out[3..7].copy_from_slice(&self.timestamp_s.to_be_bytes());
+        out[7..9].copy_from_slice(&self.voc_raw.to_be_bytes());
+        out[9..11].copy_from_slice(&self.nox_raw.to_be_bytes());
+        out[11..13].copy_from_slice(&self.voc_index.to_be_bytes());
+        out[13..15].copy_from_slice(&self.nox_index.to_be_bytes());
+        out[15] = calculate_crc(&out[..15]);
+        out
+    }
+
+    pub fn decode(bytes: &[u8; WIRE_LEN]) -> Result<Self, WireError> {
+        if calculate_crc(&bytes[..15]) != bytes[15] {
+            return Err(WireError::BadCrc);
+        }
+        let version = bytes[0] >> 4;
+        if version != WIRE_VERSION {
+            return Err(WireError::UnsupportedVersion(version));
+        }
+        let word = |i: usize| u16::from_be_bytes([bytes[i], bytes[i + 1]]);
+        Ok(Self {
+            flags: bytes[0] & 0x0F,
+            serial_low: word(1),
+            timestamp_s: u32::from_be_bytes([bytes[3], bytes[4], bytes[5], bytes[6]]),
+            voc_raw: word(7),
+            nox_raw: word(9),
+            voc_index: word(11),
+            nox_index: word(13),
+        })
+    }
+}
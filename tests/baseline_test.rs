@@ -0,0 +1,70 @@
+//! Tests for the rolling baseline's bucket window and eviction.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::assert_eq;
+    use embassy_time::{Duration, Instant};
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::baseline::{BaselineDelta, RollingBaseline, BASELINE_BUCKETS};
+
+    // One bucket per second.
+    const WINDOW: Duration = Duration::from_secs(BASELINE_BUCKETS as u64);
+
+    fn at(s: u64) -> Instant {
+        Instant::from_secs(s)
+    }
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn no_delta_until_the_first_bucket_completes() {
+        let mut baseline = RollingBaseline::new(WINDOW, at(0));
+        assert_eq!(baseline.update(100, 1, at(0)), None);
+        assert_eq!(baseline.update(100, 1, Instant::from_millis(999)), None);
+        assert_eq!(baseline.baseline(), None);
+        assert_eq!(baseline.update(130, 4, at(1)), Some(BaselineDelta { voc: 30, nox: 3 }));
+    }
+
+    #[test]
+    fn warm_up_samples_are_skipped() {
+        let mut baseline = RollingBaseline::new(WINDOW, at(0));
+        assert_eq!(baseline.update(0, 0, at(0)), None);
+        // The warm-up sample left the first bucket empty, so it never completes.
+        assert_eq!(baseline.update(100, 1, at(1)), None);
+        assert_eq!(baseline.baseline(), None);
+    }
+
+    #[test]
+    fn oldest_buckets_are_evicted_after_a_full_window() {
+        let mut baseline = RollingBaseline::new(WINDOW, at(0));
+        let n = BASELINE_BUCKETS as u64;
+        // A full window at 100, then a step to 200.
+        for s in 0..n {
+            baseline.update(100, 1, at(s));
+        }
+        assert_eq!(baseline.update(200, 1, at(n)), Some(BaselineDelta { voc: 100, nox: 0 }));
+        // Half the window has moved on: 12 buckets at 100, 12 at 200.
+        for s in n + 1..n + n / 2 {
+            baseline.update(200, 1, at(s));
+        }
+        assert_eq!(baseline.update(200, 1, at(n + n / 2)), Some(BaselineDelta { voc: 50, nox: 0 }));
+        // Every 100 bucket evicted.
+        for s in n + n / 2 + 1..2 * n {
+            baseline.update(200, 1, at(s));
+        }
+        assert_eq!(baseline.update(200, 1, at(2 * n)), Some(BaselineDelta { voc: 0, nox: 0 }));
+        assert_eq!(baseline.baseline(), Some((200.0, 1.0)));
+    }
+}
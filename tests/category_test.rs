@@ -0,0 +1,39 @@
+//! Tests for the VOC index to air-quality category mapping.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::assert_eq;
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::category::{voc_category, AirQualityCategory};
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn warm_up_index_is_not_excellent() {
+        assert_eq!(voc_category(0), AirQualityCategory::WarmingUp);
+        assert_eq!(voc_category(0) as u8, 255);
+        assert_eq!(voc_category(1), AirQualityCategory::Excellent);
+    }
+
+    #[test]
+    fn band_edges() {
+        assert_eq!(voc_category(92), AirQualityCategory::Excellent);
+        assert_eq!(voc_category(93), AirQualityCategory::Good);
+        assert_eq!(voc_category(155), AirQualityCategory::Moderate);
+        assert_eq!(voc_category(156), AirQualityCategory::Poor);
+        assert_eq!(voc_category(400), AirQualityCategory::Unhealthy);
+        assert_eq!(voc_category(500), AirQualityCategory::Hazardous);
+    }
+}
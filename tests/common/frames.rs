@@ -0,0 +1,19 @@
+//! SGP41 response frames shared by the driver, mux and sampling tests. Each
+//! data word is followed by its CRC byte.
+
+/// VOC 0x757F, NOx 0x4559 with valid CRCs.
+pub const GOOD_FRAME: [u8; 6] = [0x75, 0x7F, 0x1B, 0x45, 0x59, 0x89];
+/// `GOOD_FRAME` with the VOC CRC byte corrupted (expected 0x1B).
+pub const BAD_VOC_CRC_FRAME: [u8; 6] = [0x75, 0x7F, 0x00, 0x45, 0x59, 0x89];
+/// `GOOD_FRAME` with the NOx CRC byte corrupted (expected 0x89).
+pub const BAD_NOX_CRC_FRAME: [u8; 6] = [0x75, 0x7F, 0x1B, 0x45, 0x59, 0x00];
+
+/// Serial 0000_0A3F_A3F2 with valid CRCs.
+pub const SERIAL_FRAME: [u8; 9] = [0x00, 0x00, 0x81, 0x0A, 0x3F, 0x84, 0xA3, 0xF2, 0xB3];
+/// `SERIAL_FRAME` with the middle word's CRC corrupted (expected 0x84).
+pub const BAD_SERIAL_FRAME: [u8; 9] = [0x00, 0x00, 0x81, 0x0A, 0x3F, 0x00, 0xA3, 0xF2, 0xB3];
+
+/// Self-test result 0xD400: both pixels passed.
+pub const SELF_TEST_PASSED_FRAME: [u8; 3] = [0xD4, 0x00, 0xC6];
+/// Self-test result 0xD402: NOx pixel failed.
+pub const SELF_TEST_NOX_FAILED_FRAME: [u8; 3] = [0xD4, 0x02, 0xA4];
@@ -0,0 +1,101 @@
+//! Scripted I²C bus implementing the embedded-hal 0.2 blocking traits and
+//! the `embedded-hal-async` I²C trait.
+
+use embedded_hal_02::blocking::i2c::{Read, Write, WriteRead};
+use embedded_hal_async::i2c::{ErrorKind, ErrorType, I2c, Operation};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, defmt::Format)]
+pub struct MockError;
+
+impl embedded_hal_async::i2c::Error for MockError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+// Longest frame the SGP41 is sent: 2 command bytes + 6 parameter bytes.
+const WRITE_MAX: usize = 8;
+
+/// Each `read` consumes the next scripted response: `Some(bytes)` fills the
+/// buffer, `None` fails the transfer. Writes always succeed; the last one is
+/// recorded with its address so tests can check the bytes on the wire.
+pub struct MockI2c<'a> {
+    reads: &'a [Option<&'a [u8]>],
+    next_read: usize,
+    writes: usize,
+    last_addr: u8,
+    last_write: [u8; WRITE_MAX],
+    last_write_len: usize,
+}
+
+impl<'a> MockI2c<'a> {
+    pub fn new(reads: &'a [Option<&'a [u8]>]) -> Self {
+        Self {
+            reads,
+            next_read: 0,
+            writes: 0,
+            last_addr: 0,
+            last_write: [0; WRITE_MAX],
+            last_write_len: 0,
+        }
+    }
+
+    pub fn reads_consumed(&self) -> usize {
+        self.next_read
+    }
+
+    pub fn writes(&self) -> usize {
+        self.writes
+    }
+
+    /// Address and bytes of the most recent write.
+    pub fn last_write(&self) -> (u8, &[u8]) {
+        (self.last_addr, &self.last_write[..self.last_write_len])
+    }
+}
+
+impl Read for MockI2c<'_> {
+    type Error = MockError;
+    fn read(&mut self, _addr: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        let response = self.reads.get(self.next_read).copied().flatten();
+        self.next_read += 1;
+        let data = response.ok_or(MockError)?;
+        buf.copy_from_slice(&data[..buf.len()]);
+        Ok(())
+    }
+}
+
+impl Write for MockI2c<'_> {
+    type Error = MockError;
+    fn write(&mut self, addr: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.writes += 1;
+        self.last_addr = addr;
+        self.last_write_len = bytes.len().min(WRITE_MAX);
+        self.last_write[..self.last_write_len].copy_from_slice(&bytes[..self.last_write_len]);
+        Ok(())
+    }
+}
+
+impl WriteRead for MockI2c<'_> {
+    type Error = MockError;
+    fn write_read(&mut self, addr: u8, bytes: &[u8], buf: &mut [u8]) -> Result<(), Self::Error> {
+        Write::write(self, addr, bytes)?;
+        Read::read(self, addr, buf)
+    }
+}
+
+impl ErrorType for MockI2c<'_> {
+    type Error = MockError;
+}
+
+impl I2c for MockI2c<'_> {
+    async fn transaction(&mut self, addr: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        for operation in operations {
+            match operation {
+                Operation::Read(buf) => Read::read(self, addr, buf)?,
+                Operation::Write(bytes) => Write::write(self, addr, bytes)?,
+            }
+        }
+        Ok(())
+    }
+}
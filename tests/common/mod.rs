@@ -0,0 +1,6 @@
+//! Shared test helpers. Each test binary uses only some of them.
+#![allow(dead_code)]
+
+pub mod frames;
+pub mod mock_i2c;
+pub mod trace_i2c;
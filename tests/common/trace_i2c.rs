@@ -0,0 +1,61 @@
+//! Replays a recorded I²C session.
+//!
+//! Trace format: one `TraceEntry` per recorded read, in capture order.
+//! `at_ms` is the capture time since the first command; `response` holds the
+//! exact bytes the sensor returned (data words with their CRCs), or `None`
+//! where the read failed on the bus (NACK, timeout). Writes are not recorded;
+//! they are accepted and counted so tests can check the command cadence.
+
+use embedded_hal_02::blocking::i2c::{Read, Write};
+
+use super::mock_i2c::MockError;
+
+#[derive(Copy, Clone)]
+pub struct TraceEntry {
+    pub at_ms: u32,
+    pub response: Option<&'static [u8]>,
+}
+
+pub struct TraceI2c {
+    trace: &'static [TraceEntry],
+    next: usize,
+    writes: usize,
+}
+
+impl TraceI2c {
+    pub fn new(trace: &'static [TraceEntry]) -> Self {
+        Self {
+            trace,
+            next: 0,
+            writes: 0,
+        }
+    }
+
+    /// Capture time of the entry the next read will replay, `None` once done.
+    pub fn next_at_ms(&self) -> Option<u32> {
+        self.trace.get(self.next).map(|e| e.at_ms)
+    }
+
+    pub fn writes(&self) -> usize {
+        self.writes
+    }
+}
+
+impl Read for TraceI2c {
+    type Error = MockError;
+    fn read(&mut self, _addr: u8, buf: &mut [u8]) -> Result<(), Self::Error> {
+        let entry = self.trace.get(self.next).ok_or(MockError)?;
+        self.next += 1;
+        let data = entry.response.ok_or(MockError)?;
+        buf.copy_from_slice(&data[..buf.len()]);
+        Ok(())
+    }
+}
+
+impl Write for TraceI2c {
+    type Error = MockError;
+    fn write(&mut self, _addr: u8, _bytes: &[u8]) -> Result<(), Self::Error> {
+        self.writes += 1;
+        Ok(())
+    }
+}
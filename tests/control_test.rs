@@ -0,0 +1,57 @@
+//! Tests for decoding BLE control characteristic writes.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::assert;
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::calibration::IndexOffset;
+    use esp_sgp41_voc_nox::control::{ControlCommand, CONTROL_BLE_LEN};
+    use esp_sgp41_voc_nox::escalation::Gas;
+
+    fn write(prefix: &[u8]) -> [u8; CONTROL_BLE_LEN] {
+        let mut bytes = [0u8; CONTROL_BLE_LEN];
+        bytes[..prefix.len()].copy_from_slice(prefix);
+        bytes
+    }
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn decodes_arguments() {
+        assert!(matches!(
+            ControlCommand::from_ble_bytes(&write(&[0x04, 30])),
+            Some(ControlCommand::CleanAirReset { recondition_secs: 30 })
+        ));
+        assert!(matches!(
+            ControlCommand::from_ble_bytes(&write(&[0x05, 0x10, 0x0e, 0, 0])),
+            Some(ControlCommand::StartSoak { duration_s: 3600 })
+        ));
+        assert!(matches!(
+            ControlCommand::from_ble_bytes(&write(&[0x03, 1, 1, 2, 3, 4, 5, 6, 7, 8])),
+            Some(ControlCommand::ImportAlgorithmState { gas: Gas::Nox, state: [1, 2, 3, 4, 5, 6, 7, 8] })
+        ));
+        assert!(matches!(
+            ControlCommand::from_ble_bytes(&write(&[0x07, 0xf6, 0xff, 5, 0])),
+            Some(ControlCommand::SetCalibration { offset: IndexOffset { voc: -10, nox: 5 } })
+        ));
+    }
+
+    #[test]
+    fn rejects_unknown_opcode_and_gas() {
+        assert!(ControlCommand::from_ble_bytes(&write(&[0x00])).is_none());
+        assert!(ControlCommand::from_ble_bytes(&write(&[0x08])).is_none());
+        assert!(ControlCommand::from_ble_bytes(&write(&[0x03, 2])).is_none());
+    }
+}
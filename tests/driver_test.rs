@@ -0,0 +1,201 @@
+//! Tests for the `Sgp41` driver command sequences against a mock I²C bus.
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use crate::common::frames::{
+        BAD_NOX_CRC_FRAME, BAD_SERIAL_FRAME, GOOD_FRAME, SELF_TEST_NOX_FAILED_FRAME,
+        SELF_TEST_PASSED_FRAME, SERIAL_FRAME,
+    };
+    use crate::common::mock_i2c::{MockError, MockI2c};
+    use defmt::{assert, assert_eq};
+    use embassy_time::{Duration, Instant};
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::driver::{FeatureSet, SelfTestResult, Sgp41, Sgp41Async, Sgp41Error};
+    use esp_sgp41_voc_nox::prepare_default_params;
+    use esp_sgp41_voc_nox::tasks::conditioning::{
+        CMD_EXECUTE_SELF_TEST, CMD_GET_SERIAL_NUMBER, CMD_MEASURE_RAW_SIGNALS, CMD_SOFT_RESET,
+        GENERAL_CALL_ADDR, SGP41_ADDR,
+    };
+    use esp_sgp41_voc_nox::timing::{CONDITIONING_TIME, MEASURE_RAW_TIME, SELF_TEST_TIME};
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    async fn measure_decodes_both_words() {
+        let reads = [Some(&GOOD_FRAME[..])];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+
+        let raw = sgp41.measure_raw_signals_with(prepare_default_params()).await;
+        assert_eq!(raw, Ok((0x757F, 0x4559)));
+        assert_eq!(sgp41.release().reads_consumed(), 1);
+    }
+
+    #[test]
+    async fn measure_sends_command_then_params() {
+        let reads = [Some(&GOOD_FRAME[..])];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+        let params = prepare_default_params();
+
+        let _ = sgp41.measure_raw_signals_with(params).await;
+        let i2c = sgp41.release();
+        let (addr, bytes) = i2c.last_write();
+        assert_eq!(i2c.writes(), 1);
+        assert_eq!(addr, SGP41_ADDR);
+        assert_eq!(bytes[..2], CMD_MEASURE_RAW_SIGNALS[..]);
+        assert_eq!(bytes[2..], params[..]);
+    }
+
+    #[test]
+    async fn bare_commands_send_two_bytes() {
+        let reads = [Some(&SERIAL_FRAME[..]), Some(&SELF_TEST_PASSED_FRAME[..])];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+
+        let _ = sgp41.read_serial_number().await;
+        let i2c = sgp41.release();
+        assert_eq!(i2c.last_write(), (SGP41_ADDR, &CMD_GET_SERIAL_NUMBER[..]));
+
+        let mut sgp41 = Sgp41::new(i2c);
+        let _ = sgp41.execute_self_test().await;
+        assert_eq!(sgp41.release().last_write(), (SGP41_ADDR, &CMD_EXECUTE_SELF_TEST[..]));
+    }
+
+    #[test]
+    async fn feature_set_decodes_type_and_version() {
+        // 0x1042: product type 1, firmware version 0x42; second frame has a bad CRC
+        let reads = [Some(&[0x10, 0x42, 0xB0][..]), Some(&[0x10, 0x42, 0x00][..])];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+
+        assert_eq!(
+            sgp41.get_feature_set().await,
+            Ok(FeatureSet { product_type: 1, firmware_version: 0x42 })
+        );
+        assert_eq!(
+            sgp41.get_feature_set().await,
+            Err(Sgp41Error::CrcMismatch {
+                expected: 0xB0,
+                got: 0x00
+            })
+        );
+    }
+
+    #[test]
+    async fn soft_reset_uses_general_call() {
+        let mut sgp41 = Sgp41::new(MockI2c::new(&[]));
+
+        assert_eq!(sgp41.soft_reset().await, Ok(()));
+        assert_eq!(sgp41.release().last_write(), (GENERAL_CALL_ADDR, &CMD_SOFT_RESET[..]));
+    }
+
+    #[test]
+    async fn measure_rejects_bad_crc() {
+        let reads = [Some(&BAD_NOX_CRC_FRAME[..])];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+
+        assert_eq!(
+            sgp41.measure_raw_signals(25.0, 50.0).await,
+            Err(Sgp41Error::CrcMismatch {
+                expected: 0x89,
+                got: 0x00
+            })
+        );
+    }
+
+    #[test]
+    async fn conditioning_reads_one_voc_word() {
+        // Only the first word of the frame is returned while conditioning.
+        let reads = [Some(&GOOD_FRAME[..3]), None];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+
+        assert_eq!(sgp41.execute_conditioning(prepare_default_params()).await, Ok(0x757F));
+        assert_eq!(
+            sgp41.execute_conditioning(prepare_default_params()).await,
+            Err(Sgp41Error::I2c(MockError))
+        );
+    }
+
+    #[test]
+    async fn serial_number_checks_every_word() {
+        let reads = [Some(&SERIAL_FRAME[..]), Some(&BAD_SERIAL_FRAME[..])];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+
+        assert_eq!(sgp41.read_serial_number().await, Ok([0x0000, 0x0A3F, 0xA3F2]));
+        assert_eq!(
+            sgp41.read_serial_number().await,
+            Err(Sgp41Error::CrcMismatch {
+                expected: 0x84,
+                got: 0x00
+            })
+        );
+    }
+
+    #[test]
+    async fn self_test_decodes_pixel_flags() {
+        let reads = [Some(&SELF_TEST_PASSED_FRAME[..]), Some(&SELF_TEST_NOX_FAILED_FRAME[..])];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+
+        let passed = sgp41.execute_self_test().await;
+        assert_eq!(passed, Ok(SelfTestResult { voc_ok: true, nox_ok: true }));
+
+        let nox_failed = sgp41.execute_self_test().await.unwrap();
+        assert_eq!(nox_failed, SelfTestResult { voc_ok: true, nox_ok: false });
+        assert_eq!(nox_failed.check::<MockError>(), Err(Sgp41Error::SelfTestFailed));
+    }
+
+    #[test]
+    async fn waits_datasheet_time_before_reading() {
+        // Datasheet max durations; reading earlier NACKs on some samples.
+        assert_eq!(MEASURE_RAW_TIME, Duration::from_millis(50));
+        assert_eq!(CONDITIONING_TIME, Duration::from_millis(50));
+        assert_eq!(SELF_TEST_TIME, Duration::from_millis(320));
+
+        let reads = [
+            Some(&GOOD_FRAME[..]),
+            Some(&GOOD_FRAME[..3]),
+            Some(&SELF_TEST_PASSED_FRAME[..]),
+        ];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+
+        let start = Instant::now();
+        let _ = sgp41.measure_raw_signals_with(prepare_default_params()).await;
+        assert!(start.elapsed() >= MEASURE_RAW_TIME);
+
+        let start = Instant::now();
+        let _ = sgp41.execute_conditioning(prepare_default_params()).await;
+        assert!(start.elapsed() >= CONDITIONING_TIME);
+
+        let start = Instant::now();
+        let _ = sgp41.execute_self_test().await;
+        assert!(start.elapsed() >= SELF_TEST_TIME);
+    }
+
+    #[test]
+    async fn async_driver_matches_blocking_decoding() {
+        let reads = [Some(&GOOD_FRAME[..]), Some(&BAD_SERIAL_FRAME[..])];
+        let mut sgp41 = Sgp41Async::new(MockI2c::new(&reads));
+
+        let raw = sgp41.measure_raw_signals_with(prepare_default_params()).await;
+        assert_eq!(raw, Ok((0x757F, 0x4559)));
+        assert_eq!(
+            sgp41.read_serial_number().await,
+            Err(Sgp41Error::CrcMismatch {
+                expected: 0x84,
+                got: 0x00
+            })
+        );
+        assert_eq!(sgp41.release().reads_consumed(), 2);
+    }
+}
@@ -0,0 +1,58 @@
+//! Tests for `filter::MovingAverage`.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::{assert, assert_eq};
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::filter::MovingAverage;
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn averages_partial_window() {
+        let mut avg = MovingAverage::<4>::new();
+        assert!(avg.average().is_none());
+        assert_eq!(avg.push(100), 100);
+        assert_eq!(avg.push(110), 105);
+        assert_eq!(avg.sample_count(), 2);
+    }
+
+    #[test]
+    fn oldest_sample_drops_out() {
+        let mut avg = MovingAverage::<3>::new();
+        for value in [90, 120, 150] {
+            avg.push(value);
+        }
+        assert_eq!(avg.average(), Some(120));
+        assert_eq!(avg.push(180), 150);
+        assert_eq!(avg.sample_count(), 3);
+    }
+
+    #[test]
+    fn rounds_to_nearest() {
+        let mut avg = MovingAverage::<2>::new();
+        avg.push(100);
+        assert_eq!(avg.push(101), 101);
+    }
+
+    #[test]
+    fn reset_empties_window() {
+        let mut avg = MovingAverage::<3>::new();
+        avg.push(200);
+        avg.reset();
+        assert_eq!(avg.sample_count(), 0);
+        assert_eq!(avg.push(50), 50);
+    }
+}
@@ -0,0 +1,67 @@
+//! Tests for the stuck-sensor detectors.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::assert_eq;
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::freeze::{FailureStreak, FreezeDetector};
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn freeze_flags_identical_readings_at_threshold() {
+        let mut detector = FreezeDetector::new(3);
+        assert_eq!(detector.update(30080, 17753), None);
+        assert_eq!(detector.update(30080, 17753), None);
+        assert_eq!(detector.update(30080, 17753), Some(3));
+        assert_eq!(detector.update(30080, 17753), Some(4));
+    }
+
+    #[test]
+    fn freeze_restarts_on_a_changed_reading() {
+        let mut detector = FreezeDetector::new(3);
+        detector.update(30080, 17753);
+        detector.update(30080, 17753);
+        // Only NOx moved
+        assert_eq!(detector.update(30080, 17754), None);
+        assert_eq!(detector.update(30080, 17754), None);
+        assert_eq!(detector.update(30080, 17754), Some(3));
+        detector.reset();
+        assert_eq!(detector.update(30080, 17754), None);
+    }
+
+    #[test]
+    fn failure_streak_fires_once_at_threshold() {
+        let mut streak = FailureStreak::new(3);
+        assert_eq!(streak.record_failure(), None);
+        assert_eq!(streak.record_failure(), None);
+        assert_eq!(streak.record_failure(), Some(3));
+        // Counting starts again, so recovery runs once per streak.
+        assert_eq!(streak.record_failure(), None);
+        assert_eq!(streak.record_failure(), None);
+        assert_eq!(streak.record_failure(), Some(3));
+    }
+
+    #[test]
+    fn failure_streak_resets_on_success() {
+        let mut streak = FailureStreak::new(3);
+        streak.record_failure();
+        streak.record_failure();
+        streak.record_success();
+        assert_eq!(streak.record_failure(), None);
+        assert_eq!(streak.record_failure(), None);
+        assert_eq!(streak.record_failure(), Some(3));
+    }
+}
@@ -0,0 +1,64 @@
+//! Tests for the psychrometric conversions in `humidity.rs`.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::assert;
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::humidity::{abs_humidity_to_rh, absolute_humidity, dewpoint_to_rh};
+
+    fn close(a: f32, b: f32, tolerance: f32) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn abs_humidity_reference_values() {
+        // 20 °C / 50 %RH holds about 8.62 g/m³
+        assert!(close(abs_humidity_to_rh(8.62, 20.0), 50.0, 0.5));
+        // 25 °C with 8 g/m³ is about 34.8 %RH
+        assert!(close(abs_humidity_to_rh(8.0, 25.0), 34.8, 0.5));
+    }
+
+    #[test]
+    fn dewpoint_reference_values() {
+        // Dew point equal to air temperature means saturation
+        assert!(close(dewpoint_to_rh(25.0, 25.0), 100.0, 0.01));
+        // 20 °C air with a 9.26 °C dew point is 50 %RH
+        assert!(close(dewpoint_to_rh(9.26, 20.0), 50.0, 0.5));
+    }
+
+    #[test]
+    fn absolute_humidity_reference_values() {
+        // 20 °C / 50 %RH holds about 8.62 g/m³
+        assert!(close(absolute_humidity(20.0, 50.0), 8.62, 0.05));
+        // Saturated air at 0 °C holds about 4.85 g/m³
+        assert!(close(absolute_humidity(0.0, 100.0), 4.85, 0.05));
+        // 30 °C / 80 %RH is about 24.2 g/m³
+        assert!(close(absolute_humidity(30.0, 80.0), 24.2, 0.2));
+    }
+
+    #[test]
+    fn absolute_humidity_round_trips() {
+        let ah = absolute_humidity(23.0, 42.0);
+        assert!(close(abs_humidity_to_rh(ah, 23.0), 42.0, 0.01));
+    }
+
+    #[test]
+    fn results_are_clamped() {
+        assert!(dewpoint_to_rh(30.0, 20.0) <= 100.0);
+        assert!(abs_humidity_to_rh(-1.0, 20.0) >= 0.0);
+    }
+}
@@ -0,0 +1,93 @@
+//! Tests for the InfluxDB line-protocol output.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::assert_eq;
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::compensation::CompensationState;
+    use esp_sgp41_voc_nox::influx::{InfluxConfig, Precision, LINE_MAX};
+    use esp_sgp41_voc_nox::readings::Measurement;
+    use esp_sgp41_voc_nox::reporting::set_voc_only_reporting;
+
+    const SAMPLE: Measurement = Measurement {
+        sensor_id: 0,
+        voc_raw: 30079,
+        nox_raw: 17753,
+        voc_index: 100,
+        nox_index: 1,
+        quality: 100,
+        compensation: CompensationState::Compensated,
+        humidity_comp_ticks: None,
+        temp_comp_ticks: None,
+        timestamp_ms: 5000,
+        baseline_delta: None,
+    };
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn line_with_serial_tag_and_timestamp() {
+        set_voc_only_reporting(false);
+        let mut buf = [0u8; LINE_MAX];
+        let line = InfluxConfig::default().format_line(
+            Some([0x0000, 0x0A3F, 0xA3F2]),
+            &SAMPLE,
+            Some(1_700_000_000_000),
+            &mut buf,
+        );
+        assert_eq!(
+            line,
+            Some(
+                &b"sgp41,serial=00000A3FA3F2 voc_index=100i,voc_raw=30079i,nox_index=1i,nox_raw=17753i,\
+                   compensation=\"compensated\" 1700000000000\n"[..]
+            )
+        );
+    }
+
+    #[test]
+    fn line_without_tag_or_timestamp() {
+        set_voc_only_reporting(false);
+        let config = InfluxConfig {
+            precision: Precision::Seconds,
+            ..InfluxConfig::default()
+        };
+        let mut buf = [0u8; LINE_MAX];
+        let line = config.format_line(None, &SAMPLE, None, &mut buf);
+        assert_eq!(
+            line,
+            Some(&b"sgp41 voc_index=100i,voc_raw=30079i,nox_index=1i,nox_raw=17753i,compensation=\"compensated\"\n"[..])
+        );
+    }
+
+    #[test]
+    fn precision_scales_the_timestamp() {
+        set_voc_only_reporting(false);
+        let config = InfluxConfig {
+            precision: Precision::Seconds,
+            ..InfluxConfig::default()
+        };
+        let mut buf = [0u8; LINE_MAX];
+        let line = config.format_line(None, &SAMPLE, Some(1_700_000_000_999), &mut buf).unwrap();
+        assert_eq!(&line[line.len() - 12..], b" 1700000000\n");
+    }
+
+    #[test]
+    fn endpoint_needs_ip_and_port() {
+        let with_host = |host| InfluxConfig { host, ..InfluxConfig::default() };
+        assert_eq!(with_host("192.168.1.10:8089").endpoint(), Some(([192, 168, 1, 10], 8089)));
+        assert_eq!(with_host("influx.local:8089").endpoint(), None);
+        assert_eq!(with_host("192.168.1.10").endpoint(), None);
+    }
+}
@@ -0,0 +1,236 @@
+//! LED animation tests: frames are stepped with a fake clock, so the exact
+//! color sequence is checked without waiting in real time.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::{assert, assert_eq};
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::led::{
+        air_quality_command, gamma, gamma_color, nox_index_color, voc_index_color, CombinedAlarm,
+        ConditioningAnimation, ConnectionStatus, Frames, LedCommand, StatusLedConfig, GAMMA8,
+        NOX_ALARM_COLOR, VOC_ALARM_COLOR, VOC_ELEVATED_COLOR, VOC_GOOD_COLOR, VOC_MODERATE_COLOR,
+    };
+
+    /// Deterministic clock: only moves when a frame's hold time elapses.
+    struct FakeClock {
+        now_ms: u32,
+    }
+
+    /// Play `frames` on a fake clock, recording `(time, color)` for the first
+    /// `N` frames. Returns how many frames were produced (at most `N`).
+    fn play<const N: usize>(frames: Frames, out: &mut [(u32, (u8, u8, u8)); N]) -> usize {
+        let mut clock = FakeClock { now_ms: 0 };
+        let mut count = 0;
+        for frame in frames.take(N) {
+            out[count] = (clock.now_ms, frame.color);
+            count += 1;
+            match frame.hold_ms {
+                Some(ms) => clock.now_ms += ms,
+                None => break,
+            }
+        }
+        count
+    }
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn blink_goes_dark_then_shows_color() {
+        let config = StatusLedConfig::default();
+        let mut out = [(0, (0, 0, 0)); 4];
+        let n = play(LedCommand::Blink(30, 0, 0, Some(100)).frames(&config, (0, 0, 0)), &mut out);
+
+        assert_eq!(n, 2);
+        assert_eq!(out[..n], [(0, (0, 0, 0)), (100, (30, 0, 0))]);
+    }
+
+    #[test]
+    fn blink_n_toggles_count_times_then_restores() {
+        let config = StatusLedConfig::default();
+        let mut out = [(0, (0, 0, 0)); 10];
+        let frames = LedCommand::blink_n((30, 0, 0), 200, 3).frames(&config, (21, 27, 28));
+        let n = play(frames, &mut out);
+
+        assert_eq!(n, 7);
+        assert_eq!(
+            out[..n],
+            [
+                (0, (30, 0, 0)),
+                (100, (0, 0, 0)),
+                (200, (30, 0, 0)),
+                (300, (0, 0, 0)),
+                (400, (30, 0, 0)),
+                (500, (0, 0, 0)),
+                (600, (21, 27, 28)),
+            ]
+        );
+    }
+
+    #[test]
+    fn connection_blip_restores_current_color() {
+        let config = StatusLedConfig::default();
+        let mut out = [(0, (0, 0, 0)); 4];
+        let frames = LedCommand::Connection(ConnectionStatus::Connected).frames(&config, (21, 27, 28));
+        let n = play(frames, &mut out);
+
+        assert_eq!(n, 2);
+        assert_eq!(out[..n], [(0, config.connected), (150, (21, 27, 28))]);
+    }
+
+    #[test]
+    fn breathe_ramps_up_and_down() {
+        let config = StatusLedConfig::default();
+        let animation = ConditioningAnimation::Breathe {
+            color: (30, 30, 30),
+            period_ms: 100,
+        };
+        let mut out = [(0, (0, 0, 0)); 6];
+        let n = play(LedCommand::Conditioning(animation).frames(&config, (0, 0, 0)), &mut out);
+
+        assert_eq!(n, 6);
+        assert_eq!(
+            out,
+            [
+                (0, (0, 0, 0)),
+                (20, (12, 12, 12)),
+                (40, (24, 24, 24)),
+                (60, (24, 24, 24)),
+                (80, (12, 12, 12)),
+                (100, (0, 0, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn pulse_ramps_like_breathe_and_keeps_going() {
+        let config = StatusLedConfig::default();
+        let pulse = LedCommand::Pulse {
+            r: 30,
+            g: 0,
+            b: 30,
+            period_ms: 100,
+        };
+        let mut out = [(0, (0, 0, 0)); 8];
+        let n = play(pulse.frames(&config, (0, 0, 0)), &mut out);
+
+        assert_eq!(n, 8);
+        assert_eq!(out[1], (20, (12, 0, 12)));
+        assert_eq!(out[5], (100, (0, 0, 0)));
+        // Second period starts over
+        assert_eq!(out[6], (120, (12, 0, 12)));
+    }
+
+    #[test]
+    fn default_conditioning_alternates_every_half_period() {
+        let config = StatusLedConfig::default();
+        let mut out = [(0, (0, 0, 0)); 3];
+        let frames = LedCommand::Conditioning(ConditioningAnimation::default()).frames(&config, (0, 0, 0));
+        let n = play(frames, &mut out);
+
+        assert_eq!(n, 3);
+        assert_eq!(out, [(0, (30, 0, 0)), (500, (30, 0, 30)), (1000, (30, 0, 0))]);
+    }
+
+    #[test]
+    fn combined_alarm_alternates_voc_and_nox_colors() {
+        let config = StatusLedConfig::default();
+        let mut out = [(0, (0, 0, 0)); 3];
+        let command = air_quality_command(200, 100, true, CombinedAlarm::Alternate { period_ms: 1000 });
+        let n = play(command.frames(&config, (0, 0, 0)), &mut out);
+
+        assert!(matches!(command, LedCommand::Alternate { .. }));
+        assert_eq!(n, 3);
+        assert_eq!(out, [(0, VOC_ALARM_COLOR), (500, NOX_ALARM_COLOR), (1000, VOC_ALARM_COLOR)]);
+    }
+
+    #[test]
+    fn ready_flash_plays_once_and_ends() {
+        let config = StatusLedConfig::default();
+        let mut out = [(0, (0, 0, 0)); 6];
+        let n = play(LedCommand::Ready.frames(&config, (0, 0, 0)), &mut out);
+
+        assert_eq!(n, 4);
+        assert_eq!(
+            out[..n],
+            [(0, (0, 30, 0)), (150, (0, 0, 0)), (300, (0, 30, 0)), (450, (0, 0, 0))]
+        );
+    }
+
+    #[test]
+    fn ready_flash_can_be_disabled() {
+        let config = StatusLedConfig {
+            ready_flash: None,
+            ..StatusLedConfig::default()
+        };
+        let mut out = [(0, (0, 0, 0)); 2];
+        assert_eq!(play(LedCommand::Ready.frames(&config, (0, 0, 0)), &mut out), 0);
+    }
+
+    #[test]
+    fn voc_color_ladder_thresholds() {
+        assert_eq!(voc_index_color(0), VOC_GOOD_COLOR);
+        assert_eq!(voc_index_color(92), VOC_GOOD_COLOR);
+        assert_eq!(voc_index_color(93), VOC_MODERATE_COLOR);
+        assert_eq!(voc_index_color(115), VOC_ELEVATED_COLOR);
+        assert_eq!(voc_index_color(155), VOC_ELEVATED_COLOR);
+        assert_eq!(voc_index_color(156), VOC_ALARM_COLOR);
+    }
+
+    #[test]
+    fn nox_color_only_above_alarm_threshold() {
+        assert_eq!(nox_index_color(30), None);
+        assert_eq!(nox_index_color(31), Some(NOX_ALARM_COLOR));
+    }
+
+    #[test]
+    fn gamma_table_is_monotonic_and_keeps_endpoints() {
+        assert_eq!(gamma(0), 0);
+        assert_eq!(gamma(255), 255);
+        assert_eq!(gamma(128), 37);
+        assert!(GAMMA8.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn gamma_keeps_voc_colors_distinct_and_lit() {
+        let colors = [
+            gamma_color(VOC_GOOD_COLOR),
+            gamma_color(VOC_MODERATE_COLOR),
+            gamma_color(VOC_ELEVATED_COLOR),
+            gamma_color(VOC_ALARM_COLOR),
+        ];
+        for (i, color) in colors.iter().enumerate() {
+            assert!(*color != (0, 0, 0));
+            for other in &colors[i + 1..] {
+                assert!(color != other);
+            }
+        }
+    }
+
+    #[test]
+    fn gamma_keeps_every_lit_channel_of_the_status_colors() {
+        let config = StatusLedConfig::default();
+        let colors = [
+            config.initializing,
+            config.connecting,
+            config.connected,
+            config.disconnected,
+            config.ready_flash.map_or((0, 0, 0), |flash| flash.color),
+        ];
+        for (r, g, b) in colors {
+            let (gr, gg, gb) = gamma_color((r, g, b));
+            assert_eq!((gr == 0, gg == 0, gb == 0), (r == 0, g == 0, b == 0));
+        }
+    }
+}
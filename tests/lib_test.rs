@@ -0,0 +1,134 @@
+//! Tests for the SGP41 frame helpers in `lib.rs`.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::{assert, assert_eq};
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_hal::time::Instant;
+    use esp_sgp41_voc_nox::{
+        calculate_crc, calculate_crc_table, check_word, prepare_default_params, prepare_temp_hum_params,
+        prepare_temp_hum_params_checked, temp_hum_ticks, ticks_to_temp_hum, verify_crc, ParamError,
+        CRC_TABLE,
+    };
+
+    // Serial and measurement frames as the sensor sends them, CRC bytes included
+    const CRC_VECTORS: &[&[u8]] = &[
+        &[],
+        &[0xBE, 0xEF],
+        &[0x80, 0x00, 0xA2, 0x66, 0x66, 0x93],
+        &[0x00, 0x00, 0x81, 0x0A, 0x3F, 0x84, 0xA3, 0xF2, 0xB3],
+        &[0xFF; 9],
+    ];
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn crc_matches_datasheet_example() {
+        assert_eq!(calculate_crc(&[0xBE, 0xEF]), 0x92);
+    }
+
+    #[test]
+    fn crc_table_matches_bitwise_for_every_byte() {
+        for byte in 0..=255u8 {
+            assert_eq!(calculate_crc_table(&[byte]), calculate_crc(&[byte]));
+            // Each entry is the bitwise CRC of its index with the 0xFF init undone
+            assert_eq!(CRC_TABLE[byte as usize], calculate_crc(&[byte ^ 0xFF]));
+        }
+    }
+
+    #[test]
+    fn crc_table_matches_bitwise_for_frames() {
+        for data in CRC_VECTORS {
+            assert_eq!(calculate_crc_table(data), calculate_crc(data));
+        }
+        assert_eq!(calculate_crc_table(&[0xBE, 0xEF]), 0x92);
+    }
+
+    #[test]
+    fn crc_table_timing() {
+        const ROUNDS: u32 = 1000;
+        let frame = CRC_VECTORS[3];
+
+        let start = Instant::now();
+        let mut bitwise = 0u8;
+        for _ in 0..ROUNDS {
+            bitwise ^= calculate_crc(core::hint::black_box(frame));
+        }
+        let bitwise_us = start.elapsed().as_micros();
+
+        let start = Instant::now();
+        let mut table = 0u8;
+        for _ in 0..ROUNDS {
+            table ^= calculate_crc_table(core::hint::black_box(frame));
+        }
+        let table_us = start.elapsed().as_micros();
+
+        defmt::info!("{} 9-byte CRCs: bitwise {} us, table {} us", ROUNDS, bitwise_us, table_us);
+        assert_eq!(bitwise, table);
+    }
+
+    #[test]
+    fn verify_crc_accepts_only_the_matching_byte() {
+        assert!(verify_crc(&[0xBE, 0xEF], 0x92));
+        assert!(!verify_crc(&[0xBE, 0xEF], 0x93));
+    }
+
+    #[test]
+    fn check_word_rejects_corrupted_frames() {
+        assert_eq!(check_word(&[0xBE, 0xEF, 0x92]), Some(0xBEEF));
+        assert_eq!(check_word(&[0xBE, 0xEE, 0x92]), None);
+    }
+
+    #[test]
+    fn default_params_match_datasheet() {
+        // Datasheet: humidity 0x8000 (CRC 0xA2), temperature 0x6666 (CRC 0x93)
+        assert_eq!(
+            prepare_default_params(),
+            [0x80, 0x00, 0xA2, 0x66, 0x66, 0x93]
+        );
+    }
+
+    #[test]
+    fn checked_params_reject_invalid_inputs() {
+        assert_eq!(prepare_temp_hum_params_checked(f32::NAN, 50.0), Err(ParamError::NotFinite));
+        assert_eq!(prepare_temp_hum_params_checked(25.0, f32::INFINITY), Err(ParamError::NotFinite));
+        assert_eq!(
+            prepare_temp_hum_params_checked(-50.0, 50.0),
+            Err(ParamError::TemperatureOutOfRange)
+        );
+        assert_eq!(
+            prepare_temp_hum_params_checked(25.0, 150.0),
+            Err(ParamError::HumidityOutOfRange)
+        );
+        assert_eq!(
+            prepare_temp_hum_params_checked(25.0, 50.0),
+            Ok(prepare_temp_hum_params(25.0, 50.0))
+        );
+    }
+
+    #[test]
+    fn ticks_round_trip_within_one_tick() {
+        let (humidity_ticks, temp_ticks) = temp_hum_ticks(25.0, 50.0);
+        let (temp_c, humidity_pct) = ticks_to_temp_hum(humidity_ticks, temp_ticks);
+        // One tick is 175/65535 °C and 100/65535 %.
+        assert!((temp_c - 25.0).abs() <= 175.0 / 65535.0);
+        assert!((humidity_pct - 50.0).abs() <= 100.0 / 65535.0);
+    }
+
+    #[test]
+    fn unchecked_params_clamp_to_range() {
+        assert_eq!(prepare_temp_hum_params(-50.0, 150.0), prepare_temp_hum_params(-45.0, 100.0));
+    }
+}
@@ -0,0 +1,52 @@
+//! Tests for the serialized `MeasurementResult` record format.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::{assert, assert_eq};
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::compensation::CompensationState;
+    use esp_sgp41_voc_nox::measurement::{MeasurementResult, RECORD_LEN};
+
+    const SAMPLE: MeasurementResult = MeasurementResult {
+        voc_raw: 30079,
+        nox_raw: 17753,
+        voc_index: 100,
+        nox_index: 1,
+        humidity_comp_ticks: None,
+        temp_comp_ticks: None,
+        quality: 0,
+        compensation: CompensationState::Default,
+    };
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn record_round_trips() {
+        let record = SAMPLE.to_record();
+        assert_eq!(MeasurementResult::from_record(&record), Some(SAMPLE));
+    }
+
+    #[test]
+    fn single_bit_flip_is_detected() {
+        let record = SAMPLE.to_record();
+        for byte in 0..RECORD_LEN {
+            for bit in 0..8 {
+                let mut corrupted = record;
+                corrupted[byte] ^= 1 << bit;
+                assert!(MeasurementResult::from_record(&corrupted).is_none());
+            }
+        }
+    }
+}
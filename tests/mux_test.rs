@@ -0,0 +1,61 @@
+//! Tests for routing driver commands through a TCA9548A mux channel.
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use crate::common::frames::GOOD_FRAME;
+    use crate::common::mock_i2c::MockI2c;
+    use defmt::assert_eq;
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::driver::Sgp41;
+    use esp_sgp41_voc_nox::mux::{MuxChannel, Muxed, TCA9548A_ADDR};
+    use esp_sgp41_voc_nox::prepare_default_params;
+    use esp_sgp41_voc_nox::tasks::conditioning::{CMD_TURN_HEATER_OFF, SGP41_ADDR};
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn select_enables_one_channel() {
+        let mut i2c = MockI2c::new(&[]);
+        assert_eq!(MuxChannel::tca9548a(5).select(&mut i2c), Ok(()));
+        assert_eq!(i2c.last_write(), (TCA9548A_ADDR, &[0x20][..]));
+    }
+
+    #[test]
+    async fn every_transaction_selects_the_channel_first() {
+        let reads = [Some(&GOOD_FRAME[..])];
+        let mut i2c = MockI2c::new(&reads);
+        let mut sgp41 = Sgp41::new(Muxed::new(&mut i2c, Some(MuxChannel::tca9548a(1))));
+
+        let raw = sgp41.measure_raw_signals_with(prepare_default_params()).await;
+        assert_eq!(raw, Ok((0x757F, 0x4559)));
+        drop(sgp41);
+        // Select, command, select again before the read.
+        assert_eq!(i2c.writes(), 3);
+        assert_eq!(i2c.last_write(), (TCA9548A_ADDR, &[0x02][..]));
+    }
+
+    #[test]
+    async fn no_mux_passes_straight_through() {
+        let mut i2c = MockI2c::new(&[]);
+        let mut sgp41 = Sgp41::new(Muxed::new(&mut i2c, None));
+
+        assert_eq!(sgp41.turn_heater_off().await, Ok(()));
+        drop(sgp41);
+        assert_eq!(i2c.writes(), 1);
+        assert_eq!(i2c.last_write(), (SGP41_ADDR, &CMD_TURN_HEATER_OFF[..]));
+    }
+}
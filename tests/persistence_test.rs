@@ -0,0 +1,81 @@
+//! Tests for the saved algorithm state and calibration records.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::{assert, assert_eq};
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::calibration::IndexOffset;
+    use esp_sgp41_voc_nox::persistence::{
+        AlgorithmSnapshot, CalibrationRecord, CALIBRATION_RECORD_LEN, SNAPSHOT_RECORD_LEN,
+    };
+
+    const SAMPLE: AlgorithmSnapshot = AlgorithmSnapshot {
+        voc: [0x42, 0xC8, 0x00, 0x00, 0x42, 0x48, 0x00, 0x00],
+        nox: [0x3F, 0x80, 0x00, 0x00, 0x41, 0x20, 0x00, 0x00],
+    };
+
+    const CALIBRATION: CalibrationRecord = CalibrationRecord {
+        serial: [0x0000, 0x0345, 0x9A2B],
+        offset: IndexOffset { voc: -12, nox: 3 },
+    };
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn record_round_trips() {
+        let record = SAMPLE.to_record();
+        assert_eq!(AlgorithmSnapshot::from_record(&record), Some(SAMPLE));
+    }
+
+    #[test]
+    fn erased_flash_is_rejected() {
+        assert!(AlgorithmSnapshot::from_record(&[0xFF; SNAPSHOT_RECORD_LEN]).is_none());
+    }
+
+    #[test]
+    fn corrupted_record_is_rejected() {
+        let mut record = SAMPLE.to_record();
+        record[7] ^= 0x01;
+        assert!(AlgorithmSnapshot::from_record(&record).is_none());
+    }
+
+    #[test]
+    fn other_version_is_rejected() {
+        let mut record = SAMPLE.to_record();
+        record[4] = record[4].wrapping_add(1);
+        assert!(AlgorithmSnapshot::from_record(&record).is_none());
+    }
+
+    #[test]
+    fn calibration_record_round_trips() {
+        let record = CALIBRATION.to_record();
+        assert_eq!(CalibrationRecord::from_record(&record), Some(CALIBRATION));
+        assert!(CalibrationRecord::from_record(&[0xFF; CALIBRATION_RECORD_LEN]).is_none());
+    }
+
+    #[test]
+    fn calibration_applies_only_to_its_sensor() {
+        assert_eq!(CALIBRATION.offset_for(Some(CALIBRATION.serial)), Some(CALIBRATION.offset));
+        assert_eq!(CALIBRATION.offset_for(Some([0x0000, 0x0345, 0x9A2C])), None);
+        assert_eq!(CALIBRATION.offset_for(None), None);
+    }
+
+    #[test]
+    fn corrupted_calibration_record_is_rejected() {
+        let mut record = CALIBRATION.to_record();
+        record[11] ^= 0x01;
+        assert!(CalibrationRecord::from_record(&record).is_none());
+    }
+}
@@ -0,0 +1,82 @@
+//! Tests for the Prometheus text format of a reading.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::{assert, assert_eq};
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::compensation::CompensationState;
+    use esp_sgp41_voc_nox::prometheus::{format_metrics, METRICS_MAX};
+    use esp_sgp41_voc_nox::readings::Measurement;
+
+    const SAMPLE: Measurement = Measurement {
+        sensor_id: 0,
+        voc_raw: 30079,
+        nox_raw: 17753,
+        voc_index: 100,
+        nox_index: 1,
+        quality: 100,
+        compensation: CompensationState::Default,
+        humidity_comp_ticks: None,
+        temp_comp_ticks: None,
+        timestamp_ms: 5000,
+        baseline_delta: None,
+    };
+    const SERIAL: [u16; 3] = [0x0000, 0x0A3F, 0xA3F2];
+
+    const EXPECTED: &str = "\
+# HELP sgp41_voc_index VOC index (1..500, 100 = typical air).
+# TYPE sgp41_voc_index gauge
+sgp41_voc_index{serial=\"00000A3FA3F2\"} 100
+# HELP sgp41_voc_raw Raw VOC signal (ticks).
+# TYPE sgp41_voc_raw gauge
+sgp41_voc_raw{serial=\"00000A3FA3F2\"} 30079
+# HELP sgp41_nox_index NOx index (1..500, 1 = typical air).
+# TYPE sgp41_nox_index gauge
+sgp41_nox_index{serial=\"00000A3FA3F2\"} 1
+# HELP sgp41_nox_raw Raw NOx signal (ticks).
+# TYPE sgp41_nox_raw gauge
+sgp41_nox_raw{serial=\"00000A3FA3F2\"} 17753
+";
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn renders_four_labelled_gauges() {
+        let mut buf = [0u8; METRICS_MAX];
+        let text = format_metrics(Some(SERIAL), &SAMPLE, &mut buf).unwrap();
+        assert_eq!(text, EXPECTED.as_bytes());
+    }
+
+    #[test]
+    fn unknown_serial_drops_the_label() {
+        let mut buf = [0u8; METRICS_MAX];
+        let text = format_metrics(None, &SAMPLE, &mut buf).unwrap();
+        let first_sample = text.split(|&b| b == b'\n').nth(2).unwrap();
+        assert_eq!(first_sample, b"sgp41_voc_index 100");
+    }
+
+    #[test]
+    fn widest_values_fit() {
+        let widest = Measurement {
+            voc_raw: u16::MAX,
+            nox_raw: u16::MAX,
+            voc_index: i32::MIN,
+            nox_index: i32::MIN,
+            ..SAMPLE
+        };
+        let mut buf = [0u8; METRICS_MAX];
+        assert!(format_metrics(Some(SERIAL), &widest, &mut buf).is_some());
+    }
+}
@@ -0,0 +1,80 @@
+//! Tests for the per-reading data-quality score in `quality.rs`.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::{assert, assert_eq};
+    use embassy_time::Duration;
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::compensation::{CompensationMode, CompensationState};
+    use esp_sgp41_voc_nox::quality::{is_outlier, QualityFactors};
+
+    const CLEAN: QualityFactors = QualityFactors {
+        recent_crc_error: false,
+        warming_up: false,
+        compensation: CompensationState::Compensated,
+        outlier: false,
+    };
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn clean_fresh_reading_scores_full() {
+        assert_eq!(CLEAN.score(), 100);
+    }
+
+    #[test]
+    fn warm_up_with_stale_compensation_scores_low() {
+        let factors = QualityFactors {
+            warming_up: true,
+            compensation: CompensationState::Stale,
+            ..CLEAN
+        };
+        assert_eq!(factors.score(), 25);
+    }
+
+    #[test]
+    fn every_penalty_saturates_at_zero() {
+        let factors = QualityFactors {
+            recent_crc_error: true,
+            warming_up: true,
+            compensation: CompensationState::Stale,
+            outlier: true,
+        };
+        assert_eq!(factors.score(), 0);
+    }
+
+    #[test]
+    fn compensation_state_follows_mode_and_ticks_sent() {
+        let live = CompensationMode::Live {
+            stale_after: Duration::from_secs(10),
+        };
+        assert_eq!(
+            CompensationState::of(&CompensationMode::Default, None),
+            CompensationState::Default
+        );
+        assert_eq!(
+            CompensationState::of(&live, Some((0x8000, 0x6666))),
+            CompensationState::Compensated
+        );
+        assert_eq!(CompensationState::of(&live, None), CompensationState::Stale);
+    }
+
+    #[test]
+    fn outliers_need_a_previous_sample_and_a_large_jump() {
+        assert!(!is_outlier(None, 30_000));
+        assert!(!is_outlier(Some(30_000), 30_400));
+        assert!(is_outlier(Some(30_000), 20_000));
+    }
+}
@@ -0,0 +1,105 @@
+//! Tests for the JSON form of a published reading.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::{assert, assert_eq};
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::baseline::BaselineDelta;
+    use esp_sgp41_voc_nox::compensation::CompensationState;
+    use esp_sgp41_voc_nox::readings::{Measurement, MEASUREMENT_JSON_MAX};
+    use esp_sgp41_voc_nox::reporting::set_voc_only_reporting;
+
+    const SAMPLE: Measurement = Measurement {
+        sensor_id: 0,
+        voc_raw: 30079,
+        nox_raw: 17753,
+        voc_index: 100,
+        nox_index: 1,
+        quality: 100,
+        compensation: CompensationState::Compensated,
+        humidity_comp_ticks: Some(0x8000),
+        temp_comp_ticks: Some(0x6666),
+        timestamp_ms: 5000,
+        baseline_delta: None,
+    };
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn json_carries_nox_by_default() {
+        set_voc_only_reporting(false);
+        let mut buf = [0u8; MEASUREMENT_JSON_MAX];
+        let json = SAMPLE.to_json(&mut buf).unwrap();
+        assert!(contains(json, b"\"nox_raw\":17753"));
+        assert!(contains(json, b"\"nox_index\":1"));
+    }
+
+    #[test]
+    fn voc_only_mode_leaves_nox_out_of_json() {
+        set_voc_only_reporting(true);
+        let mut buf = [0u8; MEASUREMENT_JSON_MAX];
+        let json = SAMPLE.to_json(&mut buf).unwrap();
+        assert!(!contains(json, b"nox"));
+        assert!(contains(json, b"\"voc_index\":100"));
+        set_voc_only_reporting(false);
+    }
+
+    #[test]
+    fn json_carries_the_baseline_delta_once_known() {
+        set_voc_only_reporting(false);
+        let mut buf = [0u8; MEASUREMENT_JSON_MAX];
+        assert!(!contains(SAMPLE.to_json(&mut buf).unwrap(), b"baseline_delta"));
+
+        let reading = Measurement {
+            baseline_delta: Some(BaselineDelta { voc: 12, nox: -3 }),
+            ..SAMPLE
+        };
+        let json = reading.to_json(&mut buf).unwrap();
+        assert!(contains(json, b"\"baseline_delta\":{\"voc\":12,\"nox\":-3}"));
+
+        set_voc_only_reporting(true);
+        let json = reading.to_json(&mut buf).unwrap();
+        assert!(contains(json, b"\"baseline_delta\":{\"voc\":12}"));
+        set_voc_only_reporting(false);
+    }
+
+    #[test]
+    fn widest_values_fit() {
+        set_voc_only_reporting(false);
+        let widest = Measurement {
+            sensor_id: u8::MAX,
+            voc_raw: u16::MAX,
+            nox_raw: u16::MAX,
+            voc_index: i32::MIN,
+            nox_index: i32::MIN,
+            quality: u8::MAX,
+            humidity_comp_ticks: Some(u16::MAX),
+            temp_comp_ticks: Some(u16::MAX),
+            timestamp_ms: u64::MAX,
+            baseline_delta: Some(BaselineDelta {
+                voc: i32::MIN,
+                nox: i32::MIN,
+            }),
+            ..SAMPLE
+        };
+        let mut buf = [0u8; MEASUREMENT_JSON_MAX];
+        let json = widest.to_json(&mut buf).unwrap();
+        assert_eq!(json.first(), Some(&b'{'));
+    }
+}
@@ -0,0 +1,136 @@
+//! Tests for the measurement cycle in `sampling.rs` against a mock I²C bus.
+
+#![no_std]
+#![no_main]
+
+mod common;
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use crate::common::frames::{BAD_VOC_CRC_FRAME, GOOD_FRAME};
+    use crate::common::mock_i2c::{MockError, MockI2c};
+    use defmt::{assert, assert_eq};
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::algo::{IndexProcessor, RawThresholdMapper};
+    use esp_sgp41_voc_nox::driver::{Sgp41, Sgp41Error};
+    use esp_sgp41_voc_nox::measurement::MeasurementResult;
+    use esp_sgp41_voc_nox::prepare_default_params;
+    use esp_sgp41_voc_nox::sampling::{measure_or_rest, process_raw};
+
+    /// Records every `process` call so tests can observe what reached the algorithm.
+    struct CountingProcessor {
+        calls: u32,
+    }
+
+    impl IndexProcessor for CountingProcessor {
+        fn process(&mut self, _sraw: i32) -> i32 {
+            self.calls += 1;
+            100
+        }
+    }
+
+    /// One measurement task cycle: measure, and process only on success.
+    async fn cycle(
+        sgp41: &mut Sgp41<MockI2c<'_>>,
+        voc: &mut CountingProcessor,
+        nox: &mut CountingProcessor,
+    ) -> Result<MeasurementResult, Sgp41Error<MockError>> {
+        let (voc_raw, nox_raw) = measure_or_rest(sgp41, prepare_default_params()).await?;
+        Ok(process_raw(voc_raw, nox_raw, voc, nox))
+    }
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    async fn crc_failure_does_not_advance_algorithm() {
+        let reads = [Some(&BAD_VOC_CRC_FRAME[..])];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+        let mut voc = CountingProcessor { calls: 0 };
+        let mut nox = CountingProcessor { calls: 0 };
+
+        let result = cycle(&mut sgp41, &mut voc, &mut nox).await;
+
+        assert_eq!(result, Err(Sgp41Error::CrcMismatch { expected: 0x1B, got: 0x00 }));
+        assert_eq!(voc.calls, 0);
+        assert_eq!(nox.calls, 0);
+    }
+
+    #[test]
+    async fn valid_frame_advances_algorithm_once() {
+        let reads = [Some(&GOOD_FRAME[..])];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+        let mut voc = CountingProcessor { calls: 0 };
+        let mut nox = CountingProcessor { calls: 0 };
+
+        let result = cycle(&mut sgp41, &mut voc, &mut nox).await;
+
+        assert!(result.is_ok());
+        assert_eq!(voc.calls, 1);
+        assert_eq!(nox.calls, 1);
+    }
+
+    #[test]
+    async fn bus_failure_does_not_advance_algorithm() {
+        let reads = [None];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+        let mut voc = CountingProcessor { calls: 0 };
+        let mut nox = CountingProcessor { calls: 0 };
+
+        let result = cycle(&mut sgp41, &mut voc, &mut nox).await;
+
+        assert_eq!(result, Err(Sgp41Error::I2c(MockError)));
+        assert_eq!(voc.calls + nox.calls, 0);
+    }
+
+    #[test]
+    async fn transient_bus_error_loses_exactly_one_sample() {
+        // The first read fails, every later one succeeds.
+        let reads = [None, Some(&GOOD_FRAME[..]), Some(&GOOD_FRAME[..])];
+        let mut sgp41 = Sgp41::new(MockI2c::new(&reads));
+        let mut voc = CountingProcessor { calls: 0 };
+        let mut nox = CountingProcessor { calls: 0 };
+        let mut lost = 0;
+        let mut samples = 0;
+
+        // Three task cycles: an error skips the sample, the next cycle retries.
+        for _ in 0..3 {
+            match measure_or_rest(&mut sgp41, prepare_default_params()).await {
+                Ok((voc_raw, nox_raw)) => {
+                    let result = process_raw(voc_raw, nox_raw, &mut voc, &mut nox);
+                    assert_eq!((result.voc_raw, result.nox_raw), (0x757F, 0x4559));
+                    samples += 1;
+                }
+                Err(e) => {
+                    assert_eq!(e, Sgp41Error::I2c(MockError));
+                    assert_eq!(samples, 0);
+                    lost += 1;
+                }
+            }
+        }
+
+        assert_eq!(lost, 1);
+        assert_eq!(samples, 2);
+        assert_eq!(voc.calls, 2);
+        assert_eq!(sgp41.release().reads_consumed(), 3);
+    }
+
+    #[test]
+    fn raw_threshold_mapper_levels() {
+        let mut mapper = RawThresholdMapper::new([32_000, 30_000, 28_000, 26_000]);
+
+        assert_eq!(mapper.process(33_000), 1);
+        assert_eq!(mapper.process(32_000), 1);
+        assert_eq!(mapper.process(29_000), 3);
+        assert_eq!(mapper.process(26_000), 4);
+        assert_eq!(mapper.process(20_000), 5);
+    }
+}
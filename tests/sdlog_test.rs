@@ -0,0 +1,83 @@
+//! Tests for SD log file naming and rotation.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::{assert, assert_eq};
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::sdlog::{utc_day, LogFile, Rotation, MAX_PART};
+
+    // 2025-10-16, the last millisecond of the day.
+    const OCT_16_END_MS: u64 = 1_760_659_199_999;
+    const OCT_16: u32 = 20_377;
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn names_follow_the_utc_date() {
+        assert_eq!(utc_day(OCT_16_END_MS), OCT_16);
+        assert_eq!(utc_day(OCT_16_END_MS + 1), OCT_16 + 1);
+        assert_eq!(&LogFile::Dated { day: OCT_16, part: 1 }.name(), b"25101601.CSV");
+        // Leap day
+        assert_eq!(&LogFile::Dated { day: 19_782, part: 0 }.name(), b"24022900.CSV");
+        assert_eq!(&LogFile::Undated(42).name(), b"LOG00042.CSV");
+    }
+
+    #[test]
+    fn names_parse_back() {
+        let dated = LogFile::Dated { day: OCT_16, part: 7 };
+        assert_eq!(LogFile::parse(&dated.name()[..8], b"CSV"), Some(dated));
+        assert_eq!(LogFile::parse(b"LOG00042", b"CSV"), Some(LogFile::Undated(42)));
+        assert_eq!(LogFile::parse(b"LOG00042", b"TXT"), None);
+        assert_eq!(LogFile::parse(b"25131600", b"CSV"), None);
+        assert_eq!(LogFile::parse(b"README", b"CSV"), None);
+    }
+
+    #[test]
+    fn undated_logs_sort_before_dated_ones() {
+        assert!(LogFile::Undated(99_999) < LogFile::Dated { day: 0, part: 0 });
+        assert!(LogFile::Dated { day: OCT_16, part: 9 } < LogFile::Dated { day: OCT_16 + 1, part: 0 });
+    }
+
+    #[test]
+    fn undated_rows_start_a_new_numbered_file_and_rotate_by_size() {
+        let mut rotation = Rotation::resume(Some(4), Some(LogFile::Dated { day: OCT_16, part: 0 }));
+        assert_eq!(rotation.file_for(None, 100), (LogFile::Undated(5), true));
+        rotation.wrote(99);
+        assert_eq!(rotation.file_for(None, 100), (LogFile::Undated(5), false));
+        rotation.wrote(100);
+        assert_eq!(rotation.file_for(None, 100), (LogFile::Undated(6), true));
+    }
+
+    #[test]
+    fn dated_rows_resume_the_day_and_rotate_by_size_and_date() {
+        let mut rotation = Rotation::resume(None, Some(LogFile::Dated { day: OCT_16, part: 2 }));
+        assert_eq!(rotation.file_for(None, 100), (LogFile::Undated(0), true));
+        // Clock set: back to the day's newest part.
+        assert_eq!(rotation.file_for(Some(OCT_16), 100), (LogFile::Dated { day: OCT_16, part: 2 }, true));
+        rotation.wrote(100);
+        assert_eq!(rotation.file_for(Some(OCT_16), 100), (LogFile::Dated { day: OCT_16, part: 3 }, true));
+        rotation.wrote(10);
+        assert_eq!(rotation.file_for(Some(OCT_16 + 1), 100), (LogFile::Dated { day: OCT_16 + 1, part: 0 }, true));
+    }
+
+    #[test]
+    fn last_part_keeps_growing() {
+        let last = LogFile::Dated { day: OCT_16, part: MAX_PART };
+        let mut rotation = Rotation::resume(None, Some(last));
+        assert_eq!(rotation.file_for(Some(OCT_16), 100), (last, true));
+        rotation.wrote(1000);
+        assert_eq!(rotation.file_for(Some(OCT_16), 100), (last, false));
+    }
+}
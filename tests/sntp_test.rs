@@ -0,0 +1,59 @@
+//! Tests for SNTP request and reply decoding.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::assert_eq;
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::sntp::{request, unix_ms_from_reply, PACKET_LEN};
+
+    // Server reply (version 4, mode 4, stratum 2) whose transmit timestamp is
+    // 2023-11-14 22:13:20.5 UTC: Unix 1_700_000_000 s plus half a second.
+    fn reply() -> [u8; PACKET_LEN] {
+        let mut packet = [0u8; PACKET_LEN];
+        packet[0] = 4 << 3 | 4;
+        packet[1] = 2;
+        packet[40..44].copy_from_slice(&(1_700_000_000u32 + 2_208_988_800).to_be_bytes());
+        packet[44..48].copy_from_slice(&0x8000_0000u32.to_be_bytes());
+        packet
+    }
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn request_is_a_version_4_client_packet() {
+        let packet = request();
+        assert_eq!(packet[0], 0x23);
+        assert_eq!(packet[1..], [0u8; PACKET_LEN - 1]);
+    }
+
+    #[test]
+    fn reply_decodes_to_unix_ms() {
+        assert_eq!(unix_ms_from_reply(&reply()), Some(1_700_000_000_500));
+    }
+
+    #[test]
+    fn rejects_unusable_replies() {
+        assert_eq!(unix_ms_from_reply(&reply()[..PACKET_LEN - 1]), None);
+        let mut kiss = reply();
+        kiss[1] = 0;
+        assert_eq!(unix_ms_from_reply(&kiss), None);
+        let mut unsynchronized = reply();
+        unsynchronized[0] |= 3 << 6;
+        assert_eq!(unix_ms_from_reply(&unsynchronized), None);
+        let mut client = reply();
+        client[0] = 4 << 3 | 3;
+        assert_eq!(unix_ms_from_reply(&client), None);
+    }
+}
@@ -0,0 +1,67 @@
+//! Tests pinning the binary wire format in `wire.rs`.
+
+#![no_std]
+#![no_main]
+
+#[cfg(test)]
+#[embedded_test::tests(executor = esp_hal_embassy::Executor::new())]
+mod tests {
+    use defmt::assert_eq;
+    use esp_hal::timer::systimer::SystemTimer;
+    use esp_sgp41_voc_nox::calculate_crc;
+    use esp_sgp41_voc_nox::wire::{WireError, WireReading, WIRE_LEN};
+
+    const READING: WireReading = WireReading {
+        flags: 0b0111,
+        serial_low: 0xA3F2,
+        timestamp_s: 0x0102_0304,
+        voc_raw: 0x757F,
+        nox_raw: 0x4559,
+        voc_index: 0x0064,
+        nox_index: 0x0001,
+    };
+
+    #[init]
+    fn init() {
+        let peripherals = esp_hal::init(esp_hal::Config::default());
+
+        let timer0 = SystemTimer::new(peripherals.SYSTIMER);
+        esp_hal_embassy::init(timer0.alarm0);
+
+        rtt_target::rtt_init_defmt!();
+    }
+
+    #[test]
+    fn layout_is_pinned() {
+        let bytes = READING.encode();
+        assert_eq!(bytes.len(), WIRE_LEN);
+        assert_eq!(bytes[0], 0x17); // version 1, flags 0b0111
+        assert_eq!(bytes[1..3], [0xA3, 0xF2]);
+        assert_eq!(bytes[3..7], [0x01, 0x02, 0x03, 0x04]);
+        assert_eq!(bytes[7..9], [0x75, 0x7F]);
+        assert_eq!(bytes[9..11], [0x45, 0x59]);
+        assert_eq!(bytes[11..13], [0x00, 0x64]);
+        assert_eq!(bytes[13..15], [0x00, 0x01]);
+        assert_eq!(bytes[15], calculate_crc(&bytes[..15]));
+    }
+
+    #[test]
+    fn round_trips() {
+        assert_eq!(WireReading::decode(&READING.encode()), Ok(READING));
+    }
+
+    #[test]
+    fn corruption_is_rejected() {
+        let mut bytes = READING.encode();
+        bytes[8] ^= 0x10;
+        assert_eq!(WireReading::decode(&bytes), Err(WireError::BadCrc));
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let mut bytes = READING.encode();
+        bytes[0] = 0x27;
+        bytes[15] = calculate_crc(&bytes[..15]);
+        assert_eq!(WireReading::decode(&bytes), Err(WireError::UnsupportedVersion(2)));
+    }
+}